@@ -0,0 +1,49 @@
+use ini::Properties;
+
+use super::ConfigError;
+
+/// A command listed in `[autostart]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutostartCommand {
+    pub command: String,
+    /// Start the command again whenever it exits
+    pub respawn: bool,
+}
+
+/// Parse the `[autostart]` section.
+///
+/// Every `exec` line is started once, every `respawn` line is kept running.
+pub fn autostart_from_section(
+    section: Option<&Properties>,
+    errors: &mut Vec<ConfigError>,
+) -> Vec<AutostartCommand> {
+    let Some(section) = section else {
+        return Vec::new();
+    };
+
+    let mut commands = Vec::new();
+    for (key, value) in section.iter() {
+        let respawn = match key {
+            "exec" => false,
+            "respawn" => true,
+            _ => {
+                errors.push(ConfigError::invalid(
+                    "autostart",
+                    key,
+                    "unknown key, expected exec or respawn",
+                ));
+                continue;
+            }
+        };
+        let command = value.trim();
+        if command.is_empty() {
+            errors.push(ConfigError::invalid("autostart", key, "empty command"));
+            continue;
+        }
+        commands.push(AutostartCommand {
+            command: command.to_string(),
+            respawn,
+        });
+    }
+    commands
+}
@@ -0,0 +1,334 @@
+use ini::Properties;
+
+use super::{split_list, ConfigError};
+
+/// A button of the server-side header bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationButton {
+    Close,
+    Maximize,
+    Minimize,
+}
+
+/// Side of the header bar the buttons are placed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonSide {
+    Left,
+    Right,
+}
+
+/// Colors of the header bar in one focus state, as RGBA
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecorationColors {
+    pub background: [f32; 4],
+    pub close: [f32; 4],
+    pub close_hover: [f32; 4],
+    pub maximize: [f32; 4],
+    pub maximize_hover: [f32; 4],
+    pub minimize: [f32; 4],
+    pub minimize_hover: [f32; 4],
+    /// Tabs of a window group other than the shown one
+    pub inactive_tab: [f32; 4],
+}
+
+/// Settings of the `[decorations]` section, the theme of server-side
+/// decorations
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecorationTheme {
+    /// Height of the header bar in logical pixels
+    pub height: i32,
+    pub button_width: i32,
+    /// Buttons from left to right
+    pub buttons: Vec<DecorationButton>,
+    pub button_side: ButtonSide,
+    pub focused: DecorationColors,
+    pub unfocused: DecorationColors,
+}
+
+impl Default for DecorationTheme {
+    fn default() -> Self {
+        DecorationTheme {
+            height: 32,
+            button_width: 32,
+            buttons: vec![
+                DecorationButton::Minimize,
+                DecorationButton::Maximize,
+                DecorationButton::Close,
+            ],
+            button_side: ButtonSide::Right,
+            focused: DecorationColors {
+                background: [0.75, 0.9, 0.78, 1.0],
+                close: [1.0, 0.66, 0.612, 1.0],
+                close_hover: [0.75, 0.11, 0.016, 1.0],
+                maximize: [1.0, 0.965, 0.71, 1.0],
+                maximize_hover: [0.71, 0.624, 0.0, 1.0],
+                minimize: [0.71, 0.85, 1.0, 1.0],
+                minimize_hover: [0.0, 0.42, 0.75, 1.0],
+                inactive_tab: [0.6, 0.72, 0.62, 1.0],
+            },
+            unfocused: DecorationColors {
+                background: [0.85, 0.85, 0.85, 1.0],
+                close: [0.9, 0.78, 0.76, 1.0],
+                close_hover: [0.75, 0.11, 0.016, 1.0],
+                maximize: [0.92, 0.9, 0.8, 1.0],
+                maximize_hover: [0.71, 0.624, 0.0, 1.0],
+                minimize: [0.8, 0.86, 0.92, 1.0],
+                minimize_hover: [0.0, 0.42, 0.75, 1.0],
+                inactive_tab: [0.7, 0.7, 0.7, 1.0],
+            },
+        }
+    }
+}
+
+const COLOR_KEYS: &[&str] = &[
+    "background",
+    "close",
+    "close_hover",
+    "maximize",
+    "maximize_hover",
+    "minimize",
+    "minimize_hover",
+    "inactive_tab",
+];
+
+impl DecorationTheme {
+    /// Parse the `[decorations]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut theme = DecorationTheme::default();
+        let Some(section) = section else {
+            return theme;
+        };
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "height" => parse_size(value).map(|v| theme.height = v),
+                "button_width" => parse_size(value).map(|v| theme.button_width = v),
+                "button_order" => split_list(value)
+                    .iter()
+                    .map(|name| match name.as_str() {
+                        "close" => Ok(DecorationButton::Close),
+                        "maximize" => Ok(DecorationButton::Maximize),
+                        "minimize" => Ok(DecorationButton::Minimize),
+                        _ => Err(format!(
+                            "unknown button \"{}\", expected close, maximize or minimize",
+                            name
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|buttons| theme.buttons = buttons),
+                "button_side" => match value.trim() {
+                    "left" => Ok(ButtonSide::Left),
+                    "right" => Ok(ButtonSide::Right),
+                    _ => Err(format!("expected left or right, got \"{}\"", value)),
+                }
+                .map(|side| theme.button_side = side),
+                _ => {
+                    let (colors, name) = match key.strip_prefix("unfocused_") {
+                        Some(name) => (&mut theme.unfocused, name),
+                        None => (&mut theme.focused, key),
+                    };
+                    match colors.field_mut(name) {
+                        Some(color) => parse_color(value).map(|v| *color = v),
+                        None => Err(format!(
+                            "unknown key, expected height, button_width, button_order, button_side or one of {} with an optional unfocused_ prefix",
+                            COLOR_KEYS.join(", ")
+                        )),
+                    }
+                }
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid("decorations", key, message));
+            }
+        }
+
+        theme
+    }
+
+    /// Horizontal offset of the button at `index` within a header bar that
+    /// is `width` wide
+    pub fn button_offset(&self, index: usize, width: i32) -> i32 {
+        let start = match self.button_side {
+            ButtonSide::Left => 0,
+            ButtonSide::Right => width - self.buttons.len() as i32 * self.button_width,
+        };
+        start + index as i32 * self.button_width
+    }
+
+    /// Index of the button at horizontal offset `x` of a header bar that is
+    /// `width` wide
+    pub fn button_index_at(&self, x: f64, width: i32) -> Option<usize> {
+        (0..self.buttons.len()).find(|index| {
+            let start = self.button_offset(*index, width) as f64;
+            x >= start && x < start + self.button_width as f64
+        })
+    }
+
+    /// Horizontal span of a header bar that is `width` wide left free by the
+    /// buttons, where the tabs of a window group go
+    pub fn tab_span(&self, width: i32) -> (i32, i32) {
+        let buttons = self.buttons.len() as i32 * self.button_width;
+        match self.button_side {
+            ButtonSide::Left => (buttons, width),
+            ButtonSide::Right => (0, width - buttons),
+        }
+    }
+}
+
+impl DecorationColors {
+    fn field_mut(&mut self, name: &str) -> Option<&mut [f32; 4]> {
+        Some(match name {
+            "background" => &mut self.background,
+            "close" => &mut self.close,
+            "close_hover" => &mut self.close_hover,
+            "maximize" => &mut self.maximize,
+            "maximize_hover" => &mut self.maximize_hover,
+            "minimize" => &mut self.minimize,
+            "minimize_hover" => &mut self.minimize_hover,
+            "inactive_tab" => &mut self.inactive_tab,
+            _ => return None,
+        })
+    }
+
+    /// Color of `button`, depending on whether the pointer is over it
+    pub fn button(&self, button: DecorationButton, hovered: bool) -> [f32; 4] {
+        match (button, hovered) {
+            (DecorationButton::Close, false) => self.close,
+            (DecorationButton::Close, true) => self.close_hover,
+            (DecorationButton::Maximize, false) => self.maximize,
+            (DecorationButton::Maximize, true) => self.maximize_hover,
+            (DecorationButton::Minimize, false) => self.minimize,
+            (DecorationButton::Minimize, true) => self.minimize_hover,
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Result<i32, String> {
+    match value.trim().parse::<i32>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("expected a positive number, got \"{}\"", value)),
+    }
+}
+
+/// Parse `#rrggbb` or `#rrggbbaa`
+pub(super) fn parse_color(value: &str) -> Result<[f32; 4], String> {
+    let error = || format!("expected a color like #rrggbb or #rrggbbaa, got \"{}\"", value);
+    let hex = value.trim().strip_prefix('#').ok_or_else(error)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(error());
+    }
+
+    let mut color = [1.0; 4];
+    for (index, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        let byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| error())?;
+        *channel = byte as f32 / 255.0;
+    }
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(text: &str) -> (DecorationTheme, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let theme = DecorationTheme::from_section(ini.section(Some("decorations")), &mut errors);
+        (theme, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn missing_section_is_default() {
+        let (theme, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(theme, DecorationTheme::default());
+        assert_eq!((theme.height, theme.button_width), (32, 32));
+        assert_eq!(theme.button_side, ButtonSide::Right);
+    }
+
+    #[test]
+    fn valid_values() {
+        let (theme, errors) = parse(
+            "[decorations]\nheight = 24\nbutton_width = 20\nbutton_order = close, minimize\nbutton_side = left\n\
+             background = #000000\nunfocused_close_hover = #ffffff80\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(theme.height, 24);
+        assert_eq!(theme.button_width, 20);
+        assert_eq!(
+            theme.buttons,
+            [DecorationButton::Close, DecorationButton::Minimize]
+        );
+        assert_eq!(theme.button_side, ButtonSide::Left);
+        assert_eq!(theme.focused.background, [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(theme.unfocused.close_hover, [1.0, 1.0, 1.0, 128.0 / 255.0]);
+        // the prefix only changes the unfocused colors
+        assert_eq!(
+            theme.focused.close_hover,
+            DecorationTheme::default().focused.close_hover
+        );
+        assert_eq!(
+            theme.unfocused.background,
+            DecorationTheme::default().unfocused.background
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (theme, errors) = parse(
+            "[decorations]\nheight = 0\nbutton_width = big\nbutton_order = close, shade\nbutton_side = top\n\
+             close = red\nunfocused_title = #000000\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[decorations] height: expected a positive number, got \"0\"",
+                "[decorations] button_width: expected a positive number, got \"big\"",
+                "[decorations] button_order: unknown button \"shade\", expected close, maximize or minimize",
+                "[decorations] button_side: expected left or right, got \"top\"",
+                "[decorations] close: expected a color like #rrggbb or #rrggbbaa, got \"red\"",
+                "[decorations] unfocused_title: unknown key, expected height, button_width, button_order, button_side or one of background, close, close_hover, maximize, maximize_hover, minimize, minimize_hover, inactive_tab with an optional unfocused_ prefix",
+            ]
+        );
+        assert_eq!(theme, DecorationTheme::default());
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#ff0000"), Ok([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_color(" #00FF0000 "), Ok([0.0, 1.0, 0.0, 0.0]));
+        for invalid in ["ff0000", "#ff00", "#ff00000", "#gg0000", "#ffé000"] {
+            assert_eq!(
+                parse_color(invalid),
+                Err(format!(
+                    "expected a color like #rrggbb or #rrggbbaa, got \"{}\"",
+                    invalid
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn buttons_on_the_right() {
+        let theme = DecorationTheme::default();
+        assert_eq!(theme.button_offset(0, 300), 204);
+        assert_eq!(theme.button_offset(2, 300), 268);
+        assert_eq!(theme.button_index_at(203.0, 300), None);
+        assert_eq!(theme.button_index_at(204.0, 300), Some(0));
+        assert_eq!(theme.button_index_at(299.5, 300), Some(2));
+        assert_eq!(theme.tab_span(300), (0, 204));
+    }
+
+    #[test]
+    fn buttons_on_the_left() {
+        let theme = DecorationTheme {
+            button_side: ButtonSide::Left,
+            ..DecorationTheme::default()
+        };
+        assert_eq!(theme.button_offset(1, 300), 32);
+        assert_eq!(theme.button_index_at(95.0, 300), Some(2));
+        assert_eq!(theme.button_index_at(96.0, 300), None);
+        assert_eq!(theme.tab_span(300), (96, 300));
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use ini::Properties;
+
+use super::{keybindings::parse_bindings, split_list, ConfigError, KeyCombo};
+
+/// A program started by the compositor.
+///
+/// The command line is run through `sh -c`, so it can contain arguments,
+/// quoting and anything else the shell understands.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpawnCommand {
+    pub command: String,
+    /// Environment variables set in addition to the compositor's own
+    pub env: Vec<(String, String)>,
+    /// Working directory, the compositor's own if unset
+    pub cwd: Option<PathBuf>,
+}
+
+impl SpawnCommand {
+    /// A command line without extra environment or working directory
+    pub fn shell(command: impl Into<String>) -> Self {
+        SpawnCommand {
+            command: command.into(),
+            env: Vec::new(),
+            cwd: None,
+        }
+    }
+}
+
+const EXEC_KEYS: &[&str] = &["binding", "command", "env", "cwd"];
+
+/// Parse the section named `[exec.<name>]` into the key combinations it is
+/// bound to and the command to start.
+///
+/// Returns `None` if the section is unusable, after reporting why in `errors`.
+pub fn exec_from_section(
+    name: &str,
+    section: &Properties,
+    errors: &mut Vec<ConfigError>,
+) -> Option<(Vec<KeyCombo>, SpawnCommand)> {
+    let section_name = format!("exec.{}", name);
+    let mut combos = None;
+    let mut spawn = SpawnCommand::default();
+
+    for (key, value) in section.iter() {
+        let result = match key {
+            "binding" => parse_bindings(value).and_then(|bindings| {
+                if bindings.iter().any(|(index, _)| *index > 0) {
+                    return Err("ranges are not supported for commands".into());
+                }
+                combos = Some(bindings.into_iter().map(|(_, combo)| combo).collect());
+                Ok(())
+            }),
+            "command" => {
+                spawn.command = value.trim().to_string();
+                Ok(())
+            }
+            "env" => split_list(value)
+                .into_iter()
+                .map(|item| match item.split_once('=') {
+                    Some((name, value)) if !name.trim().is_empty() => {
+                        Ok((name.trim().to_string(), value.trim().to_string()))
+                    }
+                    _ => Err(format!("expected NAME=value, got \"{}\"", item)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|env| spawn.env.extend(env)),
+            "cwd" => {
+                spawn.cwd = Some(expand_home(value.trim()));
+                Ok(())
+            }
+            _ => Err(format!("unknown key, expected one of {}", EXEC_KEYS.join(", "))),
+        };
+        if let Err(message) = result {
+            errors.push(ConfigError::invalid(&section_name, key, message));
+        }
+    }
+
+    if spawn.command.is_empty() {
+        errors.push(ConfigError::invalid(&section_name, "command", "missing command"));
+        return None;
+    }
+    let Some(combos) = combos else {
+        errors.push(ConfigError::invalid(&section_name, "binding", "missing binding"));
+        return None;
+    };
+    Some((combos, spawn))
+}
+
+/// Parse an `exec = "<binding>", "<command>"` line of `[shortcuts]`
+pub(super) fn parse_exec_shortcut(value: &str) -> Result<(KeyCombo, SpawnCommand), String> {
+    match split_list(value).as_slice() {
+        [binding, command] => Ok((KeyCombo::parse(binding)?, SpawnCommand::shell(command.as_str()))),
+        _ => Err(format!(
+            "expected a binding and a command such as \"Logo+d\", \"fuzzel\", got {}",
+            value
+        )),
+    }
+}
+
+/// Replace a leading `~` with the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
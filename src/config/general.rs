@@ -0,0 +1,79 @@
+use ini::Properties;
+
+use super::ConfigError;
+
+/// Where new floating windows are opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// In the middle of the output
+    Centered,
+    /// Below and right of the topmost window of the output
+    Cascade,
+    /// Centered on the pointer
+    UnderPointer,
+    /// Where the window covers the least of the other windows
+    LeastOverlap,
+}
+
+impl Placement {
+    fn parse(value: &str) -> Result<Placement, String> {
+        match value.trim() {
+            "centered" => Ok(Placement::Centered),
+            "cascade" => Ok(Placement::Cascade),
+            "under-pointer" => Ok(Placement::UnderPointer),
+            "least-overlap" => Ok(Placement::LeastOverlap),
+            _ => Err(format!(
+                "expected centered, cascade, under-pointer or least-overlap, got \"{}\"",
+                value
+            )),
+        }
+    }
+}
+
+/// Settings of the `[general]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneralConfig {
+    /// Command line started by the `run_terminal` shortcut
+    pub terminal: String,
+    /// Where new floating windows are opened
+    pub placement: Placement,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        GeneralConfig {
+            terminal: "weston-terminal".into(),
+            placement: Placement::LeastOverlap,
+        }
+    }
+}
+
+impl GeneralConfig {
+    /// Parse the `[general]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = GeneralConfig::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            match key {
+                "terminal" if value.trim().is_empty() => {
+                    errors.push(ConfigError::invalid("general", key, "empty command"));
+                }
+                "terminal" => config.terminal = value.trim().to_string(),
+                "placement" => match Placement::parse(value) {
+                    Ok(placement) => config.placement = placement,
+                    Err(message) => errors.push(ConfigError::invalid("general", key, message)),
+                },
+                _ => errors.push(ConfigError::invalid(
+                    "general",
+                    key,
+                    "unknown key, expected terminal or placement",
+                )),
+            }
+        }
+
+        config
+    }
+}
@@ -0,0 +1,282 @@
+use ini::Properties;
+
+use super::{keybindings::named_action, split_list, ConfigError, GeneralConfig, KeyAction};
+
+/// The kinds of touchpad gestures reported by libinput
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureType {
+    Swipe,
+    Pinch,
+    Hold,
+}
+
+/// A completed gesture, including its direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    /// Fingers moved towards each other
+    PinchIn,
+    /// Fingers moved away from each other
+    PinchOut,
+    Hold,
+}
+
+impl Gesture {
+    fn parse(name: &str) -> Result<Gesture, String> {
+        Ok(match name {
+            "swipe_left" => Gesture::SwipeLeft,
+            "swipe_right" => Gesture::SwipeRight,
+            "swipe_up" => Gesture::SwipeUp,
+            "swipe_down" => Gesture::SwipeDown,
+            "pinch_in" => Gesture::PinchIn,
+            "pinch_out" => Gesture::PinchOut,
+            "hold" => Gesture::Hold,
+            _ => {
+                return Err(format!(
+                    "unknown gesture \"{}\", expected swipe_left, swipe_right, swipe_up, swipe_down, pinch_in, pinch_out or hold",
+                    name
+                ))
+            }
+        })
+    }
+
+    pub fn gesture_type(&self) -> GestureType {
+        match self {
+            Gesture::SwipeLeft | Gesture::SwipeRight | Gesture::SwipeUp | Gesture::SwipeDown => {
+                GestureType::Swipe
+            }
+            Gesture::PinchIn | Gesture::PinchOut => GestureType::Pinch,
+            Gesture::Hold => GestureType::Hold,
+        }
+    }
+}
+
+/// A gesture and the number of fingers it has to be made with, e.g. `swipe_left:3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureCombo {
+    pub gesture: Gesture,
+    /// Any number of fingers above the threshold if unset
+    pub fingers: Option<u32>,
+}
+
+impl GestureCombo {
+    /// Parse a combination of the form `<gesture>[:<fingers>]`
+    pub fn parse(text: &str) -> Result<GestureCombo, String> {
+        let (gesture, fingers) = match text.split_once(':') {
+            Some((gesture, fingers)) => match fingers.trim().parse::<u32>() {
+                Ok(fingers) if fingers > 0 => (gesture, Some(fingers)),
+                _ => return Err(format!("invalid finger count in \"{}\"", text)),
+            },
+            None => (text, None),
+        };
+        Ok(GestureCombo {
+            gesture: Gesture::parse(gesture.trim())?,
+            fingers,
+        })
+    }
+}
+
+/// The table of touchpad gesture bindings built from `[gestures]`
+#[derive(Debug, Clone)]
+pub struct GestureBindings {
+    /// Gestures made with fewer fingers always go to the client
+    pub min_fingers: u32,
+    bindings: Vec<(GestureCombo, KeyAction)>,
+}
+
+impl Default for GestureBindings {
+    fn default() -> Self {
+        GestureBindings {
+            min_fingers: 3,
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl GestureBindings {
+    /// Build the table from the `[gestures]` section.
+    ///
+    /// Keys are the action names of `[shortcuts]`, values lists of gestures.
+    /// Nothing is bound by default.
+    pub fn from_section(
+        section: Option<&Properties>,
+        general: &GeneralConfig,
+        errors: &mut Vec<ConfigError>,
+    ) -> Self {
+        let mut config = GestureBindings::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "min_fingers" => match value.trim().parse::<u32>() {
+                    Ok(fingers) if fingers > 0 => {
+                        config.min_fingers = fingers;
+                        Ok(())
+                    }
+                    _ => Err(format!("expected a positive number, got \"{}\"", value)),
+                },
+                _ => match named_action(key, general) {
+                    Some(action) => split_list(value)
+                        .iter()
+                        .map(|item| GestureCombo::parse(item))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|combos| {
+                            config
+                                .bindings
+                                .extend(combos.into_iter().map(|combo| (combo, action.clone())))
+                        }),
+                    None => Err("unknown action".into()),
+                },
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid("gestures", key, message));
+            }
+        }
+
+        config
+    }
+
+    fn bound(&self, fingers: u32) -> impl Iterator<Item = &(GestureCombo, KeyAction)> {
+        self.bindings.iter().filter(move |(combo, _)| {
+            fingers >= self.min_fingers && combo.fingers.map_or(true, |count| count == fingers)
+        })
+    }
+
+    /// Whether a gesture of `gesture_type` made with `fingers` might be bound,
+    /// so has to be held back from the client until its direction is known
+    pub fn consumes(&self, gesture_type: GestureType, fingers: u32) -> bool {
+        self.bound(fingers)
+            .any(|(combo, _)| combo.gesture.gesture_type() == gesture_type)
+    }
+
+    /// The action bound to `gesture` made with `fingers`, preferring bindings
+    /// that name the exact finger count
+    pub fn action_for(&self, gesture: Gesture, fingers: u32) -> Option<KeyAction> {
+        self.bound(fingers)
+            .filter(|(combo, _)| combo.gesture == gesture)
+            .max_by_key(|(combo, _)| combo.fingers.is_some())
+            .map(|(_, action)| action.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(text: &str) -> (GestureBindings, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let bindings = GestureBindings::from_section(
+            ini.section(Some("gestures")),
+            &GeneralConfig::default(),
+            &mut errors,
+        );
+        (bindings, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn missing_section_binds_nothing() {
+        let (bindings, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(bindings.min_fingers, 3);
+        assert!(!bindings.consumes(GestureType::Swipe, 3));
+        assert_eq!(bindings.action_for(Gesture::SwipeLeft, 3), None);
+    }
+
+    #[test]
+    fn combos() {
+        assert_eq!(
+            GestureCombo::parse("pinch_in : 4"),
+            Ok(GestureCombo {
+                gesture: Gesture::PinchIn,
+                fingers: Some(4),
+            })
+        );
+        assert_eq!(
+            GestureCombo::parse("hold"),
+            Ok(GestureCombo {
+                gesture: Gesture::Hold,
+                fingers: None,
+            })
+        );
+    }
+
+    #[test]
+    fn bindings() {
+        let (bindings, errors) = parse(
+            "[gestures]\nscale_up = swipe_left\nscale_down = swipe_right:3\n\
+             toggle_preview = \"swipe_left:4\", hold:4\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(
+            bindings.action_for(Gesture::SwipeLeft, 3),
+            Some(KeyAction::ScaleUp)
+        );
+        // an exact finger count is preferred
+        assert_eq!(
+            bindings.action_for(Gesture::SwipeLeft, 4),
+            Some(KeyAction::TogglePreview)
+        );
+        assert_eq!(
+            bindings.action_for(Gesture::SwipeRight, 3),
+            Some(KeyAction::ScaleDown)
+        );
+        assert_eq!(bindings.action_for(Gesture::SwipeRight, 4), None);
+        assert_eq!(
+            bindings.action_for(Gesture::Hold, 4),
+            Some(KeyAction::TogglePreview)
+        );
+        assert_eq!(bindings.action_for(Gesture::SwipeUp, 3), None);
+    }
+
+    #[test]
+    fn consumes() {
+        let (bindings, _) = parse("[gestures]\nscale_up = swipe_left\ntoggle_preview = pinch_out:4\n");
+        assert!(bindings.consumes(GestureType::Swipe, 3));
+        assert!(bindings.consumes(GestureType::Swipe, 5));
+        assert!(!bindings.consumes(GestureType::Pinch, 3));
+        assert!(bindings.consumes(GestureType::Pinch, 4));
+        assert!(!bindings.consumes(GestureType::Hold, 4));
+    }
+
+    #[test]
+    fn min_fingers() {
+        let (bindings, errors) = parse("[gestures]\nmin_fingers = 4\nscale_up = swipe_left, swipe_up:3\n");
+        assert!(errors.is_empty());
+        assert_eq!(bindings.min_fingers, 4);
+        assert!(!bindings.consumes(GestureType::Swipe, 3));
+        assert_eq!(bindings.action_for(Gesture::SwipeUp, 3), None);
+        assert_eq!(
+            bindings.action_for(Gesture::SwipeLeft, 4),
+            Some(KeyAction::ScaleUp)
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (bindings, errors) = parse(
+            "[gestures]\nmin_fingers = 0\nwarp = swipe_up\nscreen_switch = swipe_up\n\
+             scale_up = swipe_left:0\nscale_down = swipe_left:many\ntoggle_preview = swipe_left, rotate\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[gestures] min_fingers: expected a positive number, got \"0\"",
+                "[gestures] warp: unknown action",
+                "[gestures] screen_switch: unknown action",
+                "[gestures] scale_up: invalid finger count in \"swipe_left:0\"",
+                "[gestures] scale_down: invalid finger count in \"swipe_left:many\"",
+                "[gestures] toggle_preview: unknown gesture \"rotate\", expected swipe_left, swipe_right, swipe_up, swipe_down, pinch_in, pinch_out or hold",
+            ]
+        );
+        assert_eq!(bindings.min_fingers, 3);
+        assert!(!bindings.consumes(GestureType::Swipe, 3));
+    }
+}
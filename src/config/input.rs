@@ -0,0 +1,197 @@
+use ini::Properties;
+
+use super::{parse_bool, ConfigError};
+
+/// Pointer acceleration profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelProfile {
+    Flat,
+    Adaptive,
+}
+
+/// How a touchpad generates scroll events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollMethod {
+    NoScroll,
+    TwoFinger,
+    Edge,
+    OnButtonDown,
+}
+
+/// How a clickpad decides which button was clicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickMethod {
+    ButtonAreas,
+    Clickfinger,
+}
+
+/// Settings of the `[input]` and `[input.<device-name>]` sections.
+///
+/// Unset options leave the libinput default of the device untouched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputConfig {
+    pub tap: Option<bool>,
+    pub natural_scroll: Option<bool>,
+    pub accel_profile: Option<AccelProfile>,
+    pub accel_speed: Option<f64>,
+    pub left_handed: Option<bool>,
+    pub middle_emulation: Option<bool>,
+    pub scroll_method: Option<ScrollMethod>,
+    pub disable_while_typing: Option<bool>,
+    pub click_method: Option<ClickMethod>,
+}
+
+const INPUT_KEYS: &[&str] = &[
+    "tap",
+    "natural_scroll",
+    "accel_profile",
+    "accel_speed",
+    "left_handed",
+    "middle_emulation",
+    "scroll_method",
+    "disable_while_typing",
+    "click_method",
+];
+
+impl InputConfig {
+    /// Parse an input section, reporting invalid values in `errors`
+    pub fn from_section(section_name: &str, section: &Properties, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = InputConfig::default();
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "tap" => parse_bool(value).map(|v| config.tap = Some(v)),
+                "natural_scroll" => parse_bool(value).map(|v| config.natural_scroll = Some(v)),
+                "accel_profile" => match value.trim() {
+                    "flat" => Ok(AccelProfile::Flat),
+                    "adaptive" => Ok(AccelProfile::Adaptive),
+                    _ => Err(format!("expected flat or adaptive, got \"{}\"", value)),
+                }
+                .map(|v| config.accel_profile = Some(v)),
+                "accel_speed" => match value.trim().parse::<f64>() {
+                    Ok(speed) if (-1.0..=1.0).contains(&speed) => {
+                        config.accel_speed = Some(speed);
+                        Ok(())
+                    }
+                    _ => Err(format!("expected a number between -1 and 1, got \"{}\"", value)),
+                },
+                "left_handed" => parse_bool(value).map(|v| config.left_handed = Some(v)),
+                "middle_emulation" => parse_bool(value).map(|v| config.middle_emulation = Some(v)),
+                "scroll_method" => match value.trim() {
+                    "none" => Ok(ScrollMethod::NoScroll),
+                    "two_finger" => Ok(ScrollMethod::TwoFinger),
+                    "edge" => Ok(ScrollMethod::Edge),
+                    "on_button_down" => Ok(ScrollMethod::OnButtonDown),
+                    _ => Err(format!(
+                        "expected none, two_finger, edge or on_button_down, got \"{}\"",
+                        value
+                    )),
+                }
+                .map(|v| config.scroll_method = Some(v)),
+                "disable_while_typing" => parse_bool(value).map(|v| config.disable_while_typing = Some(v)),
+                "click_method" => match value.trim() {
+                    "button_areas" => Ok(ClickMethod::ButtonAreas),
+                    "clickfinger" => Ok(ClickMethod::Clickfinger),
+                    _ => Err(format!("expected button_areas or clickfinger, got \"{}\"", value)),
+                }
+                .map(|v| config.click_method = Some(v)),
+                _ => Err(format!("unknown key, expected one of {}", INPUT_KEYS.join(", "))),
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid(section_name, key, message));
+            }
+        }
+
+        config
+    }
+
+    /// Combine two sections, options set in `other` taking precedence
+    pub fn merged(&self, other: &InputConfig) -> InputConfig {
+        InputConfig {
+            tap: other.tap.or(self.tap),
+            natural_scroll: other.natural_scroll.or(self.natural_scroll),
+            accel_profile: other.accel_profile.or(self.accel_profile),
+            accel_speed: other.accel_speed.or(self.accel_speed),
+            left_handed: other.left_handed.or(self.left_handed),
+            middle_emulation: other.middle_emulation.or(self.middle_emulation),
+            scroll_method: other.scroll_method.or(self.scroll_method),
+            disable_while_typing: other.disable_while_typing.or(self.disable_while_typing),
+            click_method: other.click_method.or(self.click_method),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(body: &str) -> (InputConfig, Vec<String>) {
+        let ini = Ini::load_from_str(&format!("[input]\n{}", body)).unwrap();
+        let mut errors = Vec::new();
+        let config = InputConfig::from_section("input", ini.section(Some("input")).unwrap(), &mut errors);
+        (config, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn empty_section_leaves_everything_unset() {
+        let (config, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(config, InputConfig::default());
+    }
+
+    #[test]
+    fn valid_values() {
+        let (config, errors) = parse(
+            "tap = yes\nnatural_scroll = 1\naccel_profile = flat\naccel_speed = -0.5\nleft_handed = off\n\
+             middle_emulation = true\nscroll_method = edge\ndisable_while_typing = no\nclick_method = clickfinger\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(
+            config,
+            InputConfig {
+                tap: Some(true),
+                natural_scroll: Some(true),
+                accel_profile: Some(AccelProfile::Flat),
+                accel_speed: Some(-0.5),
+                left_handed: Some(false),
+                middle_emulation: Some(true),
+                scroll_method: Some(ScrollMethod::Edge),
+                disable_while_typing: Some(false),
+                click_method: Some(ClickMethod::Clickfinger),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) = parse(
+            "tap = maybe\naccel_profile = linear\naccel_speed = 2\nscroll_method = three_finger\n\
+             click_method = middle\nsensitivity = 3\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[input] tap: expected true or false, got \"maybe\"",
+                "[input] accel_profile: expected flat or adaptive, got \"linear\"",
+                "[input] accel_speed: expected a number between -1 and 1, got \"2\"",
+                "[input] scroll_method: expected none, two_finger, edge or on_button_down, got \"three_finger\"",
+                "[input] click_method: expected button_areas or clickfinger, got \"middle\"",
+                "[input] sensitivity: unknown key, expected one of tap, natural_scroll, accel_profile, accel_speed, left_handed, middle_emulation, scroll_method, disable_while_typing, click_method",
+            ]
+        );
+        assert_eq!(config, InputConfig::default());
+    }
+
+    #[test]
+    fn merged_prefers_other() {
+        let (general, _) = parse("tap = true\nnatural_scroll = true\n");
+        let (device, _) = parse("natural_scroll = false\naccel_profile = adaptive\n");
+        let merged = general.merged(&device);
+        assert_eq!(merged.tap, Some(true));
+        assert_eq!(merged.natural_scroll, Some(false));
+        assert_eq!(merged.accel_profile, Some(AccelProfile::Adaptive));
+        assert_eq!(merged.click_method, None);
+    }
+}
@@ -0,0 +1,769 @@
+use ini::Properties;
+use smithay::input::keyboard::{Keysym, ModifiersState};
+use xkbcommon::xkb;
+
+use super::{
+    exec::{parse_exec_shortcut, SpawnCommand},
+    split_list, ConfigError, GeneralConfig,
+};
+
+bitflags::bitflags! {
+    /// Modifiers that can be part of a key binding
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Modifiers: u8 {
+        const CTRL = 1;
+        const ALT = 1 << 1;
+        const SHIFT = 1 << 2;
+        const LOGO = 1 << 3;
+    }
+}
+
+impl From<&ModifiersState> for Modifiers {
+    fn from(state: &ModifiersState) -> Self {
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::CTRL, state.ctrl);
+        modifiers.set(Modifiers::ALT, state.alt);
+        modifiers.set(Modifiers::SHIFT, state.shift);
+        modifiers.set(Modifiers::LOGO, state.logo);
+        modifiers
+    }
+}
+
+/// A direction on screen, for actions picking a window or output by geometry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Possible results of a keyboard action
+#[allow(dead_code)] // some of these are only read if udev is enabled
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyAction {
+    /// Quit the compositor
+    Quit,
+    /// Trigger a vt-switch
+    VtSwitch(i32),
+    /// run a command
+    Run(SpawnCommand),
+    /// Switch the current screen
+    Screen(usize),
+    ScaleUp,
+    ScaleDown,
+    TogglePreview,
+    RotateOutput,
+    ToggleTint,
+    ToggleDecorations,
+    /// Switch to the next keyboard layout group
+    NextLayout,
+    /// Switch to the previous keyboard layout group
+    PrevLayout,
+    /// Switch to the named binding mode
+    EnterMode(String),
+    /// Return to the default binding mode
+    ExitMode,
+    /// Grow or shrink the focused window by the given width and height
+    ResizeWindow(i32, i32),
+    /// Show the workspace at the given index of `[workspaces] names`
+    SwitchWorkspace(usize),
+    /// Move the focused window to the workspace at the given index
+    MoveToWorkspace(usize),
+    /// Show the next workspace of the current output
+    NextWorkspace,
+    /// Show the previous workspace of the current output
+    PrevWorkspace,
+    /// Move the visible workspace of the current output to the next output
+    WorkspaceToNextOutput,
+    /// Take the focused window out of the tiling layout or put it back
+    ToggleFloating,
+    /// Switch the workspace of the current output to the next layout
+    CycleLayout,
+    /// Swap the focused tile with the one the given number of places after it
+    SwapTile(i32),
+    /// Make the focused tile the master tile
+    PromoteTile,
+    /// Change the share of the master tile by the given fraction, or the
+    /// width of the focused column in the scrolling layout
+    ResizeMaster(f64),
+    /// Focus the column the given number of places to the right
+    FocusColumn(i32),
+    /// Move the focused column the given number of places to the right
+    MoveColumn(i32),
+    /// Stack the focused window onto the column to its left
+    ConsumeWindow,
+    /// Take the focused window out of its column into a new one
+    ExpelWindow,
+    /// Focus the nearest window in the given direction
+    FocusDirection(Direction),
+    /// Swap the focused window with the nearest one in the given direction
+    SwapDirection(Direction),
+    /// Focus the nearest output in the given direction
+    FocusOutput(Direction),
+    /// Open the window switcher or select the next window in it, only going
+    /// through the windows of the focused app if true
+    SwitchWindow(bool),
+    /// Minimize the focused window
+    MinimizeWindow,
+    /// Restore the most recently minimized window
+    RestoreWindow,
+    /// Hide the focused window in the scratchpad
+    MoveToScratchpad,
+    /// Show the next scratchpad window on the current output or hide the
+    /// shown one, only going through the windows of the named rule if given
+    ToggleScratchpad(Option<String>),
+    /// Keep the focused window above the others, or stop doing so
+    ToggleAlwaysOnTop,
+    /// Keep the focused window below the others, or stop doing so
+    ToggleAlwaysBelow,
+    /// Show the focused window on every workspace of its output, or stop doing so
+    ToggleSticky,
+    /// Add the focused window to the group of the nearest window in the given
+    /// direction, as its shown tab
+    GroupWindow(Direction),
+    /// Take the focused window out of its group
+    UngroupWindow,
+    /// Show the next tab of the group of the focused window, or a previous one
+    /// if negative
+    FocusTab(i32),
+    /// Switch the group of the focused window between tabs side by side and
+    /// stacked title bars
+    ToggleGroupLayout,
+    /// Do nothing more
+    None,
+}
+
+/// A single key combination, e.g. `Logo+Shift+M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub modifiers: Modifiers,
+    pub keysym: Keysym,
+}
+
+impl KeyCombo {
+    /// Parse a combination of the form `Mod+Mod+Keysym`
+    pub fn parse(text: &str) -> Result<KeyCombo, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        // allow binding the plus key itself, as in "Logo++"
+        if text.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("plus");
+        }
+        let key = parts.pop().filter(|k| !k.is_empty()).ok_or("missing key name")?;
+
+        Ok(KeyCombo {
+            modifiers: parse_modifiers(&parts, text)?,
+            keysym: parse_keysym(key)?,
+        })
+    }
+
+    /// Whether the combination is held, given the active modifiers and keysym.
+    ///
+    /// Modifiers not named in the combination are allowed to be held, so that
+    /// e.g. `XF86Switch_VT_1` still triggers while Ctrl+Alt are pressed.
+    fn matches(&self, modifiers: Modifiers, keysym: Keysym) -> bool {
+        self.keysym == to_lowercase(keysym) && modifiers.contains(self.modifiers)
+    }
+}
+
+/// Parse the modifier names of the combination `text`
+pub(super) fn parse_modifiers(names: &[&str], text: &str) -> Result<Modifiers, String> {
+    let mut modifiers = Modifiers::empty();
+    for name in names {
+        modifiers |= match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CTRL,
+            "alt" | "mod1" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            "logo" | "super" | "mod4" => Modifiers::LOGO,
+            _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", name, text)),
+        };
+    }
+    Ok(modifiers)
+}
+
+fn parse_keysym(name: &str) -> Result<Keysym, String> {
+    let keysym = xkb::keysym_from_name(name, xkb::KEYSYM_NO_FLAGS);
+    if keysym.raw() != xkb::keysyms::KEY_NoSymbol {
+        return Ok(to_lowercase(keysym));
+    }
+    let keysym = xkb::keysym_from_name(name, xkb::KEYSYM_CASE_INSENSITIVE);
+    if keysym.raw() != xkb::keysyms::KEY_NoSymbol {
+        return Ok(to_lowercase(keysym));
+    }
+    Err(format!("unknown key \"{}\"", name))
+}
+
+/// The lowercase keysym of a letter, any other keysym unchanged.
+///
+/// Letters are compared in lowercase, as Shift turns the `a` of `Shift+a`
+/// into the `A` the compositor sees.
+fn to_lowercase(keysym: Keysym) -> Keysym {
+    let Some(c) = char::from_u32(xkb::keysym_to_utf32(keysym)) else {
+        return keysym;
+    };
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) if l != c => xkb::utf32_to_keysym(l as u32),
+        _ => keysym,
+    }
+}
+
+/// Parse a list of bindings, expanding ranges such as `Logo+1..Logo+9`.
+///
+/// Each returned item carries its position inside a range, which indexed
+/// actions like `screen_switch` turn into their argument.
+pub(super) fn parse_bindings(value: &str) -> Result<Vec<(usize, KeyCombo)>, String> {
+    let mut combos = Vec::new();
+    for item in split_list(value) {
+        match item.split_once("..") {
+            Some((start, end)) => {
+                let start = KeyCombo::parse(start)?;
+                let end = KeyCombo::parse(end)?;
+                if start.modifiers != end.modifiers {
+                    return Err(format!(
+                        "range \"{}\" must use the same modifiers on both ends",
+                        item
+                    ));
+                }
+                let (first, last) = (start.keysym.raw(), end.keysym.raw());
+                if first > last {
+                    return Err(format!("range \"{}\" is empty", item));
+                }
+                combos.extend((first..=last).enumerate().map(|(index, raw)| {
+                    (
+                        index,
+                        KeyCombo {
+                            modifiers: start.modifiers,
+                            keysym: Keysym::new(raw),
+                        },
+                    )
+                }));
+            }
+            None => combos.push((0, KeyCombo::parse(&item)?)),
+        }
+    }
+    if combos.is_empty() {
+        return Err("no key binding given".into());
+    }
+    Ok(combos)
+}
+
+/// Names of the actions understood in `[shortcuts]` and their default bindings,
+/// an empty binding meaning the action is unbound by default
+const DEFAULT_SHORTCUTS: &[(&str, &str)] = &[
+    ("quit", r#"Ctrl+Alt+BackSpace, "Logo+q""#),
+    ("vt_switch", "XF86Switch_VT_1..XF86Switch_VT_12"),
+    ("run_terminal", "Logo+Return"),
+    ("screen_switch", "Logo+1..Logo+9"),
+    ("scale_down", "Logo+Shift+M"),
+    ("scale_up", "Logo+Shift+P"),
+    ("toggle_preview", "Logo+Shift+W"),
+    ("rotate_output", "Logo+Shift+R"),
+    ("toggle_tint", "Logo+Shift+T"),
+    ("toggle_decorations", "Logo+Shift+D"),
+    ("next_layout", ""),
+    ("prev_layout", ""),
+    ("exit_mode", ""),
+    ("grow_width", ""),
+    ("shrink_width", ""),
+    ("grow_height", ""),
+    ("shrink_height", ""),
+    ("workspace_switch", ""),
+    ("workspace_move_window", ""),
+    ("workspace_next", ""),
+    ("workspace_prev", ""),
+    ("workspace_to_next_output", ""),
+    ("toggle_floating", ""),
+    ("cycle_layout", ""),
+    ("swap_next", ""),
+    ("swap_prev", ""),
+    ("promote", ""),
+    ("grow_master", ""),
+    ("shrink_master", ""),
+    ("focus_column_left", ""),
+    ("focus_column_right", ""),
+    ("move_column_left", ""),
+    ("move_column_right", ""),
+    ("consume_window", ""),
+    ("expel_window", ""),
+    ("focus_left", ""),
+    ("focus_right", ""),
+    ("focus_up", ""),
+    ("focus_down", ""),
+    ("swap_left", ""),
+    ("swap_right", ""),
+    ("swap_up", ""),
+    ("swap_down", ""),
+    ("focus_output_left", ""),
+    ("focus_output_right", ""),
+    ("focus_output_up", ""),
+    ("focus_output_down", ""),
+    ("switch_windows", r#"Alt+Tab, "Alt+Shift+ISO_Left_Tab""#),
+    ("switch_app_windows", r#"Alt+grave, "Alt+Shift+asciitilde""#),
+    ("minimize_window", ""),
+    ("restore_window", ""),
+    ("move_to_scratchpad", ""),
+    ("toggle_scratchpad", ""),
+    ("toggle_always_on_top", ""),
+    ("toggle_always_below", ""),
+    ("toggle_sticky", ""),
+    ("group_left", ""),
+    ("group_right", ""),
+    ("group_up", ""),
+    ("group_down", ""),
+    ("ungroup_window", ""),
+    ("next_tab", ""),
+    ("previous_tab", ""),
+    ("toggle_group_layout", ""),
+];
+
+/// How much the master tile actions change its share of the width
+const MASTER_STEP: f64 = 0.05;
+
+/// How much the resize actions grow or shrink a window, in logical pixels
+const RESIZE_STEP: i32 = 20;
+
+fn action_for_name(name: &str, index: usize, general: &GeneralConfig) -> KeyAction {
+    match name {
+        "quit" => KeyAction::Quit,
+        "vt_switch" => KeyAction::VtSwitch(index as i32 + 1),
+        "run_terminal" => KeyAction::Run(SpawnCommand::shell(general.terminal.as_str())),
+        "screen_switch" => KeyAction::Screen(index),
+        "scale_down" => KeyAction::ScaleDown,
+        "scale_up" => KeyAction::ScaleUp,
+        "toggle_preview" => KeyAction::TogglePreview,
+        "rotate_output" => KeyAction::RotateOutput,
+        "toggle_tint" => KeyAction::ToggleTint,
+        "toggle_decorations" => KeyAction::ToggleDecorations,
+        "next_layout" => KeyAction::NextLayout,
+        "prev_layout" => KeyAction::PrevLayout,
+        "exit_mode" => KeyAction::ExitMode,
+        "grow_width" => KeyAction::ResizeWindow(RESIZE_STEP, 0),
+        "shrink_width" => KeyAction::ResizeWindow(-RESIZE_STEP, 0),
+        "grow_height" => KeyAction::ResizeWindow(0, RESIZE_STEP),
+        "shrink_height" => KeyAction::ResizeWindow(0, -RESIZE_STEP),
+        "workspace_switch" => KeyAction::SwitchWorkspace(index),
+        "workspace_move_window" => KeyAction::MoveToWorkspace(index),
+        "workspace_next" => KeyAction::NextWorkspace,
+        "workspace_prev" => KeyAction::PrevWorkspace,
+        "workspace_to_next_output" => KeyAction::WorkspaceToNextOutput,
+        "toggle_floating" => KeyAction::ToggleFloating,
+        "cycle_layout" => KeyAction::CycleLayout,
+        "swap_next" => KeyAction::SwapTile(1),
+        "swap_prev" => KeyAction::SwapTile(-1),
+        "promote" => KeyAction::PromoteTile,
+        "grow_master" => KeyAction::ResizeMaster(MASTER_STEP),
+        "shrink_master" => KeyAction::ResizeMaster(-MASTER_STEP),
+        "focus_column_left" => KeyAction::FocusColumn(-1),
+        "focus_column_right" => KeyAction::FocusColumn(1),
+        "move_column_left" => KeyAction::MoveColumn(-1),
+        "move_column_right" => KeyAction::MoveColumn(1),
+        "consume_window" => KeyAction::ConsumeWindow,
+        "expel_window" => KeyAction::ExpelWindow,
+        "focus_left" => KeyAction::FocusDirection(Direction::Left),
+        "focus_right" => KeyAction::FocusDirection(Direction::Right),
+        "focus_up" => KeyAction::FocusDirection(Direction::Up),
+        "focus_down" => KeyAction::FocusDirection(Direction::Down),
+        "swap_left" => KeyAction::SwapDirection(Direction::Left),
+        "swap_right" => KeyAction::SwapDirection(Direction::Right),
+        "swap_up" => KeyAction::SwapDirection(Direction::Up),
+        "swap_down" => KeyAction::SwapDirection(Direction::Down),
+        "focus_output_left" => KeyAction::FocusOutput(Direction::Left),
+        "focus_output_right" => KeyAction::FocusOutput(Direction::Right),
+        "focus_output_up" => KeyAction::FocusOutput(Direction::Up),
+        "focus_output_down" => KeyAction::FocusOutput(Direction::Down),
+        "switch_windows" => KeyAction::SwitchWindow(false),
+        "switch_app_windows" => KeyAction::SwitchWindow(true),
+        "minimize_window" => KeyAction::MinimizeWindow,
+        "restore_window" => KeyAction::RestoreWindow,
+        "move_to_scratchpad" => KeyAction::MoveToScratchpad,
+        "toggle_scratchpad" => KeyAction::ToggleScratchpad(None),
+        "toggle_always_on_top" => KeyAction::ToggleAlwaysOnTop,
+        "toggle_always_below" => KeyAction::ToggleAlwaysBelow,
+        "toggle_sticky" => KeyAction::ToggleSticky,
+        "group_left" => KeyAction::GroupWindow(Direction::Left),
+        "group_right" => KeyAction::GroupWindow(Direction::Right),
+        "group_up" => KeyAction::GroupWindow(Direction::Up),
+        "group_down" => KeyAction::GroupWindow(Direction::Down),
+        "ungroup_window" => KeyAction::UngroupWindow,
+        "next_tab" => KeyAction::FocusTab(1),
+        "previous_tab" => KeyAction::FocusTab(-1),
+        "toggle_group_layout" => KeyAction::ToggleGroupLayout,
+        _ => unreachable!("unknown action {}", name),
+    }
+}
+
+/// Whether an action takes its argument from its position in a range
+fn is_indexed(name: &str) -> bool {
+    matches!(
+        name,
+        "vt_switch" | "screen_switch" | "workspace_switch" | "workspace_move_window"
+    )
+}
+
+/// The action called `name` in `[shortcuts]`, unless it is unknown or takes
+/// its argument from a range
+pub(super) fn named_action(name: &str, general: &GeneralConfig) -> Option<KeyAction> {
+    let known = DEFAULT_SHORTCUTS.iter().any(|(action, _)| *action == name);
+    (known && !is_indexed(name)).then(|| action_for_name(name, 0, general))
+}
+
+/// Parse a `mode = "<binding>", "<mode>"` line
+fn parse_mode_shortcut(value: &str) -> Result<(KeyCombo, String), String> {
+    match split_list(value).as_slice() {
+        [binding, mode] => Ok((KeyCombo::parse(binding)?, mode.clone())),
+        _ => Err(format!(
+            "expected a binding and a mode name such as \"Logo+r\", \"resize\", got {}",
+            value
+        )),
+    }
+}
+
+/// The table of key bindings built from the `[shortcuts]` section, or from
+/// the section of a binding mode
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    bindings: Vec<(KeyCombo, KeyAction)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let mut errors = Vec::new();
+        let bindings = Keybindings::from_section(None, &GeneralConfig::default(), &mut errors);
+        debug_assert!(
+            errors.is_empty(),
+            "Default key bindings are invalid: {:?}",
+            errors
+        );
+        bindings
+    }
+}
+
+impl Keybindings {
+    /// Build the table from the `[shortcuts]` section.
+    ///
+    /// Actions that are not listed keep their default bindings. Invalid
+    /// entries are reported in `errors` and left unbound. Commands bound with
+    /// `exec` lines take precedence over actions bound to the same keys.
+    pub fn from_section(
+        section: Option<&Properties>,
+        general: &GeneralConfig,
+        errors: &mut Vec<ConfigError>,
+    ) -> Self {
+        Keybindings::parse("shortcuts", section, true, general, errors)
+    }
+
+    /// Build the table of the binding mode declared by `[mode.<name>]`.
+    ///
+    /// Unlike `[shortcuts]`, only the actions listed in the section are bound.
+    pub fn mode_from_section(
+        name: &str,
+        section: &Properties,
+        general: &GeneralConfig,
+        errors: &mut Vec<ConfigError>,
+    ) -> Self {
+        Keybindings::parse(&format!("mode.{}", name), Some(section), false, general, errors)
+    }
+
+    fn parse(
+        section_name: &str,
+        section: Option<&Properties>,
+        defaults: bool,
+        general: &GeneralConfig,
+        errors: &mut Vec<ConfigError>,
+    ) -> Self {
+        let mut bindings = Vec::new();
+
+        if let Some(section) = section {
+            for (key, value) in section.iter() {
+                let result = match key {
+                    "exec" => parse_exec_shortcut(value)
+                        .map(|(combo, spawn)| bindings.push((combo, KeyAction::Run(spawn)))),
+                    "mode" => parse_mode_shortcut(value)
+                        .map(|(combo, mode)| bindings.push((combo, KeyAction::EnterMode(mode)))),
+                    _ if DEFAULT_SHORTCUTS.iter().any(|(name, _)| *name == key) => Ok(()),
+                    _ => Err("unknown action".into()),
+                };
+                if let Err(message) = result {
+                    errors.push(ConfigError::invalid(section_name, key, message));
+                }
+            }
+        }
+
+        for (name, default) in DEFAULT_SHORTCUTS {
+            let default = if defaults { *default } else { "" };
+            let value = section.and_then(|s| s.get(*name)).unwrap_or(default);
+            // an empty value leaves the action unbound
+            if value.trim().is_empty() {
+                continue;
+            }
+            let combos = match parse_bindings(value) {
+                Ok(combos) => combos,
+                Err(message) => {
+                    errors.push(ConfigError::invalid(section_name, name, message));
+                    continue;
+                }
+            };
+            if !is_indexed(name) && combos.iter().any(|(index, _)| *index > 0) {
+                errors.push(ConfigError::invalid(
+                    section_name,
+                    name,
+                    "ranges are only supported for vt_switch, screen_switch, workspace_switch and workspace_move_window",
+                ));
+                continue;
+            }
+            bindings.extend(
+                combos
+                    .into_iter()
+                    .map(|(index, combo)| (combo, action_for_name(name, index, general))),
+            );
+        }
+
+        Keybindings { bindings }
+    }
+
+    /// Bind `combos` to start `spawn`, taking precedence over the bindings
+    /// already in the table.
+    pub fn bind_command(&mut self, combos: Vec<KeyCombo>, spawn: SpawnCommand) {
+        self.bind_first(combos, KeyAction::Run(spawn));
+    }
+
+    /// Bind `combos` to toggle the scratchpad of the rule called `rule`,
+    /// taking precedence over the bindings already in the table.
+    pub fn bind_scratchpad(&mut self, combos: Vec<KeyCombo>, rule: &str) {
+        self.bind_first(combos, KeyAction::ToggleScratchpad(Some(rule.to_string())));
+    }
+
+    fn bind_first(&mut self, combos: Vec<KeyCombo>, action: KeyAction) {
+        let bindings = combos.into_iter().map(|combo| (combo, action.clone()));
+        self.bindings.splice(0..0, bindings);
+    }
+
+    /// Find the action bound to `keysym` with the given modifiers held.
+    ///
+    /// If several bindings match, the one naming the most modifiers wins,
+    /// ties going to the binding listed first.
+    pub fn action_for(&self, modifiers: Modifiers, keysym: Keysym) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .rev()
+            .filter(|(combo, _)| combo.matches(modifiers, keysym))
+            .max_by_key(|(combo, _)| combo.modifiers.bits().count_ones())
+            .map(|(_, action)| action.clone())
+    }
+
+    /// Names of the binding modes the table can switch to
+    pub fn modes_entered(&self) -> impl Iterator<Item = &str> {
+        self.bindings.iter().filter_map(|(_, action)| match action {
+            KeyAction::EnterMode(mode) => Some(mode.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn combo(text: &str) -> KeyCombo {
+        KeyCombo::parse(text).unwrap()
+    }
+
+    fn keybindings(text: &str) -> (Keybindings, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let bindings = Keybindings::from_section(
+            ini.section(Some("shortcuts")),
+            &GeneralConfig::default(),
+            &mut errors,
+        );
+        (bindings, errors.iter().map(ToString::to_string).collect())
+    }
+
+    fn action(bindings: &Keybindings, text: &str) -> Option<KeyAction> {
+        let combo = combo(text);
+        bindings.action_for(combo.modifiers, combo.keysym)
+    }
+
+    #[test]
+    fn key_combo_with_modifiers() {
+        let combo = combo("Logo+Shift+M");
+        assert_eq!(combo.modifiers, Modifiers::LOGO | Modifiers::SHIFT);
+        assert_eq!(combo.keysym, Keysym::m);
+    }
+
+    #[test]
+    fn key_combo_modifier_aliases() {
+        assert_eq!(
+            combo("control+MOD1+super+a").modifiers,
+            Modifiers::CTRL | Modifiers::ALT | Modifiers::LOGO
+        );
+        assert_eq!(combo(" Ctrl + Return ").keysym, Keysym::Return);
+    }
+
+    #[test]
+    fn key_combo_without_modifiers() {
+        let combo = combo("Escape");
+        assert_eq!(combo.modifiers, Modifiers::empty());
+        assert_eq!(combo.keysym, Keysym::Escape);
+    }
+
+    #[test]
+    fn key_combo_plus_key() {
+        assert_eq!(combo("Logo++").keysym, Keysym::plus);
+    }
+
+    #[test]
+    fn key_combo_ignores_key_case_if_needed() {
+        assert_eq!(combo("Logo+escape").keysym, Keysym::Escape);
+    }
+
+    #[test]
+    fn shifted_letters_match() {
+        let (bindings, _) = keybindings("[shortcuts]\nquit = Logo+Shift+a\nscale_up = Logo+Shift+P\n");
+        let shift = Modifiers::LOGO | Modifiers::SHIFT;
+        assert_eq!(bindings.action_for(shift, Keysym::A), Some(KeyAction::Quit));
+        assert_eq!(bindings.action_for(shift, Keysym::P), Some(KeyAction::ScaleUp));
+        assert_eq!(bindings.action_for(Modifiers::LOGO, Keysym::a), None);
+        // only letters are folded
+        assert_eq!(combo("KP_Enter").keysym, Keysym::KP_Enter);
+    }
+
+    #[test]
+    fn key_combo_errors() {
+        assert_eq!(KeyCombo::parse("Logo+"), Err("missing key name".to_string()));
+        assert_eq!(
+            KeyCombo::parse("Hyper+a"),
+            Err("unknown modifier \"Hyper\" in \"Hyper+a\"".to_string())
+        );
+        assert_eq!(
+            KeyCombo::parse("Logo+NotAKey"),
+            Err("unknown key \"NotAKey\"".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_bindings_list() {
+        let combos = parse_bindings(r#"Ctrl+Alt+BackSpace, "Logo+q""#).unwrap();
+        assert_eq!(
+            combos,
+            vec![(0, combo("Ctrl+Alt+BackSpace")), (0, combo("Logo+q"))]
+        );
+    }
+
+    #[test]
+    fn parse_bindings_expands_ranges() {
+        let combos = parse_bindings("Logo+1..Logo+3").unwrap();
+        assert_eq!(
+            combos,
+            vec![(0, combo("Logo+1")), (1, combo("Logo+2")), (2, combo("Logo+3"))]
+        );
+    }
+
+    #[test]
+    fn parse_bindings_single_key_range() {
+        assert_eq!(
+            parse_bindings("Logo+5..Logo+5").unwrap(),
+            vec![(0, combo("Logo+5"))]
+        );
+    }
+
+    #[test]
+    fn parse_bindings_errors() {
+        assert_eq!(
+            parse_bindings("Ctrl+1..Logo+3"),
+            Err("range \"Ctrl+1..Logo+3\" must use the same modifiers on both ends".to_string())
+        );
+        assert_eq!(
+            parse_bindings("Logo+3..Logo+1"),
+            Err("range \"Logo+3..Logo+1\" is empty".to_string())
+        );
+        assert_eq!(parse_bindings(" , "), Err("no key binding given".to_string()));
+        assert_eq!(
+            parse_bindings("Logo+1..Logo+"),
+            Err("missing key name".to_string())
+        );
+    }
+
+    #[test]
+    fn default_bindings_without_section() {
+        let (bindings, errors) = keybindings("");
+        assert!(errors.is_empty());
+        assert_eq!(action(&bindings, "Ctrl+Alt+BackSpace"), Some(KeyAction::Quit));
+        assert_eq!(action(&bindings, "Logo+3"), Some(KeyAction::Screen(2)));
+        assert_eq!(action(&bindings, "Logo+h"), None);
+    }
+
+    #[test]
+    fn section_replaces_defaults() {
+        let (bindings, errors) =
+            keybindings("[shortcuts]\nquit = Logo+Shift+e\nscale_down =\nnext_layout = Logo+space\n");
+        assert!(errors.is_empty());
+        assert_eq!(action(&bindings, "Logo+Shift+e"), Some(KeyAction::Quit));
+        assert_eq!(action(&bindings, "Ctrl+Alt+BackSpace"), None);
+        // an empty value unbinds the action
+        assert_eq!(action(&bindings, "Logo+Shift+M"), None);
+        assert_eq!(action(&bindings, "Logo+space"), Some(KeyAction::NextLayout));
+    }
+
+    #[test]
+    fn most_specific_binding_wins() {
+        let (bindings, _) = keybindings("[shortcuts]\nquit = Logo+x\nscale_up = Logo+Shift+x\n");
+        assert_eq!(action(&bindings, "Logo+x"), Some(KeyAction::Quit));
+        assert_eq!(action(&bindings, "Logo+Shift+x"), Some(KeyAction::ScaleUp));
+        // modifiers not named in the binding may be held
+        assert_eq!(action(&bindings, "Logo+Ctrl+x"), Some(KeyAction::Quit));
+    }
+
+    #[test]
+    fn section_errors() {
+        let (bindings, errors) =
+            keybindings("[shortcuts]\nquit = Logo+1..Logo+2\nscale_up = Hyper+p\nfrobnicate = Logo+f\n");
+        assert_eq!(
+            errors,
+            vec![
+                "[shortcuts] frobnicate: unknown action",
+                "[shortcuts] quit: ranges are only supported for vt_switch, screen_switch, workspace_switch and workspace_move_window",
+                "[shortcuts] scale_up: unknown modifier \"Hyper\" in \"Hyper+p\"",
+            ]
+        );
+        // invalid entries are left unbound, the others still work
+        assert_eq!(action(&bindings, "Logo+1"), Some(KeyAction::Screen(0)));
+        assert_eq!(action(&bindings, "Ctrl+Alt+BackSpace"), None);
+    }
+
+    #[test]
+    fn mode_only_binds_listed_actions() {
+        let ini = Ini::load_from_str(
+            "[mode.resize]\nexit_mode = \"Escape\", \"Return\"\nmode = \"Logo+m\", \"move\"\n",
+        )
+        .unwrap();
+        let mut errors = Vec::new();
+        let bindings = Keybindings::mode_from_section(
+            "resize",
+            ini.section(Some("mode.resize")).unwrap(),
+            &GeneralConfig::default(),
+            &mut errors,
+        );
+        assert!(errors.is_empty());
+        assert_eq!(action(&bindings, "Return"), Some(KeyAction::ExitMode));
+        assert_eq!(
+            action(&bindings, "Logo+m"),
+            Some(KeyAction::EnterMode("move".into()))
+        );
+        assert_eq!(action(&bindings, "Ctrl+Alt+BackSpace"), None);
+        assert_eq!(bindings.modes_entered().collect::<Vec<_>>(), vec!["move"]);
+    }
+
+    #[test]
+    fn mode_shortcut_needs_binding_and_mode() {
+        let (_, errors) = keybindings("[shortcuts]\nmode = Logo+r\n");
+        assert_eq!(
+            errors,
+            vec!["[shortcuts] mode: expected a binding and a mode name such as \"Logo+r\", \"resize\", got Logo+r"]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use ini::Properties;
+use smithay::input::keyboard::XkbConfig;
+use xkbcommon::xkb;
+
+use super::ConfigError;
+
+/// Settings of the `[keyboard]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardConfig {
+    pub rules: String,
+    pub model: String,
+    /// Comma separated list of layouts, switchable at runtime
+    pub layout: String,
+    /// Comma separated list of variants, one per layout
+    pub variant: String,
+    /// xkb options such as `ctrl:nocaps,compose:ralt`
+    pub options: Option<String>,
+    /// Delay in milliseconds before a held key starts repeating
+    pub repeat_delay: i32,
+    /// Repeated key presses per second
+    pub repeat_rate: i32,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        KeyboardConfig {
+            rules: String::new(),
+            model: String::new(),
+            layout: String::new(),
+            variant: String::new(),
+            options: None,
+            repeat_delay: 200,
+            repeat_rate: 25,
+        }
+    }
+}
+
+const KEYBOARD_KEYS: &[&str] = &[
+    "rules",
+    "model",
+    "layout",
+    "variant",
+    "options",
+    "repeat_delay",
+    "repeat_rate",
+];
+
+impl KeyboardConfig {
+    /// Parse the `[keyboard]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = KeyboardConfig::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            let value = value.trim();
+            match key {
+                "rules" => config.rules = value.to_string(),
+                "model" => config.model = value.to_string(),
+                "layout" => config.layout = strip_spaces(value),
+                "variant" => config.variant = strip_spaces(value),
+                "options" => config.options = Some(strip_spaces(value)).filter(|o| !o.is_empty()),
+                "repeat_delay" => match value.parse::<i32>() {
+                    Ok(delay) if delay >= 0 => config.repeat_delay = delay,
+                    _ => errors.push(ConfigError::invalid(
+                        "keyboard",
+                        key,
+                        format!("expected a delay in milliseconds, got \"{}\"", value),
+                    )),
+                },
+                "repeat_rate" => match value.parse::<i32>() {
+                    Ok(rate) if rate >= 0 => config.repeat_rate = rate,
+                    _ => errors.push(ConfigError::invalid(
+                        "keyboard",
+                        key,
+                        format!("expected a rate in keys per second, got \"{}\"", value),
+                    )),
+                },
+                _ => errors.push(ConfigError::invalid(
+                    "keyboard",
+                    key,
+                    format!("unknown key, expected one of {}", KEYBOARD_KEYS.join(", ")),
+                )),
+            }
+        }
+
+        config
+    }
+
+    /// The xkb names to build the keymap from
+    pub fn xkb_config(&self) -> XkbConfig<'_> {
+        XkbConfig {
+            rules: &self.rules,
+            model: &self.model,
+            layout: &self.layout,
+            variant: &self.variant,
+            options: self.options.clone(),
+        }
+    }
+
+    /// Compile the keymap described by these settings, to make sure xkbcommon
+    /// knows the rules, model, layouts, variants and options.
+    pub fn compile_keymap(&self) -> Result<xkb::Keymap, ConfigError> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        xkb::Keymap::new_from_names(
+            &context,
+            &self.rules,
+            &self.model,
+            &self.layout,
+            &self.variant,
+            self.options.clone(),
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .ok_or_else(|| ConfigError::invalid("keyboard", "layout", self.keymap_error()))
+    }
+
+    /// Message used when the keymap described by these settings cannot be compiled
+    pub fn keymap_error(&self) -> String {
+        format!(
+            "failed to compile keymap for layout \"{}\" variant \"{}\" options \"{}\"",
+            self.layout,
+            self.variant,
+            self.options.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// xkbcommon does not accept spaces in its comma separated lists
+fn strip_spaces(value: &str) -> String {
+    value.split(',').map(str::trim).collect::<Vec<_>>().join(",")
+}
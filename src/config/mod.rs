@@ -0,0 +1,581 @@
+//! Parsing of `~/.config/wayice.ini`.
+//!
+//! The file is turned into a [`Config`] at startup and again whenever it
+//! changes on disk; the rest of the compositor only ever looks at the parsed
+//! values.
+
+mod autostart;
+mod decorations;
+mod exec;
+mod general;
+mod gestures;
+mod input;
+mod keybindings;
+mod keyboard;
+mod mouse;
+mod output;
+mod reload;
+mod rules;
+mod snapping;
+mod tiling;
+mod workspaces;
+
+pub use autostart::AutostartCommand;
+pub use decorations::{ButtonSide, DecorationButton, DecorationColors, DecorationTheme};
+pub use exec::SpawnCommand;
+pub use general::{GeneralConfig, Placement};
+pub use gestures::{Gesture, GestureBindings, GestureCombo, GestureType};
+pub use input::{AccelProfile, ClickMethod, InputConfig, ScrollMethod};
+pub use keybindings::*;
+pub use keyboard::KeyboardConfig;
+pub use mouse::{MouseAction, MouseBindings, MouseCombo};
+pub use output::OutputConfig;
+pub use reload::watch_config;
+pub use rules::{Decorations, RuleEffects, Stacking, WindowProperties, WindowRule};
+pub use snapping::SnappingConfig;
+pub use tiling::{LayoutKind, TilingConfig};
+pub use workspaces::WorkspaceConfig;
+
+use std::path::{Path, PathBuf};
+
+use ini::Ini;
+use tracing::{info, warn};
+
+/// Errors that can happen while loading the configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// The file exists but could not be read
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The file is not valid ini
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] ini::ParseError),
+    /// A value could not be understood
+    #[error("[{section}] {key}: {message}")]
+    Invalid {
+        section: String,
+        key: String,
+        message: String,
+    },
+}
+
+impl ConfigError {
+    /// Line of `text` the error refers to, if it can be determined
+    pub fn line(&self, text: &str) -> Option<usize> {
+        match self {
+            ConfigError::Io { .. } => None,
+            ConfigError::Parse(err) => Some(err.line),
+            ConfigError::Invalid { section, key, .. } => find_line(text, section, key),
+        }
+    }
+
+    pub(crate) fn invalid(section: &str, key: &str, message: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            section: section.into(),
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Sections read by name, besides `[input]` and those named after an output,
+/// device, mode, command, workspace or rule
+const SECTIONS: &[&str] = &[
+    "general",
+    "shortcuts",
+    "gestures",
+    "mouse_bindings",
+    "keyboard",
+    "autostart",
+    "decorations",
+    "workspaces",
+    "tiling",
+    "snapping",
+];
+
+/// Find the line on which `key` is set inside `[section]`, falling back to
+/// the section header itself.
+fn find_line(text: &str, section: &str, key: &str) -> Option<usize> {
+    let mut section_line = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if section_line.is_some() {
+                break;
+            }
+            if name.trim() == section {
+                section_line = Some(index + 1);
+            }
+        } else if section_line.is_some() && line.split_once('=').map(|(k, _)| k.trim()) == Some(key) {
+            return Some(index + 1);
+        }
+    }
+    section_line
+}
+
+/// Name of the binding mode using the `[shortcuts]` section
+pub const DEFAULT_MODE: &str = "default";
+
+/// The parsed contents of `wayice.ini`
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub general: GeneralConfig,
+    pub keybindings: Keybindings,
+    /// Binding modes declared by `[mode.<name>]` sections
+    pub modes: Vec<(String, Keybindings)>,
+    pub mouse_bindings: MouseBindings,
+    pub gestures: GestureBindings,
+    pub keyboard: KeyboardConfig,
+    pub outputs: Vec<OutputConfig>,
+    /// Settings of the `[input]` section, applied to every device
+    pub input: InputConfig,
+    /// Settings of the `[input.<device-name>]` sections
+    pub input_devices: Vec<(String, InputConfig)>,
+    pub autostart: Vec<AutostartCommand>,
+    /// The `[rule.<name>]` sections, in the order they appear in the file
+    pub rules: Vec<WindowRule>,
+    pub decorations: DecorationTheme,
+    pub workspaces: WorkspaceConfig,
+    pub tiling: TilingConfig,
+    pub snapping: SnappingConfig,
+}
+
+impl Config {
+    /// Load the configuration from `path`.
+    ///
+    /// A missing file is not an error and yields the built-in defaults.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        match read_config(path)? {
+            Some(text) => Config::parse(&text),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Parse the configuration from the contents of an ini file
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let (config, errors) = Config::parse_collecting(text)?;
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(config),
+        }
+    }
+
+    /// Validate the contents of an ini file without applying them.
+    ///
+    /// Unlike [`Config::parse`] this does not stop at the first problem and
+    /// also compiles the keymap, so every error can be reported at once.
+    pub fn check(text: &str) -> Vec<ConfigError> {
+        match Config::parse_collecting(text) {
+            Ok((config, mut errors)) => {
+                if let Err(err) = config.keyboard.compile_keymap() {
+                    errors.push(err);
+                }
+                errors
+            }
+            Err(err) => vec![err],
+        }
+    }
+
+    fn parse_collecting(text: &str) -> Result<(Config, Vec<ConfigError>), ConfigError> {
+        let ini = Ini::load_from_str(text)?;
+        let mut errors = Vec::new();
+        let general = GeneralConfig::from_section(ini.section(Some("general")), &mut errors);
+        let mut config = Config {
+            keybindings: Keybindings::from_section(ini.section(Some("shortcuts")), &general, &mut errors),
+            gestures: GestureBindings::from_section(ini.section(Some("gestures")), &general, &mut errors),
+            general,
+            modes: Vec::new(),
+            mouse_bindings: MouseBindings::from_section(ini.section(Some("mouse_bindings")), &mut errors),
+            keyboard: KeyboardConfig::from_section(ini.section(Some("keyboard")), &mut errors),
+            outputs: Vec::new(),
+            input: InputConfig::default(),
+            input_devices: Vec::new(),
+            autostart: autostart::autostart_from_section(ini.section(Some("autostart")), &mut errors),
+            rules: Vec::new(),
+            decorations: DecorationTheme::from_section(ini.section(Some("decorations")), &mut errors),
+            workspaces: WorkspaceConfig::from_section(ini.section(Some("workspaces")), &mut errors),
+            tiling: TilingConfig::from_section(ini.section(Some("tiling")), &mut errors),
+            snapping: SnappingConfig::from_section(ini.section(Some("snapping")), &mut errors),
+        };
+
+        for (name, section) in ini.iter() {
+            let Some(name) = name else {
+                continue;
+            };
+            if let Some(output) = name.strip_prefix("output.") {
+                config
+                    .outputs
+                    .push(OutputConfig::from_section(output, section, &mut errors));
+            } else if name == "input" {
+                config.input = InputConfig::from_section(name, section, &mut errors);
+            } else if let Some(device) = name.strip_prefix("input.") {
+                let device_config = InputConfig::from_section(name, section, &mut errors);
+                config.input_devices.push((device.to_string(), device_config));
+            } else if let Some(mode) = name.strip_prefix("mode.") {
+                let bindings = Keybindings::mode_from_section(mode, section, &config.general, &mut errors);
+                config.modes.push((mode.to_string(), bindings));
+            } else if let Some(exec) = name.strip_prefix("exec.") {
+                if let Some((combos, spawn)) = exec::exec_from_section(exec, section, &mut errors) {
+                    config.keybindings.bind_command(combos, spawn);
+                }
+            } else if let Some(workspace) = name.strip_prefix("workspace.") {
+                config.workspaces.add_workspace(workspace, section, &mut errors);
+            } else if let Some(rule) = name.strip_prefix("rule.") {
+                let rule = WindowRule::from_section(rule, section, &mut errors);
+                if let Some(combos) = rule.scratchpad_binding.clone() {
+                    config.keybindings.bind_scratchpad(combos, &rule.name);
+                }
+                config.rules.push(rule);
+            } else if !SECTIONS.contains(&name) {
+                errors.push(ConfigError::invalid(name, "", "unknown section"));
+            }
+        }
+
+        config.check_modes(&mut errors);
+
+        Ok((config, errors))
+    }
+
+    /// Report bindings switching to modes that are not declared
+    fn check_modes(&self, errors: &mut Vec<ConfigError>) {
+        let tables = std::iter::once(("shortcuts".to_string(), &self.keybindings)).chain(
+            self.modes
+                .iter()
+                .map(|(name, bindings)| (format!("mode.{}", name), bindings)),
+        );
+        for (section, bindings) in tables {
+            for mode in bindings.modes_entered() {
+                if mode != DEFAULT_MODE && self.mode(mode).is_none() {
+                    errors.push(ConfigError::invalid(
+                        &section,
+                        "mode",
+                        format!("unknown mode \"{}\", no [mode.{}] section", mode, mode),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// The bindings of the mode called `name`
+    pub fn mode(&self, name: &str) -> Option<&Keybindings> {
+        self.modes
+            .iter()
+            .find(|(mode, _)| mode == name)
+            .map(|(_, bindings)| bindings)
+    }
+
+    /// The bindings active in `mode`, `None` being the default mode
+    pub fn keybindings_for(&self, mode: Option<&str>) -> &Keybindings {
+        mode.and_then(|mode| self.mode(mode)).unwrap_or(&self.keybindings)
+    }
+
+    /// Settings for the input device called `device_name`, combining
+    /// `[input]` with the device's own section.
+    pub fn input_for(&self, device_name: &str) -> InputConfig {
+        self.input_devices
+            .iter()
+            .filter(|(name, _)| name == device_name)
+            .fold(self.input.clone(), |config, (_, device)| config.merged(device))
+    }
+
+    /// Combined effects of all rules matching `window`.
+    ///
+    /// Rules are applied in the order they appear in the file, so later rules
+    /// override the effects of earlier ones.
+    pub fn rules_for(&self, window: &WindowProperties) -> RuleEffects {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(window))
+            .fold(RuleEffects::default(), |effects, rule| {
+                effects.merged(&rule.effects)
+            })
+    }
+
+    /// Find the `[output.<name>]` section for an output, matching on the
+    /// connector name first and on the EDID make and model second.
+    pub fn output(&self, connector: &str, make: &str, model: &str) -> Option<&OutputConfig> {
+        let description = format!("{} {}", make, model);
+        self.outputs
+            .iter()
+            .find(|output| output.name == connector)
+            .or_else(|| self.outputs.iter().find(|output| output.name == description))
+    }
+
+    /// Load the configuration from the default location, falling back to the
+    /// defaults if it cannot be loaded.
+    ///
+    /// Invalid entries are logged and keep their defaults, everything else in
+    /// the file still applies.
+    pub fn load_or_default() -> Config {
+        let Some(path) = config_path() else {
+            warn!("Could not determine the home directory, using default config");
+            return Config::default();
+        };
+        let text = match read_config(&path) {
+            Ok(Some(text)) => text,
+            Ok(None) => return Config::default(),
+            Err(err) => {
+                warn!(path = %path.display(), %err, "Failed to load config, using defaults");
+                return Config::default();
+            }
+        };
+        match Config::parse_collecting(&text) {
+            Ok((config, errors)) => {
+                for err in &errors {
+                    warn!(path = %path.display(), line = ?err.line(&text), %err, "Ignoring invalid config entry");
+                }
+                config
+            }
+            Err(err) => {
+                warn!(path = %path.display(), %err, "Failed to parse config, using defaults");
+                Config::default()
+            }
+        }
+    }
+}
+
+pub(crate) fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("expected true or false, got \"{}\"", value)),
+    }
+}
+
+/// Read the config file, `None` if it does not exist
+fn read_config(path: &Path) -> Result<Option<String>, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            info!(path = %path.display(), "No config file found, using defaults");
+            Ok(None)
+        }
+        Err(source) => Err(ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Location of the configuration file, `~/.config/wayice.ini`
+pub fn config_path() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".config").join("wayice.ini"))
+}
+
+/// Split a list value such as `"Ctrl+Alt+BackSpace", "Logo+q"` into its items.
+///
+/// rust-ini only strips the outermost pair of quotes, so the remaining items
+/// still carry theirs. Commas inside a quoted item are kept.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => items.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_list_splits_on_commas() {
+        assert_eq!(
+            split_list("Logo+q, Ctrl+Alt+BackSpace"),
+            vec!["Logo+q", "Ctrl+Alt+BackSpace"]
+        );
+    }
+
+    #[test]
+    fn split_list_keeps_commas_in_quotes() {
+        // rust-ini has already stripped the quotes of the first item
+        assert_eq!(
+            split_list(r#"Logo+e, "echo a, b", "c""#),
+            vec!["Logo+e", "echo a, b", "c"]
+        );
+    }
+
+    #[test]
+    fn split_list_drops_empty_items() {
+        assert_eq!(split_list(" a,, b , "), vec!["a", "b"]);
+        assert!(split_list("").is_empty());
+    }
+
+    const TEXT: &str =
+        "[shortcuts]\nquit = Logo+q\n\n[keyboard]\nlayout = us\n variant = intl\n[other]\nmodel = pc105\n";
+
+    #[test]
+    fn find_line_finds_key() {
+        assert_eq!(find_line(TEXT, "keyboard", "layout"), Some(5));
+        assert_eq!(find_line(TEXT, "keyboard", "variant"), Some(6));
+    }
+
+    #[test]
+    fn find_line_falls_back_to_section() {
+        assert_eq!(find_line(TEXT, "keyboard", "options"), Some(4));
+        // keys of the following section don't count
+        assert_eq!(find_line(TEXT, "keyboard", "model"), Some(4));
+    }
+
+    #[test]
+    fn find_line_without_section() {
+        assert_eq!(find_line(TEXT, "decorations", "height"), None);
+    }
+
+    #[test]
+    fn error_lines() {
+        let text = "[shortcuts]\nquit = Logo+q\nscale_up = Hyper+p\n";
+        let errors = Config::check(text);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line(text), Some(3));
+
+        let text = "[shortcuts]\nquit\n";
+        let errors = Config::check(text);
+        assert!(matches!(errors[..], [ConfigError::Parse(_)]));
+    }
+
+    #[test]
+    fn empty_config_is_default() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.keyboard, KeyboardConfig::default());
+        assert!(Config::check("").is_empty());
+    }
+
+    #[test]
+    fn parse_stops_at_first_error() {
+        let err = Config::parse("[shortcuts]\nquit = Hyper+q\nscale_up = Logo+NotAKey\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[shortcuts] quit: unknown modifier \"Hyper\" in \"Hyper+q\""
+        );
+    }
+
+    #[test]
+    fn check_reports_every_error() {
+        let errors =
+            Config::check("[shortcuts]\nquit = Hyper+q\nscale_up = Logo+NotAKey\n[keyboard]\nlayout = xx\n");
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[shortcuts] quit: unknown modifier \"Hyper\" in \"Hyper+q\"",
+                "[shortcuts] scale_up: unknown key \"NotAKey\"",
+                "[keyboard] layout: failed to compile keymap for layout \"xx\" variant \"\" options \"\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn output_lookup() {
+        let config = Config::parse(
+            "[output.Dell Inc. U2720Q]
+scale = 2
+[output.DP-1]
+scale = 1.5
+",
+        )
+        .unwrap();
+        // the connector name wins over make and model
+        assert_eq!(
+            config.output("DP-1", "Dell Inc.", "U2720Q").unwrap().scale,
+            Some(1.5)
+        );
+        assert_eq!(
+            config.output("DP-2", "Dell Inc.", "U2720Q").unwrap().scale,
+            Some(2.0)
+        );
+        assert!(config.output("HDMI-A-1", "Unknown", "Unknown").is_none());
+    }
+
+    #[test]
+    fn bool_values() {
+        assert_eq!(parse_bool(" Yes"), Ok(true));
+        assert_eq!(parse_bool("off"), Ok(false));
+        assert_eq!(
+            parse_bool("maybe"),
+            Err("expected true or false, got \"maybe\"".to_string())
+        );
+    }
+
+    #[test]
+    fn input_sections_are_merged() {
+        let config = Config::parse(
+            "[input]\ntap = true\nnatural_scroll = true\n[input.Touchpad]\nnatural_scroll = false\n",
+        )
+        .unwrap();
+        let touchpad = config.input_for("Touchpad");
+        assert_eq!(touchpad.tap, Some(true));
+        assert_eq!(touchpad.natural_scroll, Some(false));
+        assert_eq!(config.input_for("Mouse").natural_scroll, Some(true));
+    }
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let config = Config::parse(
+            "[rule.all]\napp_id = .\nfloating = true\nopacity = 0.9\n[rule.term]\napp_id = ^foot$\nfloating = false\n",
+        )
+        .unwrap();
+        let window = |app_id: &str| WindowProperties {
+            app_id: Some(app_id.into()),
+            ..WindowProperties::default()
+        };
+        let effects = config.rules_for(&window("foot"));
+        assert_eq!(effects.floating, Some(false));
+        assert_eq!(effects.opacity, Some(0.9));
+        assert_eq!(config.rules_for(&window("mpv")).floating, Some(true));
+    }
+
+    #[test]
+    fn modes() {
+        let config = Config::parse(
+            "[shortcuts]\nmode = \"Logo+r\", \"resize\"\n[mode.resize]\nexit_mode = Escape\nmode = \"Return\", \"default\"\n",
+        )
+        .unwrap();
+        assert!(config.mode("resize").is_some());
+        assert!(config.mode("move").is_none());
+    }
+
+    #[test]
+    fn undeclared_mode_is_reported() {
+        let errors = Config::check("[shortcuts]\nmode = \"Logo+r\", \"resize\"\n");
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec!["[shortcuts] mode: unknown mode \"resize\", no [mode.resize] section"]
+        );
+    }
+
+    #[test]
+    fn unknown_section_is_reported() {
+        let text = "[tiling]\ngaps = 4\n\n[tilling]\ngaps = 4\n";
+        let errors = Config::check(text);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "[tilling] : unknown section");
+        assert_eq!(errors[0].line(text), Some(4));
+    }
+
+    #[test]
+    fn prefixed_sections_are_known() {
+        let text =
+            "[output.HDMI-A-1]\nscale = 2\n[input.touchpad]\ntap = true\n[mode.resize]\nexit_mode = Escape\n\
+                    [workspace.web]\nlayout = monocle\n[rule.term]\napp_id = foot\n";
+        assert!(Config::check(text).is_empty());
+    }
+}
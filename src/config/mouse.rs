@@ -0,0 +1,117 @@
+use ini::Properties;
+
+use super::{keybindings::parse_modifiers, split_list, ConfigError, Modifiers};
+
+/// Linux input event codes of the buttons that can be bound
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+
+/// What dragging with a mouse binding does to the window under the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    Move,
+    /// Resize from the corner of the window closest to the pointer
+    Resize,
+}
+
+/// A modifier and button combination, e.g. `Logo+LeftDrag`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseCombo {
+    pub modifiers: Modifiers,
+    /// Linux input event code of the button
+    pub button: u32,
+}
+
+impl MouseCombo {
+    /// Parse a combination of the form `Mod+Mod+<Button>Drag`
+    pub fn parse(text: &str) -> Result<MouseCombo, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let button = parts
+            .pop()
+            .filter(|b| !b.is_empty())
+            .ok_or("missing button name")?;
+        let button = match button.to_ascii_lowercase().as_str() {
+            "leftdrag" => BTN_LEFT,
+            "rightdrag" => BTN_RIGHT,
+            "middledrag" => BTN_MIDDLE,
+            _ => {
+                return Err(format!(
+                    "unknown button \"{}\", expected LeftDrag, RightDrag or MiddleDrag",
+                    button
+                ))
+            }
+        };
+
+        Ok(MouseCombo {
+            modifiers: parse_modifiers(&parts, text)?,
+            button,
+        })
+    }
+}
+
+/// Names of the actions understood in `[mouse_bindings]` and their default bindings
+const DEFAULT_MOUSE_BINDINGS: &[(&str, MouseAction, &str)] = &[
+    ("move", MouseAction::Move, "Logo+LeftDrag"),
+    ("resize", MouseAction::Resize, "Logo+RightDrag"),
+];
+
+/// The table of pointer button bindings built from `[mouse_bindings]`
+#[derive(Debug, Clone)]
+pub struct MouseBindings {
+    bindings: Vec<(MouseCombo, MouseAction)>,
+}
+
+impl Default for MouseBindings {
+    fn default() -> Self {
+        let mut errors = Vec::new();
+        let bindings = MouseBindings::from_section(None, &mut errors);
+        debug_assert!(
+            errors.is_empty(),
+            "Default mouse bindings are invalid: {:?}",
+            errors
+        );
+        bindings
+    }
+}
+
+impl MouseBindings {
+    /// Build the table from the `[mouse_bindings]` section, falling back to
+    /// the defaults for missing actions
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        if let Some(section) = section {
+            for (key, _) in section.iter() {
+                if !DEFAULT_MOUSE_BINDINGS.iter().any(|(name, _, _)| *name == key) {
+                    errors.push(ConfigError::invalid("mouse_bindings", key, "unknown action"));
+                }
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for (name, action, default) in DEFAULT_MOUSE_BINDINGS {
+            let value = section.and_then(|s| s.get(*name)).unwrap_or(default);
+            let combos = split_list(value)
+                .iter()
+                .map(|item| MouseCombo::parse(item))
+                .collect::<Result<Vec<_>, _>>();
+            match combos {
+                // an empty value leaves the action unbound
+                Ok(combos) => bindings.extend(combos.into_iter().map(|combo| (combo, *action))),
+                Err(message) => errors.push(ConfigError::invalid("mouse_bindings", name, message)),
+            }
+        }
+
+        MouseBindings { bindings }
+    }
+
+    /// The action bound to pressing `button` while `modifiers` are held.
+    ///
+    /// Like key bindings, the most specific combination wins.
+    pub fn action_for(&self, modifiers: Modifiers, button: u32) -> Option<MouseAction> {
+        self.bindings
+            .iter()
+            .filter(|(combo, _)| combo.button == button && modifiers.contains(combo.modifiers))
+            .max_by_key(|(combo, _)| combo.modifiers.bits().count_ones())
+            .map(|(_, action)| *action)
+    }
+}
@@ -0,0 +1,270 @@
+use ini::Properties;
+use smithay::utils::Transform;
+
+use super::{parse_bool, tiling::LayoutKind, ConfigError};
+
+/// Settings of an `[output.<name>]` section
+///
+/// `<name>` is either the connector name (e.g. `HDMI-A-1`) or the make and
+/// model reported by the EDID, separated by a space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConfig {
+    pub name: String,
+    pub enabled: bool,
+    /// Requested mode size in pixels
+    pub mode: Option<(i32, i32)>,
+    /// Requested refresh rate in mHz
+    pub refresh: Option<i32>,
+    pub position: Option<(i32, i32)>,
+    pub scale: Option<f64>,
+    pub transform: Option<Transform>,
+    /// Layout of the workspaces on the output that don't set their own
+    pub layout: Option<LayoutKind>,
+}
+
+const OUTPUT_KEYS: &[&str] = &[
+    "enabled",
+    "mode",
+    "refresh",
+    "position",
+    "scale",
+    "transform",
+    "layout",
+];
+
+impl OutputConfig {
+    /// Parse the section named `[output.<name>]`, reporting invalid values in `errors`
+    pub fn from_section(name: &str, section: &Properties, errors: &mut Vec<ConfigError>) -> Self {
+        let section_name = format!("output.{}", name);
+        let mut config = OutputConfig {
+            name: name.to_string(),
+            enabled: true,
+            mode: None,
+            refresh: None,
+            position: None,
+            scale: None,
+            transform: None,
+            layout: None,
+        };
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "enabled" => parse_bool(value).map(|enabled| config.enabled = enabled),
+                "mode" => parse_mode(value).map(|(size, refresh)| {
+                    config.mode = Some(size);
+                    if refresh.is_some() {
+                        config.refresh = refresh;
+                    }
+                }),
+                "refresh" => parse_refresh(value).map(|refresh| config.refresh = Some(refresh)),
+                "position" => parse_pair(value, ',').map(|position| config.position = Some(position)),
+                "scale" => match value.trim().parse::<f64>() {
+                    Ok(scale) if scale > 0.0 => {
+                        config.scale = Some(scale);
+                        Ok(())
+                    }
+                    _ => Err(format!("invalid scale \"{}\"", value)),
+                },
+                "transform" => parse_transform(value).map(|transform| config.transform = Some(transform)),
+                "layout" => LayoutKind::parse(value).map(|layout| config.layout = Some(layout)),
+                _ => Err(format!("unknown key, expected one of {}", OUTPUT_KEYS.join(", "))),
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid(&section_name, key, message));
+            }
+        }
+
+        if config.refresh.is_some() && config.mode.is_none() {
+            errors.push(ConfigError::invalid(
+                &section_name,
+                "refresh",
+                "a refresh rate needs a mode",
+            ));
+        }
+
+        config
+    }
+}
+
+pub(super) fn parse_pair(value: &str, separator: char) -> Result<(i32, i32), String> {
+    value
+        .split_once(separator)
+        .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+        .ok_or_else(|| {
+            format!(
+                "expected two numbers separated by '{}', got \"{}\"",
+                separator, value
+            )
+        })
+}
+
+/// Parse `1920x1080` or `1920x1080@60`
+fn parse_mode(value: &str) -> Result<((i32, i32), Option<i32>), String> {
+    let (size, refresh) = match value.split_once('@') {
+        Some((size, refresh)) => (size, Some(parse_refresh(refresh)?)),
+        None => (value, None),
+    };
+    let size = parse_pair(size, 'x')?;
+    if size.0 <= 0 || size.1 <= 0 {
+        return Err(format!("invalid mode \"{}\"", value));
+    }
+    Ok((size, refresh))
+}
+
+/// Parse a refresh rate given in Hz into mHz
+fn parse_refresh(value: &str) -> Result<i32, String> {
+    let value = value.trim();
+    let hz = value.strip_suffix("Hz").unwrap_or(value).trim();
+    match hz.parse::<f64>() {
+        Ok(hz) if hz > 0.0 => Ok((hz * 1000.0).round() as i32),
+        _ => Err(format!("invalid refresh rate \"{}\"", value)),
+    }
+}
+
+fn parse_transform(value: &str) -> Result<Transform, String> {
+    Ok(match value.trim().to_ascii_lowercase().as_str() {
+        "normal" | "0" => Transform::Normal,
+        "90" => Transform::_90,
+        "180" => Transform::_180,
+        "270" => Transform::_270,
+        "flipped" => Transform::Flipped,
+        "flipped-90" => Transform::Flipped90,
+        "flipped-180" => Transform::Flipped180,
+        "flipped-270" => Transform::Flipped270,
+        _ => {
+            return Err(format!(
+                "invalid transform \"{}\", expected normal, 90, 180, 270, flipped, flipped-90, flipped-180 or flipped-270",
+                value
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(body: &str) -> (OutputConfig, Vec<String>) {
+        let ini = Ini::load_from_str(&format!("[output.HDMI-A-1]\n{}", body)).unwrap();
+        let mut errors = Vec::new();
+        let config = OutputConfig::from_section(
+            "HDMI-A-1",
+            ini.section(Some("output.HDMI-A-1")).unwrap(),
+            &mut errors,
+        );
+        (config, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn empty_section_keeps_defaults() {
+        let (config, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(
+            config,
+            OutputConfig {
+                name: "HDMI-A-1".into(),
+                enabled: true,
+                mode: None,
+                refresh: None,
+                position: None,
+                scale: None,
+                transform: None,
+                layout: None,
+            }
+        );
+    }
+
+    #[test]
+    fn valid_values() {
+        let (config, errors) = parse(
+            "enabled = no\nmode = 2560x1440\nrefresh = 59.951 Hz\nposition = -1920, 0\nscale = 1.5\n\
+             transform = Flipped-90\nlayout = dwindle\n",
+        );
+        assert!(errors.is_empty());
+        assert!(!config.enabled);
+        assert_eq!(config.mode, Some((2560, 1440)));
+        assert_eq!(config.refresh, Some(59951));
+        assert_eq!(config.position, Some((-1920, 0)));
+        assert_eq!(config.scale, Some(1.5));
+        assert_eq!(config.transform, Some(Transform::Flipped90));
+        assert_eq!(config.layout, Some(LayoutKind::Dwindle));
+    }
+
+    #[test]
+    fn mode_with_refresh_rate() {
+        let (config, errors) = parse("mode = 1920x1080@60\n");
+        assert!(errors.is_empty());
+        assert_eq!(config.mode, Some((1920, 1080)));
+        assert_eq!(config.refresh, Some(60000));
+    }
+
+    #[test]
+    fn refresh_key_overrides_mode() {
+        let (config, _) = parse("mode = 1920x1080@60\nrefresh = 144\n");
+        assert_eq!(config.refresh, Some(144000));
+    }
+
+    #[test]
+    fn transform_names() {
+        for (name, transform) in [
+            ("0", Transform::Normal),
+            ("90", Transform::_90),
+            ("180", Transform::_180),
+            ("270", Transform::_270),
+            ("flipped", Transform::Flipped),
+            ("flipped-180", Transform::Flipped180),
+            ("flipped-270", Transform::Flipped270),
+        ] {
+            assert_eq!(parse_transform(name), Ok(transform));
+        }
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) = parse(
+            "enabled = sometimes\nmode = 0x1080\nposition = 10\nscale = 0\ntransform = 45\nlayout = grid\n\
+             colour = red\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[output.HDMI-A-1] enabled: expected true or false, got \"sometimes\"",
+                "[output.HDMI-A-1] mode: invalid mode \"0x1080\"",
+                "[output.HDMI-A-1] position: expected two numbers separated by ',', got \"10\"",
+                "[output.HDMI-A-1] scale: invalid scale \"0\"",
+                "[output.HDMI-A-1] transform: invalid transform \"45\", expected normal, 90, 180, 270, flipped, flipped-90, flipped-180 or flipped-270",
+                "[output.HDMI-A-1] layout: expected floating, master-stack, dwindle, monocle or scrolling, got \"grid\"",
+                "[output.HDMI-A-1] colour: unknown key, expected one of enabled, mode, refresh, position, scale, transform, layout",
+            ]
+        );
+        assert!(config.enabled);
+        assert_eq!(config.mode, None);
+    }
+
+    #[test]
+    fn invalid_modes() {
+        assert_eq!(
+            parse_mode("1920x1080@fast"),
+            Err("invalid refresh rate \"fast\"".to_string())
+        );
+        assert_eq!(
+            parse_mode("1920"),
+            Err("expected two numbers separated by 'x', got \"1920\"".to_string())
+        );
+        assert_eq!(
+            parse_refresh("0Hz"),
+            Err("invalid refresh rate \"0Hz\"".to_string())
+        );
+    }
+
+    #[test]
+    fn refresh_needs_mode() {
+        let (_, errors) = parse("refresh = 60\n");
+        assert_eq!(
+            errors,
+            vec!["[output.HDMI-A-1] refresh: a refresh rate needs a mode"]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    ffi::{CString, OsString},
+    fs::{self, File},
+    io::{self, Read},
+    os::unix::{ffi::OsStrExt, io::FromRawFd},
+    path::Path,
+};
+
+use serde_json::json;
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use tracing::{error, info, warn};
+
+use super::{config_path, Config, ConfigError, OutputConfig, DEFAULT_MODE};
+use crate::{
+    core::state::{Backend, WayiceState},
+    helpers::output_utils::{apply_output_config, output_config},
+    ipc::shared_memory::ipc_set_string,
+};
+
+/// Watch the directory containing `wayice.ini` and reload the config whenever
+/// the file is written or replaced.
+///
+/// The directory is watched instead of the file itself, as most editors save
+/// by writing a new file and renaming it over the old one. If `wayice.ini` is
+/// a symlink, the directory of its target is watched as well.
+pub fn watch_config<BackendData: Backend + 'static>(handle: &LoopHandle<'static, WayiceState<BackendData>>) {
+    let Some(path) = config_path() else {
+        return;
+    };
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    if !dir.exists() {
+        // create it, so a config written later is still picked up
+        if let Err(err) = fs::create_dir_all(dir) {
+            warn!(dir = %dir.display(), %err, "Config directory is missing, config reload disabled");
+            return;
+        }
+    }
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+    if fd < 0 {
+        warn!(err = %io::Error::last_os_error(), "Failed to create inotify instance, config reload disabled");
+        return;
+    }
+    // Safety: we just created the fd and nothing else owns it
+    let inotify = unsafe { File::from_raw_fd(fd) };
+
+    let mut paths = vec![path.clone()];
+    if path.is_symlink() {
+        match fs::canonicalize(&path) {
+            Ok(target) => paths.push(target),
+            Err(err) => warn!(path = %path.display(), %err, "Failed to resolve config symlink"),
+        }
+    }
+    // the watch descriptor of each watched directory and the file name in it
+    let watched: Vec<(i32, OsString)> = paths
+        .iter()
+        .filter_map(|path| {
+            let (dir, file_name) = (path.parent()?, path.file_name()?);
+            let wd = add_watch(fd, dir)?;
+            Some((wd, file_name.to_owned()))
+        })
+        .collect();
+    if watched.is_empty() {
+        warn!("Config reload disabled");
+        return;
+    }
+
+    handle
+        .insert_source(
+            Generic::new(inotify, Interest::READ, Mode::Level),
+            move |_, inotify, data| {
+                // Safety: we don't drop the file
+                let inotify = unsafe { inotify.get_mut() };
+                if drain_events(inotify, &watched) {
+                    data.reload_config();
+                }
+                Ok(PostAction::Continue)
+            },
+        )
+        .expect("Failed to init config watcher source");
+}
+
+/// Watch `dir` for files being written or moved into it, returning the watch
+/// descriptor
+fn add_watch(fd: i32, dir: &Path) -> Option<i32> {
+    let dir_path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let wd =
+        unsafe { libc::inotify_add_watch(fd, dir_path.as_ptr(), libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO) };
+    if wd < 0 {
+        warn!(
+            dir = %dir.display(),
+            err = %io::Error::last_os_error(),
+            "Failed to watch config directory"
+        );
+        return None;
+    }
+    Some(wd)
+}
+
+/// Read all pending inotify events, returning whether any concerned one of the
+/// `watched` files
+fn drain_events(inotify: &mut File, watched: &[(i32, OsString)]) -> bool {
+    const HEADER_LEN: usize = std::mem::size_of::<libc::inotify_event>();
+
+    let mut buffer = [0u8; 4096];
+    let mut changed = false;
+
+    loop {
+        let len = match inotify.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+
+        let mut offset = 0;
+        while offset + HEADER_LEN <= len {
+            // Safety: the kernel only ever writes complete events
+            let event = unsafe {
+                std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event)
+            };
+            let name_start = offset + HEADER_LEN;
+            let name_end = (name_start + event.len as usize).min(len);
+            let name = buffer[name_start..name_end]
+                .split(|b| *b == 0)
+                .next()
+                .unwrap_or_default();
+            if watched
+                .iter()
+                .any(|(wd, file_name)| *wd == event.wd && name == file_name.as_bytes())
+            {
+                changed = true;
+            }
+            offset = name_end;
+        }
+    }
+
+    changed
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Reload `wayice.ini` and apply it.
+    ///
+    /// If the file cannot be parsed or applied the current config stays in
+    /// effect. The outcome is written to `/wayice_config_status`.
+    pub fn reload_config(&mut self) {
+        let Some(path) = config_path() else {
+            return;
+        };
+
+        let path_str = path.display().to_string();
+        match Config::load(&path).and_then(|config| self.apply_config(config)) {
+            Ok(()) => {
+                info!(path = path_str, "Reloaded config");
+                let status = json!({ "status": "ok", "path": path_str });
+                ipc_set_string("/wayice_config_status", &status.to_string());
+            }
+            Err(err) => {
+                error!(path = path_str, %err, "Failed to reload config, keeping the previous one");
+                let status = json!({ "status": "error", "path": path_str, "error": err.to_string() });
+                ipc_set_string("/wayice_config_status", &status.to_string());
+            }
+        }
+    }
+
+    /// Apply a freshly loaded config.
+    ///
+    /// Everything that can fail is done before the config is swapped, so an
+    /// error leaves the old config fully in place.
+    fn apply_config(&mut self, config: Config) -> Result<(), ConfigError> {
+        if config.keyboard != self.config.keyboard {
+            let keyboard = self.seat.get_keyboard().unwrap();
+            keyboard
+                .set_xkb_config(self, config.keyboard.xkb_config())
+                .map_err(|_| ConfigError::invalid("keyboard", "layout", config.keyboard.keymap_error()))?;
+            keyboard.change_repeat_info(config.keyboard.repeat_rate, config.keyboard.repeat_delay);
+        }
+
+        let previous = std::mem::replace(&mut self.config, config);
+
+        self.backend_data.apply_input_config(&self.config);
+
+        // the layout names may have changed even if the active index did not
+        self.active_layout = None;
+        self.ipc_shm_update_keyboard_layout();
+
+        let mode_removed = self
+            .binding_mode
+            .as_deref()
+            .is_some_and(|mode| self.config.mode(mode).is_none());
+        if mode_removed {
+            self.set_binding_mode(DEFAULT_MODE);
+        }
+
+        for output in self.space.outputs().cloned().collect::<Vec<_>>() {
+            let config = output_config(&self.config, &output);
+            let connect_only = |c: Option<&OutputConfig>| c.map(|c| (c.enabled, c.mode, c.refresh));
+            if connect_only(config) != connect_only(output_config(&previous, &output)) {
+                warn!(
+                    output = output.name(),
+                    "Mode and enabled changes only apply once the output is connected again"
+                );
+            }
+            apply_output_config(&mut self.space, &output, config);
+        }
+        self.fixup_layout(self.pointer.current_location());
+
+        self.update_decoration_themes();
+        self.reapply_window_rules();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use ini::Properties;
+use regex::Regex;
+
+use super::{
+    keybindings::parse_bindings, output::parse_pair, parse_bool, ConfigError, KeyCombo, SpawnCommand,
+};
+
+/// Decorations forced on a window by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decorations {
+    /// Server side decorations, drawn by the compositor
+    Server,
+    /// Client side decorations, drawn by the application itself
+    Client,
+}
+
+/// Where a window is stacked relative to the other windows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Stacking {
+    /// Above every window that is not always on top
+    Above,
+    #[default]
+    Normal,
+    /// Below every window that is not always below
+    Below,
+}
+
+/// The properties of a window rules are matched against.
+///
+/// Wayland windows only have an `app_id` and a `title`, X11 windows only a
+/// `title`, `class` and `instance`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowProperties {
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    pub class: Option<String>,
+    pub instance: Option<String>,
+}
+
+/// What happens to a window matched by one or more rules
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleEffects {
+    /// Name of the output to open the window on
+    pub output: Option<String>,
+    /// Position relative to the output the window is opened on
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(i32, i32)>,
+    /// Keep the window out of tiling layouts, or tile it even if it is a dialog
+    pub floating: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub decorations: Option<Decorations>,
+    pub opacity: Option<f32>,
+    /// Whether to give the window keyboard focus when it is mapped
+    pub focus: Option<bool>,
+    /// Name of the rule whose scratchpad binding shows and hides the window
+    pub scratchpad: Option<String>,
+    pub stacking: Option<Stacking>,
+    /// Show the window on every workspace of its output
+    pub sticky: Option<bool>,
+}
+
+impl RuleEffects {
+    /// Combine two sets of effects, the ones set in `other` taking precedence
+    pub(super) fn merged(&self, other: &RuleEffects) -> RuleEffects {
+        RuleEffects {
+            output: other.output.clone().or_else(|| self.output.clone()),
+            position: other.position.or(self.position),
+            size: other.size.or(self.size),
+            floating: other.floating.or(self.floating),
+            fullscreen: other.fullscreen.or(self.fullscreen),
+            decorations: other.decorations.or(self.decorations),
+            opacity: other.opacity.or(self.opacity),
+            focus: other.focus.or(self.focus),
+            scratchpad: other.scratchpad.clone().or_else(|| self.scratchpad.clone()),
+            stacking: other.stacking.or(self.stacking),
+            sticky: other.sticky.or(self.sticky),
+        }
+    }
+}
+
+/// A `[rule.<name>]` section.
+///
+/// Every pattern given has to match for the rule to apply. Patterns are
+/// regular expressions searched anywhere in the property, use `^` and `$` to
+/// match the whole value.
+#[derive(Debug, Clone)]
+pub struct WindowRule {
+    pub name: String,
+    pub app_id: Option<Regex>,
+    pub title: Option<Regex>,
+    pub class: Option<Regex>,
+    pub instance: Option<Regex>,
+    pub effects: RuleEffects,
+    /// Keys showing and hiding the matched windows as a scratchpad
+    pub scratchpad_binding: Option<Vec<KeyCombo>>,
+    /// Command started by the scratchpad binding while no window matches
+    pub scratchpad_command: Option<SpawnCommand>,
+}
+
+const RULE_KEYS: &[&str] = &[
+    "app_id",
+    "title",
+    "class",
+    "instance",
+    "output",
+    "position",
+    "size",
+    "floating",
+    "fullscreen",
+    "decorations",
+    "opacity",
+    "focus",
+    "scratchpad",
+    "scratchpad_command",
+    "stacking",
+    "sticky",
+];
+
+impl WindowRule {
+    /// Parse the section named `[rule.<name>]`, reporting invalid values in `errors`
+    pub fn from_section(name: &str, section: &Properties, errors: &mut Vec<ConfigError>) -> Self {
+        let section_name = format!("rule.{}", name);
+        let mut rule = WindowRule {
+            name: name.to_string(),
+            app_id: None,
+            title: None,
+            class: None,
+            instance: None,
+            effects: RuleEffects::default(),
+            scratchpad_binding: None,
+            scratchpad_command: None,
+        };
+        let effects = &mut rule.effects;
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "app_id" => parse_regex(value).map(|regex| rule.app_id = Some(regex)),
+                "title" => parse_regex(value).map(|regex| rule.title = Some(regex)),
+                "class" => parse_regex(value).map(|regex| rule.class = Some(regex)),
+                "instance" => parse_regex(value).map(|regex| rule.instance = Some(regex)),
+                "output" => {
+                    effects.output = Some(value.trim().to_string());
+                    Ok(())
+                }
+                "position" => parse_pair(value, ',').map(|position| effects.position = Some(position)),
+                "size" => match parse_pair(value, 'x') {
+                    Ok((w, h)) if w > 0 && h > 0 => {
+                        effects.size = Some((w, h));
+                        Ok(())
+                    }
+                    Ok(_) => Err(format!("invalid size \"{}\"", value)),
+                    Err(err) => Err(err),
+                },
+                "floating" => parse_bool(value).map(|v| effects.floating = Some(v)),
+                "fullscreen" => parse_bool(value).map(|v| effects.fullscreen = Some(v)),
+                "decorations" => match value.trim() {
+                    "ssd" | "server" => Ok(Decorations::Server),
+                    "csd" | "client" => Ok(Decorations::Client),
+                    _ => Err(format!("expected ssd or csd, got \"{}\"", value)),
+                }
+                .map(|v| effects.decorations = Some(v)),
+                "opacity" => match value.trim().parse::<f32>() {
+                    Ok(opacity) if (0.0..=1.0).contains(&opacity) => {
+                        effects.opacity = Some(opacity);
+                        Ok(())
+                    }
+                    _ => Err(format!("expected a number between 0 and 1, got \"{}\"", value)),
+                },
+                "focus" => parse_bool(value).map(|v| effects.focus = Some(v)),
+                "scratchpad" => parse_bindings(value).and_then(|bindings| {
+                    if bindings.iter().any(|(index, _)| *index > 0) {
+                        return Err("ranges are not supported for scratchpads".into());
+                    }
+                    rule.scratchpad_binding = Some(bindings.into_iter().map(|(_, combo)| combo).collect());
+                    Ok(())
+                }),
+                "scratchpad_command" => {
+                    rule.scratchpad_command = Some(SpawnCommand::shell(value.trim()));
+                    Ok(())
+                }
+                "stacking" => match value.trim() {
+                    "above" => Ok(Stacking::Above),
+                    "normal" => Ok(Stacking::Normal),
+                    "below" => Ok(Stacking::Below),
+                    _ => Err(format!("expected above, normal or below, got \"{}\"", value)),
+                }
+                .map(|v| effects.stacking = Some(v)),
+                "sticky" => parse_bool(value).map(|v| effects.sticky = Some(v)),
+                _ => Err(format!("unknown key, expected one of {}", RULE_KEYS.join(", "))),
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid(&section_name, key, message));
+            }
+        }
+
+        if rule.scratchpad_binding.is_some() {
+            effects.scratchpad = Some(rule.name.clone());
+            // scratchpads are shown above the tiles
+            effects.floating.get_or_insert(true);
+        } else if rule.scratchpad_command.is_some() {
+            errors.push(ConfigError::invalid(
+                &section_name,
+                "scratchpad_command",
+                "a scratchpad command needs a scratchpad binding",
+            ));
+        }
+
+        if effects.sticky == Some(true) {
+            // sticky windows stay put while the tiles change with the workspace
+            effects.floating.get_or_insert(true);
+        }
+
+        if rule.app_id.is_none() && rule.title.is_none() && rule.class.is_none() && rule.instance.is_none() {
+            errors.push(ConfigError::invalid(
+                &section_name,
+                "app_id",
+                "a rule needs at least one of app_id, title, class or instance",
+            ));
+        }
+
+        rule
+    }
+
+    /// Whether every pattern of the rule matches `window`.
+    ///
+    /// A pattern for a property the window does not have never matches.
+    pub fn matches(&self, window: &WindowProperties) -> bool {
+        let check = |pattern: &Option<Regex>, value: &Option<String>| match (pattern, value) {
+            (None, _) => true,
+            (Some(pattern), Some(value)) => pattern.is_match(value),
+            (Some(_), None) => false,
+        };
+        check(&self.app_id, &window.app_id)
+            && check(&self.title, &window.title)
+            && check(&self.class, &window.class)
+            && check(&self.instance, &window.instance)
+    }
+}
+
+fn parse_regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value.trim()).map_err(|err| format!("invalid pattern: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(body: &str) -> (WindowRule, Vec<String>) {
+        let ini = Ini::load_from_str(&format!("[rule.test]\n{}", body)).unwrap();
+        let mut errors = Vec::new();
+        let rule = WindowRule::from_section("test", ini.section(Some("rule.test")).unwrap(), &mut errors);
+        (rule, errors.iter().map(ToString::to_string).collect())
+    }
+
+    fn wayland(app_id: &str, title: &str) -> WindowProperties {
+        WindowProperties {
+            app_id: Some(app_id.into()),
+            title: Some(title.into()),
+            ..WindowProperties::default()
+        }
+    }
+
+    #[test]
+    fn valid_values() {
+        let (rule, errors) = parse(
+            "app_id = ^firefox$\noutput = DP-1\nposition = 10, 20\nsize = 800x600\nfloating = yes\n\
+             fullscreen = no\ndecorations = csd\nopacity = 0.9\nfocus = false\nstacking = below\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(
+            rule.effects,
+            RuleEffects {
+                output: Some("DP-1".into()),
+                position: Some((10, 20)),
+                size: Some((800, 600)),
+                floating: Some(true),
+                fullscreen: Some(false),
+                decorations: Some(Decorations::Client),
+                opacity: Some(0.9),
+                focus: Some(false),
+                scratchpad: None,
+                stacking: Some(Stacking::Below),
+                sticky: None,
+            }
+        );
+        assert!(rule.scratchpad_binding.is_none());
+    }
+
+    #[test]
+    fn patterns_match_anywhere() {
+        let (rule, _) = parse("app_id = fire\ntitle = Private\n");
+        assert!(rule.matches(&wayland("org.mozilla.firefox", "Private Browsing")));
+        assert!(!rule.matches(&wayland("org.mozilla.firefox", "Mozilla")));
+        assert!(!rule.matches(&wayland("chromium", "Private Browsing")));
+    }
+
+    #[test]
+    fn missing_property_never_matches() {
+        let (rule, _) = parse("class = ^XTerm$\n");
+        assert!(!rule.matches(&wayland("XTerm", "xterm")));
+        assert!(rule.matches(&WindowProperties {
+            class: Some("XTerm".into()),
+            instance: Some("xterm".into()),
+            ..WindowProperties::default()
+        }));
+    }
+
+    #[test]
+    fn scratchpad() {
+        let (rule, errors) =
+            parse("app_id = ^term$\nscratchpad = Logo+grave\nscratchpad_command = foot -a term\n");
+        assert!(errors.is_empty());
+        assert_eq!(
+            rule.scratchpad_binding,
+            Some(vec![KeyCombo::parse("Logo+grave").unwrap()])
+        );
+        assert_eq!(rule.scratchpad_command, Some(SpawnCommand::shell("foot -a term")));
+        assert_eq!(rule.effects.scratchpad.as_deref(), Some("test"));
+        assert_eq!(rule.effects.floating, Some(true));
+    }
+
+    #[test]
+    fn scratchpad_can_be_tiled_explicitly() {
+        let (rule, _) = parse("app_id = ^term$\nscratchpad = Logo+grave\nfloating = false\n");
+        assert_eq!(rule.effects.floating, Some(false));
+    }
+
+    #[test]
+    fn sticky_floats() {
+        let (rule, _) = parse("app_id = ^mpv$\nsticky = true\n");
+        assert_eq!(rule.effects.sticky, Some(true));
+        assert_eq!(rule.effects.floating, Some(true));
+
+        let (rule, _) = parse("app_id = ^mpv$\nsticky = false\n");
+        assert_eq!(rule.effects.floating, None);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (_, errors) = parse(
+            "title = .\nposition = 10\nsize = 0x600\nfloating = sure\ndecorations = none\nopacity = 1.5\n\
+             scratchpad = Logo+1..Logo+3\nstacking = top\nworkspace = 2\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[rule.test] position: expected two numbers separated by ',', got \"10\"",
+                "[rule.test] size: invalid size \"0x600\"",
+                "[rule.test] floating: expected true or false, got \"sure\"",
+                "[rule.test] decorations: expected ssd or csd, got \"none\"",
+                "[rule.test] opacity: expected a number between 0 and 1, got \"1.5\"",
+                "[rule.test] scratchpad: ranges are not supported for scratchpads",
+                "[rule.test] stacking: expected above, normal or below, got \"top\"",
+                "[rule.test] workspace: unknown key, expected one of app_id, title, class, instance, output, position, size, floating, fullscreen, decorations, opacity, focus, scratchpad, scratchpad_command, stacking, sticky",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let (rule, errors) = parse("app_id = (\ntitle = x\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("[rule.test] app_id: invalid pattern: "));
+        assert!(rule.app_id.is_none());
+    }
+
+    #[test]
+    fn scratchpad_command_needs_binding() {
+        let (_, errors) = parse("app_id = ^term$\nscratchpad_command = foot\n");
+        assert_eq!(
+            errors,
+            vec!["[rule.test] scratchpad_command: a scratchpad command needs a scratchpad binding"]
+        );
+    }
+
+    #[test]
+    fn rule_needs_pattern() {
+        let (_, errors) = parse("floating = true\n");
+        assert_eq!(
+            errors,
+            vec!["[rule.test] app_id: a rule needs at least one of app_id, title, class or instance"]
+        );
+    }
+
+    #[test]
+    fn merged_effects() {
+        let (first, _) = parse("app_id = .\nfloating = true\noutput = DP-1\n");
+        let (second, _) = parse("app_id = .\nfloating = false\nopacity = 0.5\n");
+        let merged = first.effects.merged(&second.effects);
+        assert_eq!(merged.floating, Some(false));
+        assert_eq!(merged.output.as_deref(), Some("DP-1"));
+        assert_eq!(merged.opacity, Some(0.5));
+    }
+}
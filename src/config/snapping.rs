@@ -0,0 +1,105 @@
+use ini::Properties;
+
+use super::{decorations::parse_color, parse_bool, ConfigError};
+
+/// Settings of the `[snapping]` section, used while moving windows
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnappingConfig {
+    /// Distance in logical pixels at which a moved window snaps to output
+    /// and window edges, 0 to disable snapping
+    pub threshold: i32,
+    /// Whether dragging a window to the left or right edge of an output tiles
+    /// it to that half, and dragging it to the top maximizes it
+    pub drop_zones: bool,
+    /// Color of the preview of the drop zone under the pointer, as RGBA
+    pub preview_color: [f32; 4],
+}
+
+impl Default for SnappingConfig {
+    fn default() -> Self {
+        SnappingConfig {
+            threshold: 12,
+            drop_zones: true,
+            preview_color: [0.2, 0.5, 0.9, 0.3],
+        }
+    }
+}
+
+impl SnappingConfig {
+    /// Parse the `[snapping]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = SnappingConfig::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "threshold" => match value.trim().parse::<i32>() {
+                    Ok(threshold) if threshold >= 0 => {
+                        config.threshold = threshold;
+                        Ok(())
+                    }
+                    _ => Err(format!("expected a number of pixels, got \"{}\"", value)),
+                },
+                "drop_zones" => parse_bool(value).map(|v| config.drop_zones = v),
+                "preview_color" => parse_color(value).map(|v| config.preview_color = v),
+                _ => Err("unknown key, expected threshold, drop_zones or preview_color".into()),
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid("snapping", key, message));
+            }
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(text: &str) -> (SnappingConfig, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let config = SnappingConfig::from_section(ini.section(Some("snapping")), &mut errors);
+        (config, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn missing_section_is_default() {
+        let (config, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(config, SnappingConfig::default());
+        assert_eq!(config.threshold, 12);
+        assert!(config.drop_zones);
+    }
+
+    #[test]
+    fn valid_values() {
+        let (config, errors) =
+            parse("[snapping]\nthreshold = 0\ndrop_zones = off\npreview_color = #ff000080\n");
+        assert!(errors.is_empty());
+        assert_eq!(config.threshold, 0);
+        assert!(!config.drop_zones);
+        assert_eq!(config.preview_color, [1.0, 0.0, 0.0, 128.0 / 255.0]);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) =
+            parse("[snapping]\nthreshold = -1\ndrop_zones = 2\npreview_color = blue\nmagnet = true\n");
+        assert_eq!(
+            errors,
+            vec![
+                "[snapping] threshold: expected a number of pixels, got \"-1\"",
+                "[snapping] drop_zones: expected true or false, got \"2\"",
+                "[snapping] preview_color: expected a color like #rrggbb or #rrggbbaa, got \"blue\"",
+                "[snapping] magnet: unknown key, expected threshold, drop_zones or preview_color",
+            ]
+        );
+        assert_eq!(config, SnappingConfig::default());
+    }
+}
@@ -0,0 +1,197 @@
+use ini::Properties;
+
+use super::ConfigError;
+
+/// How the windows of a workspace are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutKind {
+    /// Windows stay where they are placed
+    Floating,
+    /// One large master tile, the others stacked next to it
+    MasterStack,
+    /// Every tile splits the remaining space in two, alternating directions
+    Dwindle,
+    /// Every tile covers the whole area, the focused one on top
+    Monocle,
+    /// Columns of tiles on a strip scrolled to keep the focused column in view
+    Scrolling,
+}
+
+impl LayoutKind {
+    /// The layouts in the order `cycle_layout` goes through them
+    pub const ALL: [LayoutKind; 5] = [
+        LayoutKind::Floating,
+        LayoutKind::MasterStack,
+        LayoutKind::Dwindle,
+        LayoutKind::Monocle,
+        LayoutKind::Scrolling,
+    ];
+
+    pub(super) fn parse(value: &str) -> Result<LayoutKind, String> {
+        match value.trim() {
+            "floating" => Ok(LayoutKind::Floating),
+            "master-stack" => Ok(LayoutKind::MasterStack),
+            "dwindle" => Ok(LayoutKind::Dwindle),
+            "monocle" => Ok(LayoutKind::Monocle),
+            "scrolling" => Ok(LayoutKind::Scrolling),
+            _ => Err(format!(
+                "expected floating, master-stack, dwindle, monocle or scrolling, got \"{}\"",
+                value
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutKind::Floating => "floating",
+            LayoutKind::MasterStack => "master-stack",
+            LayoutKind::Dwindle => "dwindle",
+            LayoutKind::Monocle => "monocle",
+            LayoutKind::Scrolling => "scrolling",
+        }
+    }
+}
+
+/// Settings of the `[tiling]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilingConfig {
+    /// Layout of workspaces that don't set their own
+    pub layout: LayoutKind,
+    /// Space between tiles in logical pixels
+    pub gaps: i32,
+    /// Space between tiles and the edges of the output
+    pub outer_gaps: i32,
+    /// Share of the width the master tile takes, between 0.1 and 0.9
+    pub master_ratio: f64,
+    /// Share of the width of the output new columns of the scrolling layout
+    /// take, between 0.1 and 1
+    pub column_width: f64,
+}
+
+impl Default for TilingConfig {
+    fn default() -> Self {
+        TilingConfig {
+            layout: LayoutKind::Floating,
+            gaps: 8,
+            outer_gaps: 8,
+            master_ratio: 0.55,
+            column_width: 0.5,
+        }
+    }
+}
+
+impl TilingConfig {
+    /// Parse the `[tiling]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = TilingConfig::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            let result = match key {
+                "layout" => LayoutKind::parse(value).map(|layout| config.layout = layout),
+                "gaps" => parse_gap(value).map(|gaps| config.gaps = gaps),
+                "outer_gaps" => parse_gap(value).map(|gaps| config.outer_gaps = gaps),
+                "master_ratio" => match value.trim().parse::<f64>() {
+                    Ok(ratio) if (0.1..=0.9).contains(&ratio) => {
+                        config.master_ratio = ratio;
+                        Ok(())
+                    }
+                    _ => Err(format!(
+                        "expected a number between 0.1 and 0.9, got \"{}\"",
+                        value
+                    )),
+                },
+                "column_width" => match value.trim().parse::<f64>() {
+                    Ok(width) if (0.1..=1.0).contains(&width) => {
+                        config.column_width = width;
+                        Ok(())
+                    }
+                    _ => Err(format!("expected a number between 0.1 and 1, got \"{}\"", value)),
+                },
+                _ => {
+                    Err("unknown key, expected layout, gaps, outer_gaps, master_ratio or column_width".into())
+                }
+            };
+            if let Err(message) = result {
+                errors.push(ConfigError::invalid("tiling", key, message));
+            }
+        }
+
+        config
+    }
+}
+
+fn parse_gap(value: &str) -> Result<i32, String> {
+    match value.trim().parse::<i32>() {
+        Ok(gap) if gap >= 0 => Ok(gap),
+        _ => Err(format!("expected a number of pixels, got \"{}\"", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    fn parse(text: &str) -> (TilingConfig, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let config = TilingConfig::from_section(ini.section(Some("tiling")), &mut errors);
+        (config, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn missing_section_is_default() {
+        let (config, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(config, TilingConfig::default());
+        assert_eq!(config.layout, LayoutKind::Floating);
+        assert_eq!((config.gaps, config.outer_gaps), (8, 8));
+    }
+
+    #[test]
+    fn valid_values() {
+        let (config, errors) =
+            parse("[tiling]\nlayout = master-stack\ngaps = 0\nouter_gaps = 16\nmaster_ratio = 0.6\ncolumn_width = 1\n");
+        assert!(errors.is_empty());
+        assert_eq!(
+            config,
+            TilingConfig {
+                layout: LayoutKind::MasterStack,
+                gaps: 0,
+                outer_gaps: 16,
+                master_ratio: 0.6,
+                column_width: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn layout_names_round_trip() {
+        for layout in LayoutKind::ALL {
+            assert_eq!(LayoutKind::parse(layout.name()), Ok(layout));
+        }
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) = parse(
+            "[tiling]\nlayout = spiral\ngaps = -4\nouter_gaps = wide\nmaster_ratio = 0.95\ncolumn_width = 0\nborder = 2\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[tiling] layout: expected floating, master-stack, dwindle, monocle or scrolling, got \"spiral\"",
+                "[tiling] gaps: expected a number of pixels, got \"-4\"",
+                "[tiling] outer_gaps: expected a number of pixels, got \"wide\"",
+                "[tiling] master_ratio: expected a number between 0.1 and 0.9, got \"0.95\"",
+                "[tiling] column_width: expected a number between 0.1 and 1, got \"0\"",
+                "[tiling] border: unknown key, expected layout, gaps, outer_gaps, master_ratio or column_width",
+            ]
+        );
+        assert_eq!(config, TilingConfig::default());
+    }
+}
@@ -0,0 +1,199 @@
+use ini::Properties;
+
+use super::{split_list, tiling::LayoutKind, ConfigError};
+
+/// Settings of the `[workspaces]` and `[workspace.<name>]` sections
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceConfig {
+    /// Workspace names, in the order `workspace_switch` ranges refer to them
+    pub names: Vec<String>,
+    /// Outputs workspaces are opened on, by workspace name
+    pub outputs: Vec<(String, String)>,
+    /// Layouts of workspaces that don't use the one of `[tiling]`
+    pub layouts: Vec<(String, LayoutKind)>,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        WorkspaceConfig {
+            names: (1..=9).map(|n| n.to_string()).collect(),
+            outputs: Vec::new(),
+            layouts: Vec::new(),
+        }
+    }
+}
+
+impl WorkspaceConfig {
+    /// Parse the `[workspaces]` section, reporting invalid values in `errors`
+    pub fn from_section(section: Option<&Properties>, errors: &mut Vec<ConfigError>) -> Self {
+        let mut config = WorkspaceConfig::default();
+        let Some(section) = section else {
+            return config;
+        };
+
+        for (key, value) in section.iter() {
+            match key {
+                "names" => {
+                    let names = split_list(value);
+                    if names.is_empty() {
+                        errors.push(ConfigError::invalid(
+                            "workspaces",
+                            key,
+                            "no workspace names given",
+                        ));
+                    } else {
+                        config.names = names;
+                    }
+                }
+                _ => errors.push(ConfigError::invalid(
+                    "workspaces",
+                    key,
+                    "unknown key, expected names",
+                )),
+            }
+        }
+
+        config
+    }
+
+    /// Add the settings of the section named `[workspace.<name>]`, declaring
+    /// the workspace if `names` does not list it
+    pub(super) fn add_workspace(&mut self, name: &str, section: &Properties, errors: &mut Vec<ConfigError>) {
+        let section_name = format!("workspace.{}", name);
+        for (key, value) in section.iter() {
+            match key {
+                "output" if value.trim().is_empty() => {
+                    errors.push(ConfigError::invalid(&section_name, key, "empty output name"));
+                }
+                "output" => self.outputs.push((name.to_string(), value.trim().to_string())),
+                "layout" => match LayoutKind::parse(value) {
+                    Ok(layout) => self.layouts.push((name.to_string(), layout)),
+                    Err(message) => errors.push(ConfigError::invalid(&section_name, key, message)),
+                },
+                _ => errors.push(ConfigError::invalid(
+                    &section_name,
+                    key,
+                    "unknown key, expected output or layout",
+                )),
+            }
+        }
+        if !self.names.iter().any(|n| n == name) {
+            self.names.push(name.to_string());
+        }
+    }
+
+    /// Name of the workspace at `index` of a `workspace_switch` range.
+    ///
+    /// Indices past the configured names are numbered.
+    pub fn name_for(&self, index: usize) -> String {
+        self.names
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| (index + 1).to_string())
+    }
+
+    /// Whether the workspace is named in the config, and so kept while empty
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+
+    /// Output the workspace is opened on
+    pub fn output_for(&self, name: &str) -> Option<&str> {
+        self.outputs
+            .iter()
+            .rev()
+            .find(|(workspace, _)| workspace == name)
+            .map(|(_, output)| output.as_str())
+    }
+
+    /// Layout configured for the workspace, if it has its own
+    pub fn layout_for(&self, name: &str) -> Option<LayoutKind> {
+        self.layouts
+            .iter()
+            .rev()
+            .find(|(workspace, _)| workspace == name)
+            .map(|(_, layout)| *layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ini::Ini;
+
+    use super::*;
+
+    /// Parse `[workspaces]` and the `[workspace.<name>]` sections of `text`
+    /// the way the config does
+    fn parse(text: &str) -> (WorkspaceConfig, Vec<String>) {
+        let ini = Ini::load_from_str(text).unwrap();
+        let mut errors = Vec::new();
+        let mut config = WorkspaceConfig::from_section(ini.section(Some("workspaces")), &mut errors);
+        for (name, section) in ini.iter() {
+            if let Some(workspace) = name.and_then(|n| n.strip_prefix("workspace.")) {
+                config.add_workspace(workspace, section, &mut errors);
+            }
+        }
+        (config, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn missing_section_is_default() {
+        let (config, errors) = parse("");
+        assert!(errors.is_empty());
+        assert_eq!(config.names, ["1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+        assert_eq!(config.output_for("1"), None);
+        assert_eq!(config.layout_for("1"), None);
+    }
+
+    #[test]
+    fn names() {
+        let (config, errors) = parse("[workspaces]\nnames = web, \"chat, mail\", code\n");
+        assert!(errors.is_empty());
+        assert_eq!(config.names, ["web", "chat, mail", "code"]);
+        assert_eq!(config.name_for(1), "chat, mail");
+        // indices past the names are numbered
+        assert_eq!(config.name_for(4), "5");
+        assert!(config.is_declared("code"));
+        assert!(!config.is_declared("1"));
+    }
+
+    #[test]
+    fn workspace_sections() {
+        let (config, errors) = parse(
+            "[workspaces]\nnames = web, code\n\n[workspace.code]\noutput = DP-1\nlayout = dwindle\n\n\
+             [workspace.music]\noutput = HDMI-A-1\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(config.output_for("code"), Some("DP-1"));
+        assert_eq!(config.layout_for("code"), Some(LayoutKind::Dwindle));
+        assert_eq!(config.output_for("web"), None);
+        // undeclared workspaces are appended to the names
+        assert_eq!(config.names, ["web", "code", "music"]);
+    }
+
+    #[test]
+    fn last_setting_wins() {
+        let (config, _) = parse("[workspace.1]\nlayout = monocle\n[workspace.1]\nlayout = master-stack\n");
+        assert_eq!(config.layout_for("1"), Some(LayoutKind::MasterStack));
+        assert_eq!(config.names.len(), 9);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) = parse(
+            "[workspaces]\nnames = ,\ncount = 4\n\n[workspace.web]\noutput =\nlayout = grid\ngaps = 0\n",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "[workspaces] names: no workspace names given",
+                "[workspaces] count: unknown key, expected names",
+                "[workspace.web] output: empty output name",
+                "[workspace.web] layout: expected floating, master-stack, dwindle, monocle or scrolling, got \"grid\"",
+                "[workspace.web] gaps: unknown key, expected output or layout",
+            ]
+        );
+        assert_eq!(config.output_for("web"), None);
+        assert_eq!(config.layout_for("web"), None);
+    }
+}
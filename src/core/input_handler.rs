@@ -1,12 +1,14 @@
-use std::{convert::TryInto, process::Command, sync::atomic::Ordering};
+use std::{convert::TryInto, sync::atomic::Ordering};
 
 use crate::core::state::WayiceState;
-use crate::{core::focus::PointerFocusTarget, shell::FullscreenSurface};
-
-use ini::Ini;
-use std::env;
-use std::path::PathBuf;
+use crate::{
+    config::{KeyAction, Modifiers, MouseAction, DEFAULT_MODE},
+    core::focus::PointerFocusTarget,
+    shell::{FullscreenSurface, Overview, PressSource},
+};
 
+#[cfg(feature = "udev")]
+use crate::config::{Gesture, GestureType};
 #[cfg(feature = "udev")]
 use crate::platform::udev::UdevData;
 #[cfg(feature = "udev")]
@@ -19,8 +21,8 @@ use smithay::{
     },
     desktop::{layer_map_for_output, WindowSurfaceType},
     input::{
-        keyboard::{keysyms as xkb, FilterResult, Keysym, ModifiersState},
-        pointer::{AxisFrame, ButtonEvent, MotionEvent},
+        keyboard::{FilterResult, Keysym},
+        pointer::{AxisFrame, ButtonEvent, GrabStartData as PointerGrabStartData, MotionEvent},
     },
     output::Scale,
     reexports::{
@@ -70,6 +72,89 @@ use smithay::{
     },
 };
 
+/// How far fingers have to travel for a swipe to count, in the units libinput
+/// reports deltas in
+#[cfg(feature = "udev")]
+const SWIPE_DISTANCE: f64 = 50.0;
+/// How much a pinch has to change the finger spread to count
+#[cfg(feature = "udev")]
+const PINCH_SCALE: f64 = 0.2;
+
+/// A touchpad gesture event held back from the client while it is unknown
+/// whether the gesture is bound
+#[cfg(feature = "udev")]
+#[derive(Debug)]
+enum HeldGestureEvent {
+    SwipeBegin(GestureSwipeBeginEvent),
+    SwipeUpdate(GestureSwipeUpdateEvent),
+    PinchBegin(GesturePinchBeginEvent),
+    PinchUpdate(GesturePinchUpdateEvent),
+    HoldBegin(GestureHoldBeginEvent),
+}
+
+/// A touchpad gesture that might be bound in `[gestures]`, tracked from begin
+/// to end instead of being forwarded to the client.
+///
+/// Its events are held back until its direction is known, and handed to the
+/// client if no binding matches it.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct BoundGesture {
+    fingers: u32,
+    delta: Point<f64, Logical>,
+    scale: f64,
+    /// The gesture once it is known to be bound
+    #[cfg(feature = "udev")]
+    recognized: Option<Gesture>,
+    #[cfg(feature = "udev")]
+    held: Vec<HeldGestureEvent>,
+}
+
+#[cfg(feature = "udev")]
+impl BoundGesture {
+    fn new(fingers: u32, begin: HeldGestureEvent) -> Self {
+        BoundGesture {
+            fingers,
+            delta: Point::default(),
+            scale: 1.0,
+            recognized: None,
+            held: vec![begin],
+        }
+    }
+
+    /// Hold back an update of a gesture not recognized yet
+    fn hold(&mut self, event: HeldGestureEvent) {
+        if self.recognized.is_none() {
+            self.held.push(event);
+        }
+    }
+
+    /// Direction of a swipe, once the fingers moved far enough
+    fn swipe(&self) -> Option<Gesture> {
+        let Point { x, y, .. } = self.delta;
+        if x.abs().max(y.abs()) < SWIPE_DISTANCE {
+            return None;
+        }
+        Some(match (x.abs() > y.abs(), x > 0.0, y > 0.0) {
+            (true, true, _) => Gesture::SwipeRight,
+            (true, false, _) => Gesture::SwipeLeft,
+            (false, _, true) => Gesture::SwipeDown,
+            (false, _, false) => Gesture::SwipeUp,
+        })
+    }
+
+    /// Direction of a pinch, once the spread changed enough
+    fn pinch(&self) -> Option<Gesture> {
+        if self.scale < 1.0 - PINCH_SCALE {
+            Some(Gesture::PinchIn)
+        } else if self.scale > 1.0 + PINCH_SCALE {
+            Some(Gesture::PinchOut)
+        } else {
+            None
+        }
+    }
+}
+
 impl<BackendData: Backend> WayiceState<BackendData> {
     fn process_common_key_action(&mut self, action: KeyAction) {
         match action {
@@ -80,31 +165,9 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                 self.running.store(false, Ordering::SeqCst);
             }
 
-            KeyAction::Run(cmd) => {
-                info!(cmd, "Starting program");
-
-                if let Err(e) = Command::new(&cmd)
-                    .envs(
-                        self.socket_name
-                            .clone()
-                            .map(|v| ("WAYLAND_DISPLAY", v))
-                            .into_iter()
-                            .chain(
-                                #[cfg(feature = "xwayland")]
-                                self.xdisplay.map(|v| ("DISPLAY", format!(":{}", v))),
-                                #[cfg(not(feature = "xwayland"))]
-                                None,
-                            ),
-                    )
-                    .spawn()
-                {
-                    error!(cmd, err = %e, "Failed to start program");
-                }
-            }
+            KeyAction::Run(spawn) => self.spawn_child(&spawn, false),
 
-            KeyAction::TogglePreview => {
-                self.show_window_preview = !self.show_window_preview;
-            }
+            KeyAction::TogglePreview => self.toggle_overview(),
 
             KeyAction::ToggleDecorations => {
                 for element in self.space.elements() {
@@ -131,6 +194,86 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                 }
             }
 
+            KeyAction::NextLayout => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.with_xkb_state(self, |mut context| context.cycle_next_layout());
+                self.ipc_shm_update_keyboard_layout();
+            }
+
+            KeyAction::PrevLayout => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.with_xkb_state(self, |mut context| context.cycle_prev_layout());
+                self.ipc_shm_update_keyboard_layout();
+            }
+
+            KeyAction::EnterMode(mode) => self.set_binding_mode(&mode),
+
+            KeyAction::ExitMode => self.set_binding_mode(DEFAULT_MODE),
+
+            KeyAction::ResizeWindow(width, height) => self.resize_focused_window((width, height).into()),
+
+            KeyAction::SwitchWorkspace(index) => self.switch_workspace_index(index),
+
+            KeyAction::MoveToWorkspace(index) => self.move_window_to_workspace_index(index),
+
+            KeyAction::NextWorkspace => self.cycle_workspace(true),
+
+            KeyAction::PrevWorkspace => self.cycle_workspace(false),
+
+            KeyAction::WorkspaceToNextOutput => self.move_workspace_to_next_output(),
+
+            KeyAction::ToggleFloating => self.toggle_floating(),
+
+            KeyAction::CycleLayout => self.cycle_layout(),
+
+            KeyAction::SwapTile(offset) => self.swap_tile(offset),
+
+            KeyAction::PromoteTile => self.promote_tile(),
+
+            KeyAction::ResizeMaster(delta) => self.resize_master(delta),
+
+            KeyAction::FocusColumn(offset) => self.focus_column(offset),
+
+            KeyAction::MoveColumn(offset) => self.move_column(offset),
+
+            KeyAction::ConsumeWindow => self.consume_window(),
+
+            KeyAction::ExpelWindow => self.expel_window(),
+
+            KeyAction::FocusDirection(direction) => self.focus_direction(direction),
+
+            KeyAction::SwapDirection(direction) => self.swap_direction(direction),
+
+            KeyAction::FocusOutput(direction) => self.focus_output_direction(direction),
+
+            KeyAction::SwitchWindow(same_app) => self.switch_window(same_app),
+
+            KeyAction::MinimizeWindow => {
+                if let Some(window) = self.focused_window() {
+                    self.minimize_window(&window);
+                }
+            }
+
+            KeyAction::RestoreWindow => self.restore_minimized(None),
+
+            KeyAction::MoveToScratchpad => self.move_to_scratchpad(),
+
+            KeyAction::ToggleScratchpad(name) => self.toggle_scratchpad(name.as_deref()),
+
+            KeyAction::ToggleAlwaysOnTop => self.toggle_always_on_top(),
+
+            KeyAction::ToggleAlwaysBelow => self.toggle_always_below(),
+
+            KeyAction::ToggleSticky => self.toggle_sticky(),
+
+            KeyAction::GroupWindow(direction) => self.group_direction(direction),
+
+            KeyAction::UngroupWindow => self.ungroup_window(),
+
+            KeyAction::FocusTab(offset) => self.focus_tab(offset),
+
+            KeyAction::ToggleGroupLayout => self.toggle_group_layout(),
+
             _ => unreachable!(
                 "Common key action handler encountered backend specific action {:?}",
                 action
@@ -138,6 +281,20 @@ impl<BackendData: Backend> WayiceState<BackendData> {
         }
     }
 
+    /// Switch the keyboard to the bindings of `mode`
+    pub fn set_binding_mode(&mut self, mode: &str) {
+        let mode = (mode != DEFAULT_MODE).then(|| mode.to_string());
+        if self.binding_mode == mode {
+            return;
+        }
+        info!(
+            mode = mode.as_deref().unwrap_or(DEFAULT_MODE),
+            "Entering binding mode"
+        );
+        self.binding_mode = mode;
+        self.ipc_shm_update_binding_mode();
+    }
+
     fn keyboard_key_to_action<B: InputBackend>(&mut self, evt: B::KeyboardKeyEvent) -> KeyAction {
         let keycode = evt.key_code();
         let state = evt.state();
@@ -179,9 +336,13 @@ impl<BackendData: Backend> WayiceState<BackendData> {
             .map(|inhibitor| inhibitor.is_active())
             .unwrap_or(false);
 
+        let mut finish_switch = false;
+        let mut overview_key = None;
+        let mut layout = None;
         let action = keyboard
-            .input(self, keycode, state, serial, time, |_, modifiers, handle| {
+            .input(self, keycode, state, serial, time, |data, modifiers, handle| {
                 let keysym = handle.modified_sym();
+                layout = Some(modifiers.serialized.layout_effective);
 
                 debug!(
                     ?state,
@@ -190,6 +351,28 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                     "keysym"
                 );
 
+                // the window switcher closes once its modifiers are released,
+                // or is cancelled with Escape
+                if let Some(switcher) = data.switcher.as_ref() {
+                    if !switcher.holds(Modifiers::from(modifiers)) {
+                        finish_switch = true;
+                    } else if matches!(state, KeyState::Pressed) && keysym == Keysym::Escape {
+                        data.cancel_switch();
+                        suppressed_keys.push(keysym);
+                        return FilterResult::Intercept(KeyAction::None);
+                    }
+                }
+
+                // the open overview takes the arrow keys, Enter and Escape
+                if matches!(state, KeyState::Pressed)
+                    && Overview::handles(keysym)
+                    && data.overview.as_ref().is_some_and(|o| o.is_open())
+                {
+                    overview_key = Some(keysym);
+                    suppressed_keys.push(keysym);
+                    return FilterResult::Intercept(KeyAction::None);
+                }
+
                 // If the key is pressed and triggered a action
                 // we will not forward the key to the client.
                 // Additionally add the key to the suppressed keys
@@ -197,7 +380,10 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                 // should be forwarded to the client or not.
                 if let KeyState::Pressed = state {
                     if !inhibited {
-                        let action = process_keyboard_shortcut(*modifiers, keysym);
+                        let action = data
+                            .config
+                            .keybindings_for(data.binding_mode.as_deref())
+                            .action_for(Modifiers::from(modifiers), keysym);
 
                         if action.is_some() {
                             suppressed_keys.push(keysym);
@@ -222,6 +408,16 @@ impl<BackendData: Backend> WayiceState<BackendData> {
             .unwrap_or(KeyAction::None);
 
         self.suppressed_keys = suppressed_keys;
+        if finish_switch {
+            self.finish_switch();
+        }
+        if let Some(keysym) = overview_key {
+            self.overview_key(keysym);
+        }
+        // xkb options like grp:alt_shift_toggle switch layouts on their own
+        if layout.is_some() && layout != self.active_layout {
+            self.ipc_shm_update_keyboard_layout();
+        }
         action
     }
 
@@ -231,8 +427,19 @@ impl<BackendData: Backend> WayiceState<BackendData> {
 
         let state = wl_pointer::ButtonState::from(evt.state());
 
-        if wl_pointer::ButtonState::Pressed == state {
+        // the open overview covers the windows, which the button is still
+        // sent to without a focus to keep the pressed buttons in sync
+        let in_overview = match state {
+            wl_pointer::ButtonState::Pressed => {
+                self.overview_press(PressSource::Button(button), self.pointer.current_location())
+            }
+            _ => self.overview_release(PressSource::Button(button)),
+        };
+        if wl_pointer::ButtonState::Pressed == state && !in_overview {
             self.update_keyboard_focus(self.pointer.current_location(), serial);
+            if !self.pointer.is_grabbed() {
+                self.process_mouse_binding(button, serial);
+            }
         };
         let pointer = self.pointer.clone();
         pointer.button(
@@ -247,6 +454,62 @@ impl<BackendData: Backend> WayiceState<BackendData> {
         pointer.frame(self);
     }
 
+    /// Start the move or resize bound to pressing `button`, if any.
+    ///
+    /// The grab clears the pointer focus, so the press itself never reaches
+    /// the client under the pointer.
+    fn process_mouse_binding(&mut self, button: u32, serial: Serial) {
+        let modifiers = Modifiers::from(&self.seat.get_keyboard().unwrap().modifier_state());
+        let Some(action) = self.config.mouse_bindings.action_for(modifiers, button) else {
+            return;
+        };
+        let location = self.pointer.current_location();
+        // panels and other layer surfaces above the windows take the press
+        if let Some(output) = self.space.output_under(location).next() {
+            let output_geo = self.space.output_geometry(output).unwrap();
+            let layers = layer_map_for_output(output);
+            let on_layer = layers
+                .layer_under(WlrLayer::Overlay, location)
+                .or_else(|| layers.layer_under(WlrLayer::Top, location))
+                .is_some_and(|layer| {
+                    let layer_loc = layers.layer_geometry(layer).unwrap().loc;
+                    layer
+                        .surface_under(
+                            location - output_geo.loc.to_f64() - layer_loc.to_f64(),
+                            WindowSurfaceType::ALL,
+                        )
+                        .is_some()
+                });
+            if on_layer {
+                return;
+            }
+        }
+        let Some(window) = self.space.element_under(location).map(|(w, _)| w.clone()) else {
+            return;
+        };
+        // fullscreen windows stay in place
+        let fullscreen = self.space.outputs().any(|output| {
+            output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .and_then(|f| f.get())
+                .is_some_and(|w| w == window)
+        });
+        if fullscreen {
+            return;
+        }
+
+        let start_data = PointerGrabStartData {
+            focus: None,
+            button,
+            location,
+        };
+        match action {
+            MouseAction::Move => self.start_pointer_move(window, start_data, serial),
+            MouseAction::Resize => self.start_pointer_resize(window, start_data, serial),
+        }
+    }
+
     fn update_keyboard_focus(&mut self, location: Point<f64, Logical>, serial: Serial) {
         let keyboard = self.seat.get_keyboard().unwrap();
         let touch = self.seat.get_touch();
@@ -304,12 +567,7 @@ impl<BackendData: Backend> WayiceState<BackendData> {
             }
 
             if let Some((window, _)) = self.space.element_under(location).map(|(w, p)| (w.clone(), p)) {
-                self.space.raise_element(&window, true);
-                #[cfg(feature = "xwayland")]
-                if let Some(surface) = window.0.x11_surface() {
-                    self.xwm.as_mut().unwrap().raise_window(surface).unwrap();
-                }
-                keyboard.set_focus(self, Some(window.into()), serial);
+                self.focus_window(&window, serial);
                 return;
             }
 
@@ -339,6 +597,10 @@ impl<BackendData: Backend> WayiceState<BackendData> {
         &self,
         pos: Point<f64, Logical>,
     ) -> Option<(PointerFocusTarget, Point<f64, Logical>)> {
+        // the open overview takes the pointer for itself
+        if self.overview.as_ref().is_some_and(|o| o.is_open()) {
+            return None;
+        }
         let output = self.space.outputs().find(|o| {
             let geometry = self.space.output_geometry(o).unwrap();
             geometry.contains(pos.to_i32_round())
@@ -460,7 +722,7 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                     let new_scale = current_scale + 0.25;
                     output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
 
-                    crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
+                    self.fixup_layout(self.pointer.current_location());
                     self.backend_data.reset_buffers(&output);
                 }
 
@@ -476,7 +738,7 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                     let new_scale = f64::max(1.0, current_scale - 0.25);
                     output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
 
-                    crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
+                    self.fixup_layout(self.pointer.current_location());
                     self.backend_data.reset_buffers(&output);
                 }
 
@@ -501,7 +763,7 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                     };
                     tracing::info!(?current_transform, ?new_transform, output = ?output.name(), "changing output transform");
                     output.change_current_state(None, Some(new_transform), None, None);
-                    crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
+                    self.fixup_layout(self.pointer.current_location());
                     self.backend_data.reset_buffers(&output);
                 }
 
@@ -510,7 +772,41 @@ impl<BackendData: Backend> WayiceState<BackendData> {
                     | KeyAction::Quit
                     | KeyAction::Run(_)
                     | KeyAction::TogglePreview
-                    | KeyAction::ToggleDecorations => self.process_common_key_action(action),
+                    | KeyAction::ToggleDecorations
+                    | KeyAction::NextLayout
+                    | KeyAction::PrevLayout
+                    | KeyAction::EnterMode(_)
+                    | KeyAction::ExitMode
+                    | KeyAction::ResizeWindow(..)
+                    | KeyAction::SwitchWorkspace(_)
+                    | KeyAction::MoveToWorkspace(_)
+                    | KeyAction::NextWorkspace
+                    | KeyAction::PrevWorkspace
+                    | KeyAction::WorkspaceToNextOutput
+                    | KeyAction::ToggleFloating
+                    | KeyAction::CycleLayout
+                    | KeyAction::SwapTile(_)
+                    | KeyAction::PromoteTile
+                    | KeyAction::ResizeMaster(_)
+                    | KeyAction::FocusColumn(_)
+                    | KeyAction::MoveColumn(_)
+                    | KeyAction::ConsumeWindow
+                    | KeyAction::ExpelWindow
+                    | KeyAction::FocusDirection(_)
+                    | KeyAction::SwapDirection(_)
+                    | KeyAction::FocusOutput(_)
+                    | KeyAction::SwitchWindow(_)
+                    | KeyAction::MinimizeWindow
+                    | KeyAction::RestoreWindow
+                    | KeyAction::MoveToScratchpad
+                    | KeyAction::ToggleScratchpad(_)
+                    | KeyAction::ToggleAlwaysOnTop
+                    | KeyAction::ToggleAlwaysBelow
+                    | KeyAction::ToggleSticky
+                    | KeyAction::GroupWindow(_)
+                    | KeyAction::UngroupWindow
+                    | KeyAction::FocusTab(_)
+                    | KeyAction::ToggleGroupLayout => self.process_common_key_action(action),
 
                     _ => tracing::warn!(
                         ?action,
@@ -557,6 +853,7 @@ impl<BackendData: Backend> WayiceState<BackendData> {
             },
         );
         pointer.frame(self);
+        self.overview_motion(None, pos);
     }
 
     pub fn release_all_keys(&mut self) {
@@ -576,160 +873,201 @@ impl<BackendData: Backend> WayiceState<BackendData> {
 
 #[cfg(feature = "udev")]
 impl WayiceState<UdevData> {
-    pub fn process_input_event<B: InputBackend>(&mut self, dh: &DisplayHandle, event: InputEvent<B>) {
-        match event {
-            InputEvent::Keyboard { event, .. } => match self.keyboard_key_to_action::<B>(event) {
-                #[cfg(feature = "udev")]
-                KeyAction::VtSwitch(vt) => {
-                    info!(to = vt, "Trying to switch vt");
-                    if let Err(err) = self.backend_data.session.change_vt(vt) {
-                        error!(vt, "Error switching vt: {}", err);
-                    }
-                }
-                KeyAction::Screen(num) => {
-                    let geometry = self
-                        .space
-                        .outputs()
-                        .nth(num)
-                        .map(|o| self.space.output_geometry(o).unwrap());
-
-                    if let Some(geometry) = geometry {
-                        let x = geometry.loc.x as f64 + geometry.size.w as f64 / 2.0;
-                        let y = geometry.size.h as f64 / 2.0;
-                        let location = (x, y).into();
-                        let pointer = self.pointer.clone();
-                        let under = self.surface_under(location);
-                        pointer.motion(
-                            self,
-                            under,
-                            &MotionEvent {
-                                location,
-                                serial: SCOUNTER.next_serial(),
-                                time: self.clock.now().as_millis(),
-                            },
-                        );
-                        pointer.frame(self);
-                    }
-                }
-                KeyAction::ScaleUp => {
-                    let pos = self.pointer.current_location().to_i32_round();
-                    let output = self
-                        .space
-                        .outputs()
-                        .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
-                        .cloned();
-
-                    if let Some(output) = output {
-                        let (output_location, scale) = (
-                            self.space.output_geometry(&output).unwrap().loc,
-                            output.current_scale().fractional_scale(),
-                        );
-                        let new_scale = scale + 0.25;
-                        output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
-
-                        let rescale = scale / new_scale;
-                        let output_location = output_location.to_f64();
-                        let mut pointer_output_location = self.pointer.current_location() - output_location;
-                        pointer_output_location.x *= rescale;
-                        pointer_output_location.y *= rescale;
-                        let pointer_location = output_location + pointer_output_location;
-
-                        crate::shell::fixup_positions(&mut self.space, pointer_location);
-                        let pointer = self.pointer.clone();
-                        let under = self.surface_under(pointer_location);
-                        pointer.motion(
-                            self,
-                            under,
-                            &MotionEvent {
-                                location: pointer_location,
-                                serial: SCOUNTER.next_serial(),
-                                time: self.clock.now().as_millis(),
-                            },
-                        );
-                        pointer.frame(self);
-                        self.backend_data.reset_buffers(&output);
-                    }
+    fn process_key_action(&mut self, action: KeyAction) {
+        match action {
+            #[cfg(feature = "udev")]
+            KeyAction::VtSwitch(vt) => {
+                info!(to = vt, "Trying to switch vt");
+                if let Err(err) = self.backend_data.session.change_vt(vt) {
+                    error!(vt, "Error switching vt: {}", err);
                 }
-                KeyAction::ScaleDown => {
-                    let pos = self.pointer.current_location().to_i32_round();
-                    let output = self
-                        .space
-                        .outputs()
-                        .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
-                        .cloned();
-
-                    if let Some(output) = output {
-                        let (output_location, scale) = (
-                            self.space.output_geometry(&output).unwrap().loc,
-                            output.current_scale().fractional_scale(),
-                        );
-                        let new_scale = f64::max(1.0, scale - 0.25);
-                        output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
-
-                        let rescale = scale / new_scale;
-                        let output_location = output_location.to_f64();
-                        let mut pointer_output_location = self.pointer.current_location() - output_location;
-                        pointer_output_location.x *= rescale;
-                        pointer_output_location.y *= rescale;
-                        let pointer_location = output_location + pointer_output_location;
-
-                        crate::shell::fixup_positions(&mut self.space, pointer_location);
-                        let pointer = self.pointer.clone();
-                        let under = self.surface_under(pointer_location);
-                        pointer.motion(
-                            self,
-                            under,
-                            &MotionEvent {
-                                location: pointer_location,
-                                serial: SCOUNTER.next_serial(),
-                                time: self.clock.now().as_millis(),
-                            },
-                        );
-                        pointer.frame(self);
-                        self.backend_data.reset_buffers(&output);
-                    }
+            }
+            KeyAction::Screen(num) => {
+                let geometry = self
+                    .space
+                    .outputs()
+                    .nth(num)
+                    .map(|o| self.space.output_geometry(o).unwrap());
+
+                if let Some(geometry) = geometry {
+                    let x = geometry.loc.x as f64 + geometry.size.w as f64 / 2.0;
+                    let y = geometry.size.h as f64 / 2.0;
+                    let location = (x, y).into();
+                    let pointer = self.pointer.clone();
+                    let under = self.surface_under(location);
+                    pointer.motion(
+                        self,
+                        under,
+                        &MotionEvent {
+                            location,
+                            serial: SCOUNTER.next_serial(),
+                            time: self.clock.now().as_millis(),
+                        },
+                    );
+                    pointer.frame(self);
                 }
-                KeyAction::RotateOutput => {
-                    let pos = self.pointer.current_location().to_i32_round();
-                    let output = self
-                        .space
-                        .outputs()
-                        .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
-                        .cloned();
-
-                    if let Some(output) = output {
-                        let current_transform = output.current_transform();
-                        let new_transform = match current_transform {
-                            Transform::Normal => Transform::_90,
-                            Transform::_90 => Transform::_180,
-                            Transform::_180 => Transform::_270,
-                            Transform::_270 => Transform::Flipped,
-                            Transform::Flipped => Transform::Flipped90,
-                            Transform::Flipped90 => Transform::Flipped180,
-                            Transform::Flipped180 => Transform::Flipped270,
-                            Transform::Flipped270 => Transform::Normal,
-                        };
-                        output.change_current_state(None, Some(new_transform), None, None);
-                        crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
-                        self.backend_data.reset_buffers(&output);
-                    }
+            }
+            KeyAction::ScaleUp => {
+                let pos = self.pointer.current_location().to_i32_round();
+                let output = self
+                    .space
+                    .outputs()
+                    .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
+                    .cloned();
+
+                if let Some(output) = output {
+                    let (output_location, scale) = (
+                        self.space.output_geometry(&output).unwrap().loc,
+                        output.current_scale().fractional_scale(),
+                    );
+                    let new_scale = scale + 0.25;
+                    output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
+
+                    let rescale = scale / new_scale;
+                    let output_location = output_location.to_f64();
+                    let mut pointer_output_location = self.pointer.current_location() - output_location;
+                    pointer_output_location.x *= rescale;
+                    pointer_output_location.y *= rescale;
+                    let pointer_location = output_location + pointer_output_location;
+
+                    self.fixup_layout(pointer_location);
+                    let pointer = self.pointer.clone();
+                    let under = self.surface_under(pointer_location);
+                    pointer.motion(
+                        self,
+                        under,
+                        &MotionEvent {
+                            location: pointer_location,
+                            serial: SCOUNTER.next_serial(),
+                            time: self.clock.now().as_millis(),
+                        },
+                    );
+                    pointer.frame(self);
+                    self.backend_data.reset_buffers(&output);
                 }
-                KeyAction::ToggleTint => {
-                    let mut debug_flags = self.backend_data.debug_flags();
-                    debug_flags.toggle(DebugFlags::TINT);
-                    self.backend_data.set_debug_flags(debug_flags);
+            }
+            KeyAction::ScaleDown => {
+                let pos = self.pointer.current_location().to_i32_round();
+                let output = self
+                    .space
+                    .outputs()
+                    .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
+                    .cloned();
+
+                if let Some(output) = output {
+                    let (output_location, scale) = (
+                        self.space.output_geometry(&output).unwrap().loc,
+                        output.current_scale().fractional_scale(),
+                    );
+                    let new_scale = f64::max(1.0, scale - 0.25);
+                    output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
+
+                    let rescale = scale / new_scale;
+                    let output_location = output_location.to_f64();
+                    let mut pointer_output_location = self.pointer.current_location() - output_location;
+                    pointer_output_location.x *= rescale;
+                    pointer_output_location.y *= rescale;
+                    let pointer_location = output_location + pointer_output_location;
+
+                    self.fixup_layout(pointer_location);
+                    let pointer = self.pointer.clone();
+                    let under = self.surface_under(pointer_location);
+                    pointer.motion(
+                        self,
+                        under,
+                        &MotionEvent {
+                            location: pointer_location,
+                            serial: SCOUNTER.next_serial(),
+                            time: self.clock.now().as_millis(),
+                        },
+                    );
+                    pointer.frame(self);
+                    self.backend_data.reset_buffers(&output);
                 }
+            }
+            KeyAction::RotateOutput => {
+                let pos = self.pointer.current_location().to_i32_round();
+                let output = self
+                    .space
+                    .outputs()
+                    .find(|o| self.space.output_geometry(o).unwrap().contains(pos))
+                    .cloned();
 
-                action => match action {
-                    KeyAction::None
-                    | KeyAction::Quit
-                    | KeyAction::Run(_)
-                    | KeyAction::TogglePreview
-                    | KeyAction::ToggleDecorations => self.process_common_key_action(action),
+                if let Some(output) = output {
+                    let current_transform = output.current_transform();
+                    let new_transform = match current_transform {
+                        Transform::Normal => Transform::_90,
+                        Transform::_90 => Transform::_180,
+                        Transform::_180 => Transform::_270,
+                        Transform::_270 => Transform::Flipped,
+                        Transform::Flipped => Transform::Flipped90,
+                        Transform::Flipped90 => Transform::Flipped180,
+                        Transform::Flipped180 => Transform::Flipped270,
+                        Transform::Flipped270 => Transform::Normal,
+                    };
+                    output.change_current_state(None, Some(new_transform), None, None);
+                    self.fixup_layout(self.pointer.current_location());
+                    self.backend_data.reset_buffers(&output);
+                }
+            }
+            KeyAction::ToggleTint => {
+                let mut debug_flags = self.backend_data.debug_flags();
+                debug_flags.toggle(DebugFlags::TINT);
+                self.backend_data.set_debug_flags(debug_flags);
+            }
 
-                    _ => unreachable!(),
-                },
+            action => match action {
+                KeyAction::None
+                | KeyAction::Quit
+                | KeyAction::Run(_)
+                | KeyAction::TogglePreview
+                | KeyAction::ToggleDecorations
+                | KeyAction::NextLayout
+                | KeyAction::PrevLayout
+                | KeyAction::EnterMode(_)
+                | KeyAction::ExitMode
+                | KeyAction::ResizeWindow(..)
+                | KeyAction::SwitchWorkspace(_)
+                | KeyAction::MoveToWorkspace(_)
+                | KeyAction::NextWorkspace
+                | KeyAction::PrevWorkspace
+                | KeyAction::WorkspaceToNextOutput
+                | KeyAction::ToggleFloating
+                | KeyAction::CycleLayout
+                | KeyAction::SwapTile(_)
+                | KeyAction::PromoteTile
+                | KeyAction::ResizeMaster(_)
+                | KeyAction::FocusColumn(_)
+                | KeyAction::MoveColumn(_)
+                | KeyAction::ConsumeWindow
+                | KeyAction::ExpelWindow
+                | KeyAction::FocusDirection(_)
+                | KeyAction::SwapDirection(_)
+                | KeyAction::FocusOutput(_)
+                | KeyAction::SwitchWindow(_)
+                | KeyAction::MinimizeWindow
+                | KeyAction::RestoreWindow
+                | KeyAction::MoveToScratchpad
+                | KeyAction::ToggleScratchpad(_)
+                | KeyAction::ToggleAlwaysOnTop
+                | KeyAction::ToggleAlwaysBelow
+                | KeyAction::ToggleSticky
+                | KeyAction::GroupWindow(_)
+                | KeyAction::UngroupWindow
+                | KeyAction::FocusTab(_)
+                | KeyAction::ToggleGroupLayout => self.process_common_key_action(action),
+
+                _ => unreachable!(),
             },
+        }
+    }
+
+    pub fn process_input_event<B: InputBackend>(&mut self, dh: &DisplayHandle, event: InputEvent<B>) {
+        match event {
+            InputEvent::Keyboard { event, .. } => {
+                let action = self.keyboard_key_to_action::<B>(event);
+                self.process_key_action(action)
+            }
             InputEvent::PointerMotion { event, .. } => self.on_pointer_move::<B>(dh, event),
             InputEvent::PointerMotionAbsolute { event, .. } => self.on_pointer_move_absolute::<B>(dh, event),
             InputEvent::PointerButton { event, .. } => self.on_pointer_button::<B>(event),
@@ -867,6 +1205,7 @@ impl WayiceState<UdevData> {
             },
         );
         pointer.frame(self);
+        self.overview_motion(None, pointer_location);
 
         // If pointer is now in a constraint region, activate it
         // TODO Anywhere else pointer is moved needs to do this
@@ -923,6 +1262,7 @@ impl WayiceState<UdevData> {
             },
         );
         pointer.frame(self);
+        self.overview_motion(None, pointer_location);
     }
 
     fn on_tablet_tool_axis<B: InputBackend>(&mut self, evt: B::TabletToolAxisEvent) {
@@ -1071,31 +1411,121 @@ impl WayiceState<UdevData> {
         }
     }
 
+    /// Start tracking a gesture if it might be bound, holding back `begin`.
+    /// Returns whether it is tracked.
+    fn begin_bound_gesture(
+        &mut self,
+        gesture_type: GestureType,
+        fingers: u32,
+        begin: HeldGestureEvent,
+    ) -> bool {
+        self.bound_gesture = self
+            .config
+            .gestures
+            .consumes(gesture_type, fingers)
+            .then(|| BoundGesture::new(fingers, begin));
+        self.bound_gesture.is_some()
+    }
+
+    /// Decide on the tracked gesture once its direction is known: keep it if
+    /// it is bound, or hand it to the client otherwise
+    fn recognize_bound_gesture(&mut self, gesture: impl FnOnce(&BoundGesture) -> Option<Gesture>) {
+        let Some(bound) = self.bound_gesture.as_mut() else {
+            return;
+        };
+        if bound.recognized.is_some() {
+            return;
+        }
+        let Some(gesture) = gesture(bound) else {
+            return;
+        };
+        if self.config.gestures.action_for(gesture, bound.fingers).is_some() {
+            bound.recognized = Some(gesture);
+            bound.held.clear();
+        } else if let Some(bound) = self.bound_gesture.take() {
+            debug!(?gesture, "Gesture is not bound, forwarding it");
+            self.release_gesture(bound.held);
+        }
+    }
+
+    /// Forward gesture events held back to the client
+    fn release_gesture(&mut self, held: Vec<HeldGestureEvent>) {
+        let pointer = self.pointer.clone();
+        for event in held {
+            match event {
+                HeldGestureEvent::SwipeBegin(event) => pointer.gesture_swipe_begin(self, &event),
+                HeldGestureEvent::SwipeUpdate(event) => pointer.gesture_swipe_update(self, &event),
+                HeldGestureEvent::PinchBegin(event) => pointer.gesture_pinch_begin(self, &event),
+                HeldGestureEvent::PinchUpdate(event) => pointer.gesture_pinch_update(self, &event),
+                HeldGestureEvent::HoldBegin(event) => pointer.gesture_hold_begin(self, &event),
+            }
+        }
+    }
+
+    /// Stop tracking the current gesture, running its action unless it was
+    /// cancelled.
+    ///
+    /// Returns whether the gesture was bound. If it was not, its held back
+    /// events were forwarded and the end event has to follow them.
+    fn end_bound_gesture(
+        &mut self,
+        cancelled: bool,
+        gesture: impl FnOnce(&BoundGesture) -> Option<Gesture>,
+    ) -> bool {
+        let Some(bound) = self.bound_gesture.take() else {
+            return false;
+        };
+        let action = bound
+            .recognized
+            .or_else(|| gesture(&bound))
+            .and_then(|gesture| self.config.gestures.action_for(gesture, bound.fingers));
+        let Some(action) = action else {
+            self.release_gesture(bound.held);
+            return false;
+        };
+        if !cancelled {
+            debug!(?action, "Gesture triggered action");
+            self.process_key_action(action);
+        }
+        true
+    }
+
     fn on_gesture_swipe_begin<B: InputBackend>(&mut self, evt: B::GestureSwipeBeginEvent) {
-        let serial = SCOUNTER.next_serial();
+        let event = GestureSwipeBeginEvent {
+            serial: SCOUNTER.next_serial(),
+            time: evt.time_msec(),
+            fingers: evt.fingers(),
+        };
+        if self.begin_bound_gesture(
+            GestureType::Swipe,
+            evt.fingers(),
+            HeldGestureEvent::SwipeBegin(event),
+        ) {
+            return;
+        }
         let pointer = self.pointer.clone();
-        pointer.gesture_swipe_begin(
-            self,
-            &GestureSwipeBeginEvent {
-                serial,
-                time: evt.time_msec(),
-                fingers: evt.fingers(),
-            },
-        );
+        pointer.gesture_swipe_begin(self, &event);
     }
 
     fn on_gesture_swipe_update<B: InputBackend>(&mut self, evt: B::GestureSwipeUpdateEvent) {
+        let event = GestureSwipeUpdateEvent {
+            time: evt.time_msec(),
+            delta: evt.delta(),
+        };
+        if let Some(bound) = self.bound_gesture.as_mut() {
+            bound.delta += evt.delta();
+            bound.hold(HeldGestureEvent::SwipeUpdate(event));
+            self.recognize_bound_gesture(BoundGesture::swipe);
+            return;
+        }
         let pointer = self.pointer.clone();
-        pointer.gesture_swipe_update(
-            self,
-            &GestureSwipeUpdateEvent {
-                time: evt.time_msec(),
-                delta: evt.delta(),
-            },
-        );
+        pointer.gesture_swipe_update(self, &event);
     }
 
     fn on_gesture_swipe_end<B: InputBackend>(&mut self, evt: B::GestureSwipeEndEvent) {
+        if self.end_bound_gesture(evt.cancelled(), BoundGesture::swipe) {
+            return;
+        }
         let serial = SCOUNTER.next_serial();
         let pointer = self.pointer.clone();
         pointer.gesture_swipe_end(
@@ -1109,32 +1539,43 @@ impl WayiceState<UdevData> {
     }
 
     fn on_gesture_pinch_begin<B: InputBackend>(&mut self, evt: B::GesturePinchBeginEvent) {
-        let serial = SCOUNTER.next_serial();
+        let event = GesturePinchBeginEvent {
+            serial: SCOUNTER.next_serial(),
+            time: evt.time_msec(),
+            fingers: evt.fingers(),
+        };
+        if self.begin_bound_gesture(
+            GestureType::Pinch,
+            evt.fingers(),
+            HeldGestureEvent::PinchBegin(event),
+        ) {
+            return;
+        }
         let pointer = self.pointer.clone();
-        pointer.gesture_pinch_begin(
-            self,
-            &GesturePinchBeginEvent {
-                serial,
-                time: evt.time_msec(),
-                fingers: evt.fingers(),
-            },
-        );
+        pointer.gesture_pinch_begin(self, &event);
     }
 
     fn on_gesture_pinch_update<B: InputBackend>(&mut self, evt: B::GesturePinchUpdateEvent) {
+        let event = GesturePinchUpdateEvent {
+            time: evt.time_msec(),
+            delta: evt.delta(),
+            scale: evt.scale(),
+            rotation: evt.rotation(),
+        };
+        if let Some(bound) = self.bound_gesture.as_mut() {
+            bound.scale = evt.scale();
+            bound.hold(HeldGestureEvent::PinchUpdate(event));
+            self.recognize_bound_gesture(BoundGesture::pinch);
+            return;
+        }
         let pointer = self.pointer.clone();
-        pointer.gesture_pinch_update(
-            self,
-            &GesturePinchUpdateEvent {
-                time: evt.time_msec(),
-                delta: evt.delta(),
-                scale: evt.scale(),
-                rotation: evt.rotation(),
-            },
-        );
+        pointer.gesture_pinch_update(self, &event);
     }
 
     fn on_gesture_pinch_end<B: InputBackend>(&mut self, evt: B::GesturePinchEndEvent) {
+        if self.end_bound_gesture(evt.cancelled(), BoundGesture::pinch) {
+            return;
+        }
         let serial = SCOUNTER.next_serial();
         let pointer = self.pointer.clone();
         pointer.gesture_pinch_end(
@@ -1148,19 +1589,26 @@ impl WayiceState<UdevData> {
     }
 
     fn on_gesture_hold_begin<B: InputBackend>(&mut self, evt: B::GestureHoldBeginEvent) {
-        let serial = SCOUNTER.next_serial();
+        let event = GestureHoldBeginEvent {
+            serial: SCOUNTER.next_serial(),
+            time: evt.time_msec(),
+            fingers: evt.fingers(),
+        };
+        if self.begin_bound_gesture(
+            GestureType::Hold,
+            evt.fingers(),
+            HeldGestureEvent::HoldBegin(event),
+        ) {
+            return;
+        }
         let pointer = self.pointer.clone();
-        pointer.gesture_hold_begin(
-            self,
-            &GestureHoldBeginEvent {
-                serial,
-                time: evt.time_msec(),
-                fingers: evt.fingers(),
-            },
-        );
+        pointer.gesture_hold_begin(self, &event);
     }
 
     fn on_gesture_hold_end<B: InputBackend>(&mut self, evt: B::GestureHoldEndEvent) {
+        if self.end_bound_gesture(evt.cancelled(), |_| Some(Gesture::Hold)) {
+            return;
+        }
         let serial = SCOUNTER.next_serial();
         let pointer = self.pointer.clone();
         pointer.gesture_hold_end(
@@ -1203,6 +1651,10 @@ impl WayiceState<UdevData> {
             return;
         };
 
+        if self.overview_press(PressSource::Touch(evt.slot()), touch_location) {
+            return;
+        }
+
         let serial = SCOUNTER.next_serial();
         self.update_keyboard_focus(touch_location, serial);
 
@@ -1222,6 +1674,9 @@ impl WayiceState<UdevData> {
         let Some(handle) = self.seat.get_touch() else {
             return;
         };
+        if self.overview_release(PressSource::Touch(evt.slot())) {
+            return;
+        }
         let serial = SCOUNTER.next_serial();
         handle.up(
             self,
@@ -1239,6 +1694,7 @@ impl WayiceState<UdevData> {
         let Some(touch_location) = self.touch_location_transformed(&evt) else {
             return;
         };
+        self.overview_motion(Some(evt.slot()), touch_location);
 
         let under = self.surface_under(touch_location);
         handle.motion(
@@ -1292,98 +1748,3 @@ impl WayiceState<UdevData> {
         }
     }
 }
-
-/// Possible results of a keyboard action
-#[allow(dead_code)] // some of these are only read if udev is enabled
-#[derive(Debug)]
-enum KeyAction {
-    /// Quit the compositor
-    Quit,
-    /// Trigger a vt-switch
-    VtSwitch(i32),
-    /// run a command
-    Run(String),
-    /// Switch the current screen
-    Screen(usize),
-    ScaleUp,
-    ScaleDown,
-    TogglePreview,
-    RotateOutput,
-    ToggleTint,
-    ToggleDecorations,
-    /// Do nothing more
-    None,
-}
-
-fn get_config_path() -> PathBuf {
-    // Get the user's home directory and append the config file path
-    let home_dir = env::var("HOME").expect("Could not get HOME directory");
-    let config_path = format!("{}/.config/wayice.ini", home_dir);
-    PathBuf::from(config_path)
-}
-
-fn process_keyboard_shortcut(modifiers: ModifiersState, keysym: Keysym) -> Option<KeyAction> {
-    // Load the configuration file from ~/.config/wayice.ini
-    let config_path = get_config_path();
-    let config = Ini::load_from_file(config_path).expect("Failed to load wayice.ini");
-
-    let section = config.section(Some("shortcuts")).unwrap();
-
-    // Check Quit shortcuts
-    if (modifiers.ctrl && modifiers.alt && keysym == Keysym::BackSpace)
-        || (modifiers.logo && keysym == Keysym::q)
-    {
-        return Some(KeyAction::Quit);
-    }
-
-    // Check VT Switch
-    let vt_switch_keys: Vec<&str> = section.get("vt_switch").unwrap().split("..").collect();
-    let vt_range = xkb::KEY_XF86Switch_VT_1..=xkb::KEY_XF86Switch_VT_12;
-    if vt_range.contains(&keysym.raw()) {
-        return Some(KeyAction::VtSwitch(
-            (keysym.raw() - xkb::KEY_XF86Switch_VT_1 + 1) as i32,
-        ));
-    }
-
-    // Run terminal
-    if modifiers.logo && keysym == Keysym::Return {
-        return Some(KeyAction::Run("weston-terminal".into()));
-    }
-
-    // Switch screen (Logo+1 to Logo+9)
-    if modifiers.logo && (xkb::KEY_1..=xkb::KEY_9).contains(&keysym.raw()) {
-        return Some(KeyAction::Screen((keysym.raw() - xkb::KEY_1) as usize));
-    }
-
-    // Scale down
-    if modifiers.logo && modifiers.shift && keysym == Keysym::M {
-        return Some(KeyAction::ScaleDown);
-    }
-
-    // Scale up
-    if modifiers.logo && modifiers.shift && keysym == Keysym::P {
-        return Some(KeyAction::ScaleUp);
-    }
-
-    // Toggle preview
-    if modifiers.logo && modifiers.shift && keysym == Keysym::W {
-        return Some(KeyAction::TogglePreview);
-    }
-
-    // Rotate output
-    if modifiers.logo && modifiers.shift && keysym == Keysym::R {
-        return Some(KeyAction::RotateOutput);
-    }
-
-    // Toggle tint
-    if modifiers.logo && modifiers.shift && keysym == Keysym::T {
-        return Some(KeyAction::ToggleTint);
-    }
-
-    // Toggle decorations
-    if modifiers.logo && modifiers.shift && keysym == Keysym::D {
-        return Some(KeyAction::ToggleDecorations);
-    }
-
-    None
-}
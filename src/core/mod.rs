@@ -2,5 +2,6 @@ pub mod cursor;
 pub mod drawing;
 pub mod focus;
 pub mod input_handler;
+pub mod process;
 pub mod render;
 pub mod state;
@@ -0,0 +1,153 @@
+use std::{
+    io,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::SpawnCommand,
+    core::state::{Backend, WayiceState},
+};
+
+/// How often exited children are reaped and respawned
+const REAP_INTERVAL: Duration = Duration::from_secs(1);
+/// Children exiting sooner than this after being started are not
+/// respawned right away, to avoid spinning on a command that always fails
+const RESPAWN_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A process started by the compositor
+#[derive(Debug)]
+pub struct ChildProcess {
+    pub command: SpawnCommand,
+    pub child: Option<Child>,
+    /// Start the command again whenever it exits
+    pub respawn: bool,
+    started: Instant,
+}
+
+/// Periodically reap exited children and restart the ones that should
+/// always be running.
+pub fn watch_children<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, WayiceState<BackendData>>,
+) {
+    handle
+        .insert_source(Timer::from_duration(REAP_INTERVAL), |_, _, data| {
+            data.reap_children();
+            TimeoutAction::ToDuration(REAP_INTERVAL)
+        })
+        .expect("Failed to init child process timer");
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Environment given to every program started by the compositor
+    pub fn child_environment(&self) -> Vec<(&'static str, String)> {
+        self.socket_name
+            .clone()
+            .map(|v| ("WAYLAND_DISPLAY", v))
+            .into_iter()
+            .chain(
+                #[cfg(feature = "xwayland")]
+                self.xdisplay.map(|v| ("DISPLAY", format!(":{}", v))),
+                #[cfg(not(feature = "xwayland"))]
+                None,
+            )
+            .collect()
+    }
+
+    /// Start `spawn` through `sh -c` with the compositor's environment
+    fn spawn_shell_command(&self, spawn: &SpawnCommand) -> io::Result<Child> {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&spawn.command)
+            .envs(self.child_environment())
+            .envs(spawn.env.iter().map(|(name, value)| (name, value)));
+        if let Some(cwd) = &spawn.cwd {
+            command.current_dir(cwd);
+        }
+        command.spawn()
+    }
+
+    /// Start `spawn` and keep track of it, so it gets reaped once it
+    /// exits and optionally restarted.
+    pub fn spawn_child(&mut self, spawn: &SpawnCommand, respawn: bool) {
+        let command = spawn.command.as_str();
+        info!(command, respawn, "Starting program");
+        let child = match self.spawn_shell_command(spawn) {
+            Ok(child) => Some(child),
+            Err(err) => {
+                error!(command, %err, "Failed to start program");
+                if !respawn {
+                    return;
+                }
+                None
+            }
+        };
+        self.children.push(ChildProcess {
+            command: spawn.clone(),
+            child,
+            respawn,
+            started: Instant::now(),
+        });
+    }
+
+    /// Start the commands of the `[autostart]` section.
+    ///
+    /// Only the first call does anything, so this can be called from every
+    /// place that finishes the startup of the compositor.
+    pub fn run_autostart(&mut self) {
+        if self.autostart_done {
+            return;
+        }
+        self.autostart_done = true;
+
+        for entry in self.config.autostart.clone() {
+            self.spawn_child(&SpawnCommand::shell(entry.command), entry.respawn);
+        }
+    }
+
+    /// Collect exited children and restart the ones marked for respawn
+    pub fn reap_children(&mut self) {
+        let mut respawn = Vec::new();
+
+        self.children.retain_mut(|process| {
+            if let Some(child) = process.child.as_mut() {
+                match child.try_wait() {
+                    Ok(None) => return true,
+                    Ok(Some(status)) => {
+                        if process.respawn {
+                            warn!(command = process.command.command, %status, "Program exited, restarting it");
+                        } else {
+                            info!(command = process.command.command, %status, "Program exited");
+                        }
+                    }
+                    Err(err) => {
+                        error!(command = process.command.command, %err, "Failed to query program status");
+                        return true;
+                    }
+                }
+                process.child = None;
+            }
+
+            if !process.respawn {
+                return false;
+            }
+            if process.started.elapsed() < RESPAWN_BACKOFF {
+                // keep the entry around and try again on a later tick
+                return true;
+            }
+            respawn.push(process.command.clone());
+            false
+        });
+
+        for command in respawn {
+            self.spawn_child(&command, true);
+        }
+    }
+}
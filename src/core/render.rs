@@ -2,6 +2,7 @@ use smithay::{
     backend::renderer::{
         damage::{Error as OutputDamageTrackerError, OutputDamageTracker, RenderOutputResult},
         element::{
+            solid::SolidColorRenderElement,
             surface::WaylandSurfaceRenderElement,
             utils::{
                 ConstrainAlign, ConstrainScaleBehavior, CropRenderElement, RelocateRenderElement,
@@ -15,14 +16,14 @@ use smithay::{
         constrain_space_element, ConstrainBehavior, ConstrainReference, Space, SpaceRenderElements,
     },
     output::Output,
-    utils::{Point, Rectangle, Size},
+    utils::{Logical, Point, Rectangle, Size},
 };
 
 #[cfg(feature = "debug")]
 use crate::drawing::FpsElement;
 use crate::{
     core::drawing::{PointerRenderElement, CLEAR_COLOR, CLEAR_COLOR_FULLSCREEN},
-    shell::{FullscreenSurface, WindowElement, WindowRenderElement},
+    shell::{FullscreenSurface, Overview, WindowElement, WindowRenderElement, WindowSwitcher},
 };
 
 smithay::backend::renderer::element::render_elements! {
@@ -30,6 +31,7 @@ smithay::backend::renderer::element::render_elements! {
         R: ImportAll + ImportMem;
     Pointer=PointerRenderElement<R>,
     Surface=WaylandSurfaceRenderElement<R>,
+    Solid=SolidColorRenderElement,
     #[cfg(feature = "debug")]
     // Note: We would like to borrow this element instead, but that would introduce
     // a feature-dependent lifetime, which introduces a lot more feature bounds
@@ -43,6 +45,7 @@ impl<R: Renderer> std::fmt::Debug for CustomRenderElements<R> {
         match self {
             Self::Pointer(arg0) => f.debug_tuple("Pointer").field(arg0).finish(),
             Self::Surface(arg0) => f.debug_tuple("Surface").field(arg0).finish(),
+            Self::Solid(arg0) => f.debug_tuple("Solid").field(arg0).finish(),
             #[cfg(feature = "debug")]
             Self::Fps(arg0) => f.debug_tuple("Fps").field(arg0).finish(),
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
@@ -72,28 +75,14 @@ impl<R: Renderer + ImportAll + ImportMem, E: RenderElement<R> + std::fmt::Debug>
     }
 }
 
-pub fn space_preview_elements<'a, R, C>(
-    renderer: &'a mut R,
-    space: &'a Space<WindowElement>,
-    output: &'a Output,
-) -> impl Iterator<Item = C> + 'a
-where
-    R: Renderer + ImportAll + ImportMem,
-    R::TextureId: Clone + 'static,
-    C: From<CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>> + 'a,
-{
-    let constrain_behavior = ConstrainBehavior {
-        reference: ConstrainReference::BoundingBox,
-        behavior: ConstrainScaleBehavior::Fit,
-        align: ConstrainAlign::CENTER,
-    };
-
-    let preview_padding = 10;
+/// Space between window previews and around them
+pub(crate) const PREVIEW_PADDING: i32 = 10;
 
-    let elements_on_space = space.elements_for_output(output).count();
+/// Size of `output` in logical pixels
+pub(crate) fn output_logical_size(output: &Output) -> Size<f64, Logical> {
     let output_scale = output.current_scale().fractional_scale();
     let output_transform = output.current_transform();
-    let output_size = output
+    output
         .current_mode()
         .map(|mode| {
             output_transform
@@ -101,52 +90,145 @@ where
                 .to_f64()
                 .to_logical(output_scale)
         })
-        .unwrap_or_default();
+        .unwrap_or_default()
+}
+
+/// How window previews are fit into their cell
+const PREVIEW_CONSTRAIN: ConstrainBehavior = ConstrainBehavior {
+    reference: ConstrainReference::BoundingBox,
+    behavior: ConstrainScaleBehavior::Fit,
+    align: ConstrainAlign::CENTER,
+};
 
-    let max_elements_per_row = 4;
-    let elements_per_row = usize::min(elements_on_space, max_elements_per_row);
-    let rows = f64::ceil(elements_on_space as f64 / elements_per_row as f64);
+/// Area of the preview at `index` in a grid of `count` previews covering an
+/// output of `output_size`, relative to the output.
+///
+/// The grid is as close to square as possible, which makes the previews
+/// largest for windows shaped like the output.
+pub(crate) fn preview_cell(
+    index: usize,
+    count: usize,
+    output_size: Size<f64, Logical>,
+) -> Rectangle<i32, Logical> {
+    let elements_per_row = (count as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = f64::ceil(count as f64 / elements_per_row as f64).max(1.0);
 
     let preview_size = Size::from((
-        f64::round(output_size.w / elements_per_row as f64) as i32 - preview_padding * 2,
-        f64::round(output_size.h / rows) as i32 - preview_padding * 2,
+        f64::round(output_size.w / elements_per_row as f64) as i32 - PREVIEW_PADDING * 2,
+        f64::round(output_size.h / rows) as i32 - PREVIEW_PADDING * 2,
+    ));
+
+    let column = index % elements_per_row;
+    let row = index / elements_per_row;
+    let preview_location = Point::from((
+        PREVIEW_PADDING + (PREVIEW_PADDING + preview_size.w) * column as i32,
+        PREVIEW_PADDING + (PREVIEW_PADDING + preview_size.h) * row as i32,
     ));
+    Rectangle::from_loc_and_size(preview_location, preview_size)
+}
 
-    space
-        .elements_for_output(output)
+/// Live previews of `windows` laid out in a grid on `output`
+pub fn preview_elements<'a, R, C>(
+    renderer: &'a mut R,
+    windows: Vec<&'a WindowElement>,
+    output: &'a Output,
+) -> impl Iterator<Item = C> + 'a
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + 'static,
+    C: From<CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>> + 'a,
+{
+    let count = windows.len();
+    let output_scale = output.current_scale().fractional_scale();
+    let output_size = output_logical_size(output);
+
+    windows
+        .into_iter()
         .enumerate()
         .flat_map(move |(element_index, window)| {
-            let column = element_index % elements_per_row;
-            let row = element_index / elements_per_row;
-            let preview_location = Point::from((
-                preview_padding + (preview_padding + preview_size.w) * column as i32,
-                preview_padding + (preview_padding + preview_size.h) * row as i32,
-            ));
-            let constrain = Rectangle::from_loc_and_size(preview_location, preview_size);
+            let constrain = preview_cell(element_index, count, output_size);
             constrain_space_element(
                 renderer,
                 window,
-                preview_location,
+                constrain.loc,
                 1.0,
                 output_scale,
                 constrain,
-                constrain_behavior,
+                PREVIEW_CONSTRAIN,
             )
         })
 }
 
+/// The thumbnails of the overview on `output`, above the frame behind the
+/// selected one
+pub fn overview_elements<R>(
+    renderer: &mut R,
+    space: &Space<WindowElement>,
+    overview: &Overview,
+    output: &Output,
+) -> Vec<OutputRenderElements<R, WindowRenderElement<R>>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + 'static,
+{
+    let output_scale = output.current_scale().fractional_scale();
+    let mut elements = Vec::new();
+    for (window, area) in overview.thumbnails(output, space) {
+        elements.extend(constrain_space_element(
+            renderer,
+            &window,
+            area.loc,
+            1.0,
+            output_scale,
+            area,
+            PREVIEW_CONSTRAIN,
+        ));
+    }
+    if let Some(highlight) = overview.highlight_element(output, output_scale.into()) {
+        elements.push(OutputRenderElements::Custom(CustomRenderElements::Solid(
+            highlight,
+        )));
+    }
+    elements
+}
+
 #[profiling::function]
 pub fn output_elements<R>(
     output: &Output,
     space: &Space<WindowElement>,
     custom_elements: impl IntoIterator<Item = CustomRenderElements<R>>,
     renderer: &mut R,
-    show_window_preview: bool,
+    switcher: Option<&WindowSwitcher>,
+    overview: Option<&Overview>,
 ) -> (Vec<OutputRenderElements<R, WindowRenderElement<R>>>, [f32; 4])
 where
     R: Renderer + ImportAll + ImportMem,
     R::TextureId: Clone + 'static,
 {
+    // the window switcher is drawn above everything but the custom elements
+    let switcher_elements: Vec<OutputRenderElements<R, WindowRenderElement<R>>> =
+        match switcher.filter(|s| s.output() == output) {
+            Some(switcher) => {
+                let scale = output.current_scale().fractional_scale().into();
+                let highlight = CustomRenderElements::Solid(switcher.highlight_element(scale));
+                preview_elements(renderer, switcher.windows().iter().collect(), output)
+                    .chain(std::iter::once(OutputRenderElements::Custom(highlight)))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+    // the overview replaces the whole scene while it is shown
+    if let Some(overview) = overview {
+        let elements = custom_elements
+            .into_iter()
+            .map(OutputRenderElements::from)
+            .chain(switcher_elements)
+            .chain(overview_elements(renderer, space, overview, output))
+            .collect::<Vec<_>>();
+        return (elements, CLEAR_COLOR);
+    }
+
     if let Some(window) = output
         .user_data()
         .get::<FullscreenSurface>()
@@ -159,6 +241,7 @@ where
         let elements = custom_elements
             .into_iter()
             .map(OutputRenderElements::from)
+            .chain(switcher_elements)
             .chain(
                 window_render_elements
                     .into_iter()
@@ -170,12 +253,9 @@ where
         let mut output_render_elements = custom_elements
             .into_iter()
             .map(OutputRenderElements::from)
+            .chain(switcher_elements)
             .collect::<Vec<_>>();
 
-        if show_window_preview && space.elements_for_output(output).count() > 0 {
-            output_render_elements.extend(space_preview_elements(renderer, space, output));
-        }
-
         let space_elements = smithay::desktop::space::space_render_elements::<_, WindowElement, _>(
             renderer,
             [space],
@@ -197,13 +277,68 @@ pub fn render_output<'a, 'd, R>(
     renderer: &'a mut R,
     damage_tracker: &'d mut OutputDamageTracker,
     age: usize,
-    show_window_preview: bool,
+    switcher: Option<&WindowSwitcher>,
+    overview: Option<&Overview>,
 ) -> Result<RenderOutputResult<'d>, OutputDamageTrackerError<R>>
 where
     R: Renderer + ImportAll + ImportMem,
     R::TextureId: Clone + 'static,
 {
     let (elements, clear_color) =
-        output_elements(output, space, custom_elements, renderer, show_window_preview);
+        output_elements(output, space, custom_elements, renderer, switcher, overview);
     damage_tracker.render_output(renderer, age, &elements, clear_color)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output_size() -> Size<f64, Logical> {
+        Size::from((1920.0, 1080.0))
+    }
+
+    /// Check that the `count` cells lie on the output without overlapping
+    fn assert_cells_fit(count: usize) {
+        let output = Rectangle::from_loc_and_size((0, 0), output_size().to_i32_round());
+        let cells: Vec<_> = (0..count)
+            .map(|i| preview_cell(i, count, output_size()))
+            .collect();
+        for (index, cell) in cells.iter().enumerate() {
+            assert!(output.contains_rect(*cell), "{cell:?} is off the output");
+            for other in &cells[index + 1..] {
+                assert!(!cell.overlaps(*other), "{cell:?} overlaps {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn preview_cell_single_window_fills_output() {
+        assert_eq!(
+            preview_cell(0, 1, output_size()),
+            Rectangle::from_loc_and_size((10, 10), (1900, 1060))
+        );
+        assert_cells_fit(1);
+    }
+
+    #[test]
+    fn preview_cell_five_windows_in_three_columns() {
+        assert_eq!(
+            preview_cell(0, 5, output_size()),
+            Rectangle::from_loc_and_size((10, 10), (620, 520))
+        );
+        assert_eq!(
+            preview_cell(4, 5, output_size()),
+            Rectangle::from_loc_and_size((640, 540), (620, 520))
+        );
+        assert_cells_fit(5);
+    }
+
+    #[test]
+    fn preview_cell_seventeen_windows_in_five_columns() {
+        assert_eq!(
+            preview_cell(16, 17, output_size()),
+            Rectangle::from_loc_and_size((384, 790), (364, 250))
+        );
+        assert_cells_fit(17);
+    }
+}
@@ -1,11 +1,9 @@
-use home::home_dir;
-use ini::Ini;
 use std::{
     os::unix::io::OwnedFd,
     sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 use smithay::{
     backend::{
@@ -29,7 +27,7 @@ use smithay::{
         PopupKind, PopupManager, Space,
     },
     input::{
-        keyboard::{Keysym, LedState, XkbConfig},
+        keyboard::{Keysym, LedState},
         pointer::{CursorImageStatus, CursorImageSurfaceData, PointerHandle},
         Seat, SeatHandler, SeatState,
     },
@@ -96,12 +94,18 @@ use smithay::{
 #[cfg(feature = "xwayland")]
 use crate::core::cursor::Cursor;
 use crate::{
-    core::focus::{KeyboardFocusTarget, PointerFocusTarget},
-    shell::WindowElement,
+    config::{self, Config, Decorations, KeyboardConfig},
+    core::{
+        focus::{KeyboardFocusTarget, PointerFocusTarget},
+        input_handler::BoundGesture,
+        process::{self, ChildProcess},
+    },
+    shell::{MinimizedWindow, Overview, SnapPreview, WindowElement, WindowGroup, WindowSwitcher, Workspaces},
 };
 #[cfg(feature = "xwayland")]
 use smithay::{
     delegate_xwayland_keyboard_grab, delegate_xwayland_shell,
+    reexports::calloop::timer::{TimeoutAction, Timer},
     utils::Size,
     wayland::selection::{SelectionSource, SelectionTarget},
     wayland::xwayland_keyboard_grab::{XWaylandKeyboardGrabHandler, XWaylandKeyboardGrabState},
@@ -109,6 +113,10 @@ use smithay::{
     xwayland::{X11Wm, XWayland, XWaylandEvent},
 };
 
+/// How long autostart waits for XWayland before starting without it
+#[cfg(feature = "xwayland")]
+const XWAYLAND_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Default)]
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
@@ -172,7 +180,32 @@ pub struct WayiceState<BackendData: Backend + 'static> {
     #[cfg(feature = "debug")]
     pub renderdoc: Option<renderdoc::RenderDoc<renderdoc::V141>>,
 
-    pub show_window_preview: bool,
+    pub config: Config,
+    pub active_layout: Option<u32>,
+    /// Binding mode the keyboard is in, `None` for the default bindings
+    pub binding_mode: Option<String>,
+    /// Touchpad gesture in progress that is handled by the compositor
+    pub bound_gesture: Option<BoundGesture>,
+    pub workspaces: Workspaces,
+    /// Window being dragged by a move grab, left alone by tiling layouts
+    pub dragged_window: Option<WindowElement>,
+    /// Drop zone the dragged window is over
+    pub snap_preview: Option<SnapPreview>,
+    /// Windows that had keyboard focus, most recent first
+    pub focus_history: Vec<WindowElement>,
+    /// The window switcher, while it is open
+    pub switcher: Option<WindowSwitcher>,
+    /// The overview, while it is open or closing
+    pub overview: Option<Overview>,
+    /// Minimized windows, in the order they were minimized
+    pub minimized: Vec<MinimizedWindow>,
+    /// Windows of the scratchpad, the hidden ones unmapped and those hidden
+    /// the longest first
+    pub scratchpad: Vec<WindowElement>,
+    /// Groups of windows shown as tabs of one frame
+    pub groups: Vec<WindowGroup>,
+    pub children: Vec<ChildProcess>,
+    pub autostart_done: bool,
     pub focused_surface: Option<wayland_server::protocol::wl_surface::WlSurface>,
 }
 
@@ -290,6 +323,12 @@ impl<BackendData: Backend> SeatHandler for WayiceState<BackendData> {
         let focus = wl_surface.and_then(|s| dh.get_client(s.id()).ok());
         set_data_device_focus(dh, seat, focus.clone());
         set_primary_focus(dh, seat, focus);
+
+        if let Some(KeyboardFocusTarget::Window(window)) = target {
+            self.record_focus(window);
+            // scroll the focused column into view, once the focus change is done
+            self.handle.insert_idle(|state| state.arrange_all());
+        }
     }
     fn cursor_image(&mut self, _seat: &Seat<Self>, image: CursorImageStatus) {
         self.cursor_status = image;
@@ -422,10 +461,16 @@ impl<BackendData: Backend> XdgDecorationHandler for WayiceState<BackendData> {
     fn request_mode(&mut self, toplevel: ToplevelSurface, mode: DecorationMode) {
         use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
 
+        // a window rule forcing the decorations takes precedence
+        let forced = self
+            .window_for_surface(toplevel.wl_surface())
+            .and_then(|window| window.forced_decorations());
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(match mode {
-                DecorationMode::ServerSide => Mode::ServerSide,
-                _ => Mode::ClientSide,
+            state.decoration_mode = Some(match (forced, mode) {
+                (Some(Decorations::Server), _) => Mode::ServerSide,
+                (Some(Decorations::Client), _) => Mode::ClientSide,
+                (None, DecorationMode::ServerSide) => Mode::ServerSide,
+                (None, _) => Mode::ClientSide,
             });
         });
         if toplevel.is_initial_configure_sent() {
@@ -434,8 +479,14 @@ impl<BackendData: Backend> XdgDecorationHandler for WayiceState<BackendData> {
     }
     fn unset_mode(&mut self, toplevel: ToplevelSurface) {
         use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
+        let forced = self
+            .window_for_surface(toplevel.wl_surface())
+            .and_then(|window| window.forced_decorations());
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(Mode::ClientSide);
+            state.decoration_mode = Some(match forced {
+                Some(Decorations::Server) => Mode::ServerSide,
+                _ => Mode::ClientSide,
+            });
         });
         if toplevel.is_initial_configure_sent() {
             toplevel.send_pending_configure();
@@ -629,39 +680,28 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
 
         let pointer = seat.add_pointer();
 
-        // keyboard setup using wayice.ini
-        let home_path = home_dir().expect("Failed to get home directory");
-        let config_path = home_path.join(".config").join("wayice.ini");
-
-        let mut layout = String::from("");
-        let mut variant = String::from("");
-
-        if let Ok(conf) = Ini::load_from_file(config_path) {
-            if let Some(section) = conf.section(Some("keyboard")) {
-                if let Some(l) = section.get("layout") {
-                    layout = l.to_string();
-                }
-                if let Some(v) = section.get("variant") {
-                    variant = v.to_string();
-                }
-            }
+        let mut config = Config::load_or_default();
+        config::watch_config(&handle);
+        process::watch_children(&handle);
+
+        // keyboard setup using wayice.ini, a keymap that fails to compile
+        // would leave no keyboard at all. The config keeps the keymap that is
+        // in use, so that reload and the published layouts compare against it.
+        if let Err(err) = config.keyboard.compile_keymap() {
+            error!(%err, "Invalid keyboard config, using the default keymap");
+            config.keyboard = KeyboardConfig {
+                repeat_delay: config.keyboard.repeat_delay,
+                repeat_rate: config.keyboard.repeat_rate,
+                ..KeyboardConfig::default()
+            };
         }
-
-        let keyboard = if layout.is_empty() && variant.is_empty() {
-            seat.add_keyboard(XkbConfig::default(), 200, 25)
-                .expect("Failed to initialize the keyboard")
-        } else {
-            seat.add_keyboard(
-                XkbConfig {
-                    layout: &layout,
-                    variant: &variant,
-                    ..XkbConfig::default()
-                },
-                200, // repeat delay
-                25,  // repeat rate
+        let keyboard = seat
+            .add_keyboard(
+                config.keyboard.xkb_config(),
+                config.keyboard.repeat_delay,
+                config.keyboard.repeat_rate,
             )
-            .expect("Failed to initialize the keyboard")
-        };
+            .expect("Failed to initialize the keyboard");
 
         let keyboard_shortcuts_inhibit_state = KeyboardShortcutsInhibitState::new::<Self>(&dh);
 
@@ -671,7 +711,7 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
         #[cfg(feature = "xwayland")]
         XWaylandKeyboardGrabState::new::<Self>(&dh.clone());
 
-        WayiceState {
+        let mut state = WayiceState {
             backend_data,
             display_handle: dh,
             socket_name,
@@ -712,9 +752,26 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
             xdisplay: None,
             #[cfg(feature = "debug")]
             renderdoc: renderdoc::RenderDoc::new().ok(),
-            show_window_preview: false,
+            config,
+            active_layout: None,
+            binding_mode: None,
+            bound_gesture: None,
+            workspaces: Workspaces::default(),
+            dragged_window: None,
+            snap_preview: None,
+            focus_history: Vec::new(),
+            switcher: None,
+            overview: None,
+            minimized: Vec::new(),
+            scratchpad: Vec::new(),
+            groups: Vec::new(),
+            children: Vec::new(),
+            autostart_done: false,
             focused_surface: None,
-        }
+        };
+        state.ipc_shm_update_keyboard_layout();
+        state.ipc_shm_update_binding_mode();
+        state
     }
 
     #[cfg(feature = "xwayland")]
@@ -723,7 +780,7 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
 
         use smithay::wayland::compositor::CompositorHandler;
 
-        let (xwayland, client) = XWayland::spawn(
+        let spawned = XWayland::spawn(
             &self.display_handle,
             None,
             std::iter::empty::<(String, String)>(),
@@ -731,8 +788,16 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
             Stdio::null(),
             Stdio::null(),
             |_| (),
-        )
-        .expect("failed to start XWayland");
+        );
+        let (xwayland, client) = match spawned {
+            Ok(spawned) => spawned,
+            Err(err) => {
+                error!(%err, "Failed to start XWayland");
+                // autostart without X11 support once the event loop runs
+                self.handle.insert_idle(|data| data.run_autostart());
+                return;
+            }
+        };
 
         let ret = self
             .handle
@@ -760,13 +825,31 @@ impl<BackendData: Backend + 'static> WayiceState<BackendData> {
                     .expect("Failed to set xwayland default cursor");
                     data.xwm = Some(wm);
                     data.xdisplay = Some(display_number);
+                    data.run_autostart();
                 }
                 XWaylandEvent::Error => {
                     warn!("XWayland crashed on startup");
+                    data.run_autostart();
                 }
             });
         if let Err(e) = ret {
             tracing::error!("Failed to insert the XWaylandSource into the event loop: {}", e);
+            self.handle.insert_idle(|data| data.run_autostart());
+            return;
+        }
+
+        // don't hold back autostart forever if XWayland hangs
+        let timeout = self
+            .handle
+            .insert_source(Timer::from_duration(XWAYLAND_TIMEOUT), |_, _, data| {
+                if !data.autostart_done {
+                    warn!("XWayland did not start in time, running autostart without it");
+                    data.run_autostart();
+                }
+                TimeoutAction::Drop
+            });
+        if let Err(err) = timeout {
+            warn!(%err, "Failed to insert the XWayland timeout");
         }
     }
 }
@@ -887,4 +970,5 @@ pub trait Backend {
     fn reset_buffers(&mut self, output: &Output);
     fn early_import(&mut self, surface: &WlSurface);
     fn update_led_state(&mut self, led_state: LedState);
+    fn apply_input_config(&mut self, config: &Config);
 }
@@ -1,8 +1,74 @@
+use crate::config::OutputConfig;
 use crate::core::state::{Backend, WayiceState};
+use crate::shell::WindowElement;
 use serde_json::{json, Value};
-use smithay::output::Output;
+use smithay::desktop::Space;
+use smithay::output::{Output, Scale};
+use smithay::utils::{Logical, Point, Transform};
+use std::cell::Cell;
 use std::collections::HashMap;
 
+/// Position of an output as set by its `[output.<name>]` section.
+///
+/// Outputs without a configured position are laid out automatically.
+#[derive(Debug, Default)]
+pub struct ConfiguredPosition(pub Cell<Option<Point<i32, Logical>>>);
+
+impl ConfiguredPosition {
+    pub fn get(output: &Output) -> Option<Point<i32, Logical>> {
+        output
+            .user_data()
+            .get::<ConfiguredPosition>()
+            .and_then(|p| p.0.get())
+    }
+}
+
+/// Transform and scale the backend gave an output before any config section
+/// applied to it, put back when its section is removed
+#[derive(Debug)]
+struct BackendOutputState {
+    transform: Transform,
+    scale: Scale,
+}
+
+/// Find the `[output.<name>]` section matching `output`
+pub fn output_config<'a>(config: &'a crate::config::Config, output: &Output) -> Option<&'a OutputConfig> {
+    let properties = output.physical_properties();
+    config.output(&output.name(), &properties.make, &properties.model)
+}
+
+/// Apply the scale, transform and position of an output's config section,
+/// or put back those of the backend if there is none.
+///
+/// The mode and `enabled` are left alone, they only apply when the output is
+/// connected.
+pub fn apply_output_config(space: &mut Space<WindowElement>, output: &Output, config: Option<&OutputConfig>) {
+    output.user_data().insert_if_missing(|| BackendOutputState {
+        transform: output.current_transform(),
+        scale: output.current_scale(),
+    });
+    let backend = output.user_data().get::<BackendOutputState>().unwrap();
+    let transform = config.and_then(|c| c.transform).unwrap_or(backend.transform);
+    let scale = config
+        .and_then(|c| c.scale)
+        .map(Scale::Fractional)
+        .unwrap_or(backend.scale);
+    let position = config.and_then(|c| c.position).map(Point::from);
+
+    output.user_data().insert_if_missing(ConfiguredPosition::default);
+    output
+        .user_data()
+        .get::<ConfiguredPosition>()
+        .unwrap()
+        .0
+        .set(position);
+
+    output.change_current_state(None, Some(transform), Some(scale), position);
+    if let Some(position) = position {
+        space.map_output(output, position);
+    }
+}
+
 pub fn get_monitor_info(output: &Output) -> HashMap<String, String> {
     let mut monitor_info = HashMap::new();
 
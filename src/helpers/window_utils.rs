@@ -1,3 +1,4 @@
+use crate::config::WindowProperties;
 use crate::shell::WindowElement;
 use serde_json::{json, Value};
 use smithay::wayland::compositor::with_states;
@@ -64,6 +65,30 @@ pub fn get_window_info(wl_surface: &WlSurface) -> String {
     })
 }
 
+/// Properties of `window` that window rules are matched against
+pub fn window_properties(window: &WindowElement) -> WindowProperties {
+    match window.0.underlying_surface() {
+        WindowSurface::Wayland(toplevel) => with_states(toplevel.wl_surface(), |states| {
+            let Some(role) = states.data_map.get::<XdgToplevelSurfaceData>() else {
+                return WindowProperties::default();
+            };
+            let role = role.lock().unwrap();
+            WindowProperties {
+                app_id: role.app_id.clone(),
+                title: role.title.clone(),
+                ..Default::default()
+            }
+        }),
+        #[cfg(feature = "xwayland")]
+        WindowSurface::X11(surface) => WindowProperties {
+            title: Some(surface.title()),
+            class: Some(surface.class()),
+            instance: Some(surface.instance()),
+            ..Default::default()
+        },
+    }
+}
+
 pub fn get_x11_window_info(x11_surface: &X11Surface) -> String {
     // Extract relevant information from the X11 surface
     let window_id = format!("{}", x11_surface.window_id());
@@ -2,9 +2,14 @@ use crate::core::state::{Backend, WayiceState};
 use futures_util::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use smithay::reexports::calloop::{
+    channel::{self, Sender},
+    LoopHandle,
+};
 use std::error::Error;
 use tipsy::{Connection, Endpoint, OnConflict, ServerId};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::runtime::Runtime;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Message {
@@ -12,19 +17,64 @@ pub struct Message {
     data: Value,
 }
 
-pub async fn process_message(message: Message) {
+/// Requests of IPC clients that change the compositor state, handled on the
+/// event loop
+#[derive(Debug)]
+pub enum IpcCommand {
+    SwitchWorkspace(String),
+    /// Move the focused window to the named workspace
+    MoveToWorkspace(String),
+    MoveWorkspaceToOutput {
+        workspace: String,
+        output: String,
+    },
+    /// Minimize the focused window
+    MinimizeWindow,
+    /// Restore the most recently minimized window, of the given app if any
+    RestoreWindow(Option<String>),
+    /// Keep the focused window above the others, or stack it normally again
+    ToggleAlwaysOnTop,
+    /// Keep the focused window below the others, or stack it normally again
+    ToggleAlwaysBelow,
+    /// Show the focused window on every workspace of its output, or stop doing so
+    ToggleSticky,
+}
+
+/// Read the string field `key` of the message data
+fn data_str(data: &Value, key: &str) -> Option<String> {
+    data.get(key).and_then(Value::as_str).map(String::from)
+}
+
+pub async fn process_message(message: Message, commands: &Sender<IpcCommand>) {
     println!("Processing message: {:?}", message);
-    match message.method.as_str() {
+    let command = match message.method.as_str() {
         "window-info" => {
             println!("Handling example_method with data: {:?}", message.data);
+            None
         }
+        "workspace-switch" => data_str(&message.data, "name").map(IpcCommand::SwitchWorkspace),
+        "workspace-move-window" => data_str(&message.data, "name").map(IpcCommand::MoveToWorkspace),
+        "workspace-move-to-output" => data_str(&message.data, "name")
+            .zip(data_str(&message.data, "output"))
+            .map(|(workspace, output)| IpcCommand::MoveWorkspaceToOutput { workspace, output }),
+        "window-minimize" => Some(IpcCommand::MinimizeWindow),
+        "window-restore" => Some(IpcCommand::RestoreWindow(data_str(&message.data, "app_id"))),
+        "window-always-on-top" => Some(IpcCommand::ToggleAlwaysOnTop),
+        "window-always-below" => Some(IpcCommand::ToggleAlwaysBelow),
+        "window-sticky" => Some(IpcCommand::ToggleSticky),
         _ => {
             println!("Unknown method: {}", message.method);
+            None
+        }
+    };
+    if let Some(command) = command {
+        if let Err(e) = commands.send(command) {
+            eprintln!("Failed to pass IPC command to the compositor: {:?}", e);
         }
     }
 }
 
-async fn handle_connection(mut conn: Connection) {
+async fn handle_connection(mut conn: Connection, commands: Sender<IpcCommand>) {
     let mut buf = [0; 1024];
     loop {
         match conn.read(&mut buf).await {
@@ -35,7 +85,7 @@ async fn handle_connection(mut conn: Connection) {
                 let message_str = String::from_utf8_lossy(&buf[..n]);
                 match serde_json::from_str::<Message>(&message_str) {
                     Ok(message) => {
-                        process_message(message).await;
+                        process_message(message, &commands).await;
 
                         if let Err(e) = conn.write_all(message_str.as_bytes()).await {
                             eprintln!("Failed to write to socket: {:?}", e);
@@ -53,7 +103,7 @@ async fn handle_connection(mut conn: Connection) {
     }
 }
 
-pub async fn start_ipc_server() -> Result<(), Box<dyn Error>> {
+pub async fn start_ipc_server(commands: Sender<IpcCommand>) -> Result<(), Box<dyn Error>> {
     let socket_path = "/tmp/wayice";
     let endpoint = Endpoint::new(ServerId::new(socket_path), OnConflict::Overwrite)?;
     let mut incoming = endpoint.incoming()?;
@@ -63,7 +113,7 @@ pub async fn start_ipc_server() -> Result<(), Box<dyn Error>> {
     while let Some(conn) = incoming.next().await {
         match conn {
             Ok(connection) => {
-                tokio::spawn(handle_connection(connection));
+                tokio::spawn(handle_connection(connection, commands.clone()));
             }
             Err(e) => eprintln!("Error when receiving connection: {:?}", e),
         }
@@ -71,3 +121,49 @@ pub async fn start_ipc_server() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Start the IPC server on a runtime of its own, passing the commands it
+/// receives to the event loop behind `handle`.
+///
+/// The server stops when the returned runtime is dropped.
+pub fn spawn_ipc_server<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, WayiceState<BackendData>>,
+) -> Runtime {
+    let (commands, channel) = channel::channel();
+    handle
+        .insert_source(channel, |event, _, state| {
+            if let channel::Event::Msg(command) = event {
+                state.handle_ipc_command(command);
+            }
+        })
+        .expect("Failed to insert the IPC command source");
+
+    let rt = Runtime::new().unwrap();
+    rt.spawn(async move {
+        if let Err(e) = start_ipc_server(commands).await {
+            eprintln!("Failed to start IPC server: {:?}", e);
+        }
+    });
+    rt
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    fn handle_ipc_command(&mut self, command: IpcCommand) {
+        match command {
+            IpcCommand::SwitchWorkspace(name) => self.switch_workspace(&name),
+            IpcCommand::MoveToWorkspace(name) => self.move_window_to_workspace(&name),
+            IpcCommand::MoveWorkspaceToOutput { workspace, output } => {
+                self.move_workspace_to_output_named(&workspace, &output)
+            }
+            IpcCommand::MinimizeWindow => {
+                if let Some(window) = self.focused_window() {
+                    self.minimize_window(&window);
+                }
+            }
+            IpcCommand::RestoreWindow(app) => self.restore_minimized(app.as_deref()),
+            IpcCommand::ToggleAlwaysOnTop => self.toggle_always_on_top(),
+            IpcCommand::ToggleAlwaysBelow => self.toggle_always_below(),
+            IpcCommand::ToggleSticky => self.toggle_sticky(),
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use crate::config::DEFAULT_MODE;
 use crate::core::state::{Backend, WayiceState};
 use crate::helpers::window_utils::{get_window_info, get_x11_window_info};
+use crate::shell::WindowElement;
 use libc::sem_t;
 use libc::{
     close, ftruncate, mmap, munmap, sem_close, sem_open, sem_post, sem_wait, shm_open, MAP_FAILED,
@@ -147,28 +149,95 @@ pub fn ipc_set_string(shm_name: &str, value: &str) {
     unsafe { sem_close(sem_fd) }; // Close semaphore
 }
 
-impl<BackendData: Backend> WayiceState<BackendData> {
-    pub fn ipc_shm_update_window_list(&mut self) {
-        let surfaces: Vec<String> = self
-            .space
-            .elements()
-            .filter_map(|window| {
-                window.wl_surface().map(|surface| {
-                    if window.is_x11() {
-                        if let Some(x11_surface) = window.0.x11_surface() {
-                            get_x11_window_info(x11_surface)
-                        } else {
-                            json!({"error": "Invalid X11 surface"}).to_string()
-                        }
+/// JSON array with the info of every window of `windows`
+fn window_list<'a>(windows: impl Iterator<Item = &'a WindowElement>) -> String {
+    let surfaces: Vec<String> = windows
+        .filter_map(|window| {
+            window.wl_surface().map(|surface| {
+                if window.is_x11() {
+                    if let Some(x11_surface) = window.0.x11_surface() {
+                        get_x11_window_info(x11_surface)
                     } else {
-                        get_window_info(surface.as_ref())
+                        json!({"error": "Invalid X11 surface"}).to_string()
                     }
-                })
+                } else {
+                    get_window_info(surface.as_ref())
+                }
             })
-            .collect();
+        })
+        .collect();
 
-        let result_str = format!("[{}]", surfaces.join(","));
+    format!("[{}]", surfaces.join(","))
+}
 
+impl<BackendData: Backend> WayiceState<BackendData> {
+    pub fn ipc_shm_update_window_list(&mut self) {
+        let result_str = window_list(self.space.elements());
         ipc_set_string("/wayice_list_windows", &result_str);
     }
+
+    /// Publish the minimized windows, in the order they were minimized, to
+    /// `/wayice_minimized_windows`
+    pub fn ipc_shm_update_minimized(&mut self) {
+        let result_str = window_list(self.minimized.iter().map(|m| &m.window));
+        ipc_set_string("/wayice_minimized_windows", &result_str);
+    }
+
+    /// Publish the active keyboard layout to `/wayice_keyboard_layout` if it
+    /// changed since the last call.
+    pub fn ipc_shm_update_keyboard_layout(&mut self) {
+        let Some(keyboard) = self.seat.get_keyboard() else {
+            return;
+        };
+        let (index, name) = keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout();
+            (layout.0, xkb.layout_name(layout).to_string())
+        });
+
+        if self.active_layout == Some(index) {
+            return;
+        }
+        self.active_layout = Some(index);
+
+        let layout = json!({ "index": index, "name": name });
+        ipc_set_string("/wayice_keyboard_layout", &layout.to_string());
+    }
+
+    /// Publish the active binding mode to `/wayice_mode`
+    pub fn ipc_shm_update_binding_mode(&mut self) {
+        let mode = self.binding_mode.as_deref().unwrap_or(DEFAULT_MODE);
+        ipc_set_string("/wayice_mode", &json!({ "mode": mode }).to_string());
+    }
+
+    /// Publish every workspace, its output and whether it is shown to
+    /// `/wayice_workspaces`
+    pub fn ipc_shm_update_workspaces(&mut self) {
+        let workspaces: Vec<_> = self
+            .workspaces
+            .iter()
+            .map(|workspace| {
+                let shown = self.workspaces.shown_on(&workspace.name).is_some();
+                let windows = if shown {
+                    self.space
+                        .elements()
+                        .filter(|w| {
+                            self.window_output(w)
+                                .is_some_and(|o| o.name() == workspace.output)
+                        })
+                        .count()
+                } else {
+                    workspace.hidden_windows()
+                };
+                json!({
+                    "name": workspace.name,
+                    "output": workspace.output,
+                    "active": shown,
+                    "windows": windows,
+                    "layout": self.workspace_layout(&workspace.name).name(),
+                })
+            })
+            .collect();
+        ipc_set_string("/wayice_workspaces", &json!(workspaces).to_string());
+    }
 }
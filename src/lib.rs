@@ -6,6 +6,7 @@
     allow(dead_code, unused_imports)
 )]
 
+pub mod config;
 pub mod core;
 pub mod helpers;
 pub mod ipc;
@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use wayice::config::{config_path, Config};
 use wayice::platform::udev;
 use wayice::platform::winit;
 use wayice::platform::x11;
@@ -89,6 +92,48 @@ fn vulkan_init() {
     drop(image_dmabuf);
 }
 
+/// Validate wayice.ini without starting a backend, returning the exit code
+fn check_config() -> i32 {
+    let mut args = ::std::env::args().skip(2);
+    let path = match (args.next().as_deref(), args.next()) {
+        (Some("--config"), Some(path)) => PathBuf::from(path),
+        (None, _) => match config_path() {
+            Some(path) => path,
+            None => {
+                eprintln!("Could not determine the home directory, use --config PATH");
+                return 2;
+            }
+        },
+        _ => {
+            eprintln!("USAGE: wayice --check-config [--config PATH]");
+            return 2;
+        }
+    };
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            return 1;
+        }
+    };
+
+    let errors = Config::check(&text);
+    for err in &errors {
+        match err.line(&text) {
+            Some(line) => eprintln!("{}:{}: {}", path.display(), line, err),
+            None => eprintln!("{}: {}", path.display(), err),
+        }
+    }
+
+    if errors.is_empty() {
+        println!("{}: ok", path.display());
+        0
+    } else {
+        1
+    }
+}
+
 fn main() {
     if let Ok(env_filter) = tracing_subscriber::EnvFilter::try_from_default_env() {
         tracing_subscriber::fmt()
@@ -131,6 +176,9 @@ fn main() {
             tracing::info!("Starting wayice with x11 backend");
             x11::run_x11();
         }
+        Some("--check-config") => {
+            std::process::exit(check_config());
+        }
         Some(other) => {
             tracing::error!("Unknown backend: {}", other);
         }
@@ -143,6 +191,9 @@ fn main() {
                 for b in POSSIBLE_BACKENDS {
                     println!("\t{}", b);
                 }
+                println!();
+                println!("To validate the config file without starting wayice:");
+                println!("\twayice --check-config [--config PATH]");
             }
         }
     }
@@ -1,9 +1,12 @@
 use crate::core::state::{DndIcon, SurfaceDmabufFeedback};
 use crate::{
+    config::{AccelProfile, ClickMethod, Config, InputConfig, OutputConfig, ScrollMethod},
     core::drawing::*,
     core::render::*,
     core::state::{post_repaint, take_presentation_feedback, Backend, WayiceState},
-    shell::WindowElement,
+    helpers::output_utils::apply_output_config,
+    ipc::server::spawn_ipc_server,
+    shell::{Overview, SnapPreview, WindowElement, WindowSwitcher},
 };
 #[cfg(feature = "renderer_sync")]
 use smithay::backend::drm::compositor::PrimaryPlaneElement;
@@ -58,7 +61,7 @@ use smithay::{
             EventLoop, LoopHandle, RegistrationToken,
         },
         drm::{
-            control::{connector, crtc, Device, ModeTypeFlags},
+            control::{connector, crtc, Device, Mode as DrmMode, ModeTypeFlags},
             Device as _,
         },
         input::{DeviceCapability, Libinput},
@@ -136,6 +139,7 @@ pub struct UdevData {
     pointer_image: crate::core::cursor::Cursor,
     debug_flags: DebugFlags,
     keyboards: Vec<smithay::reexports::input::Device>,
+    input_devices: Vec<smithay::reexports::input::Device>,
 }
 
 impl UdevData {
@@ -207,6 +211,100 @@ impl Backend for UdevData {
             keyboard.led_update(led_state.into());
         }
     }
+
+    fn apply_input_config(&mut self, config: &Config) {
+        for device in self.input_devices.iter_mut() {
+            let device_config = config.input_for(device.name());
+            configure_input_device(device, &device_config);
+        }
+    }
+}
+
+/// Apply the `[input]` settings to a libinput device.
+///
+/// Options the device does not support are skipped.
+fn configure_input_device(device: &mut smithay::reexports::input::Device, config: &InputConfig) {
+    use smithay::reexports::input as libinput;
+
+    let name = device.name().to_string();
+    let check = |option: &str, result: Result<(), libinput::DeviceConfigError>| {
+        if let Err(err) = result {
+            debug!(device = name, option, ?err, "Failed to configure input device");
+        }
+    };
+
+    if let Some(enabled) = config.tap {
+        check("tap", device.config_tap_set_enabled(enabled));
+    }
+    if let Some(enabled) = config.natural_scroll {
+        check(
+            "natural_scroll",
+            device.config_scroll_set_natural_scroll_enabled(enabled),
+        );
+    }
+    if let Some(profile) = config.accel_profile {
+        let profile = match profile {
+            AccelProfile::Flat => libinput::AccelProfile::Flat,
+            AccelProfile::Adaptive => libinput::AccelProfile::Adaptive,
+        };
+        check("accel_profile", device.config_accel_set_profile(profile));
+    }
+    if let Some(speed) = config.accel_speed {
+        check("accel_speed", device.config_accel_set_speed(speed));
+    }
+    if let Some(enabled) = config.left_handed {
+        check("left_handed", device.config_left_handed_set(enabled));
+    }
+    if let Some(enabled) = config.middle_emulation {
+        check(
+            "middle_emulation",
+            device.config_middle_emulation_set_enabled(enabled),
+        );
+    }
+    if let Some(method) = config.scroll_method {
+        let method = match method {
+            ScrollMethod::NoScroll => libinput::ScrollMethod::NoScroll,
+            ScrollMethod::TwoFinger => libinput::ScrollMethod::TwoFinger,
+            ScrollMethod::Edge => libinput::ScrollMethod::Edge,
+            ScrollMethod::OnButtonDown => libinput::ScrollMethod::OnButtonDown,
+        };
+        check("scroll_method", device.config_scroll_set_method(method));
+    }
+    if let Some(enabled) = config.disable_while_typing {
+        check("disable_while_typing", device.config_dwt_set_enabled(enabled));
+    }
+    if let Some(method) = config.click_method {
+        let method = match method {
+            ClickMethod::ButtonAreas => libinput::ClickMethod::ButtonAreas,
+            ClickMethod::Clickfinger => libinput::ClickMethod::Clickfinger,
+        };
+        check("click_method", device.config_click_set_method(method));
+    }
+}
+
+/// Index of the mode of `modes` with the size `config` asks for.
+///
+/// Among modes of that size the one with the refresh rate closest to the
+/// configured one is picked, or the fastest if none is configured. `None`
+/// if no mode has that size.
+fn select_mode(modes: &[DrmMode], config: &OutputConfig) -> Option<usize> {
+    let (width, height) = config.mode?;
+    modes
+        .iter()
+        .enumerate()
+        .filter(|(_, mode)| {
+            let (w, h) = mode.size();
+            (w as i32, h as i32) == (width, height)
+        })
+        .min_by_key(|(_, mode)| {
+            // in mHz, like the configured refresh rate
+            let refresh = WlMode::from(**mode).refresh;
+            match config.refresh {
+                Some(target) => (refresh - target).abs(),
+                None => -refresh,
+            }
+        })
+        .map(|(index, _)| index)
 }
 
 pub fn run_udev() {
@@ -261,6 +359,7 @@ pub fn run_udev() {
         fps_texture: None,
         debug_flags: DebugFlags::empty(),
         keyboards: Vec::new(),
+        input_devices: Vec::new(),
     };
     let mut state = WayiceState::init(display, event_loop.handle(), data, true);
 
@@ -292,6 +391,10 @@ pub fn run_udev() {
         .insert_source(libinput_backend, move |mut event, _, data| {
             let dh = data.backend_data.dh.clone();
             if let InputEvent::DeviceAdded { device } = &mut event {
+                let device_config = data.config.input_for(device.name());
+                configure_input_device(device, &device_config);
+                data.backend_data.input_devices.push(device.clone());
+
                 if device.has_capability(DeviceCapability::Keyboard) {
                     if let Some(led_state) = data.seat.get_keyboard().map(|keyboard| keyboard.led_state()) {
                         device.led_update(led_state.into());
@@ -299,6 +402,8 @@ pub fn run_udev() {
                     data.backend_data.keyboards.push(device.clone());
                 }
             } else if let InputEvent::DeviceRemoved { ref device } = event {
+                data.backend_data.input_devices.retain(|item| item != device);
+
                 if device.has_capability(DeviceCapability::Keyboard) {
                     data.backend_data.keyboards.retain(|item| item != device);
                 }
@@ -477,11 +582,15 @@ pub fn run_udev() {
         })
         .unwrap();
 
+    let _ipc_runtime = spawn_ipc_server(&event_loop.handle());
+
     /*
      * Start XWayland if supported
      */
     #[cfg(feature = "xwayland")]
     state.start_xwayland();
+    #[cfg(not(feature = "xwayland"))]
+    state.run_autostart();
 
     /*
      * And run our loop
@@ -494,6 +603,7 @@ pub fn run_udev() {
         } else {
             state.space.refresh();
             state.popups.cleanup();
+            state.refresh_overview();
             display_handle.flush_clients().unwrap();
         }
     }
@@ -978,11 +1088,29 @@ impl WayiceState<UdevData> {
                 );
             }
         } else {
-            let mode_id = connector
-                .modes()
-                .iter()
-                .position(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
-                .unwrap_or(0);
+            let output_config = self.config.output(&output_name, &make, &model).cloned();
+            if output_config.as_ref().is_some_and(|config| !config.enabled) {
+                info!("Connector {} is disabled in the config", output_name);
+                return;
+            }
+
+            let preferred_mode_id = || {
+                connector
+                    .modes()
+                    .iter()
+                    .position(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+                    .unwrap_or(0)
+            };
+            let mode_id = match output_config.as_ref().filter(|config| config.mode.is_some()) {
+                Some(config) => select_mode(connector.modes(), config).unwrap_or_else(|| {
+                    warn!(
+                        "Mode {:?} requested for {} is not supported, using the preferred mode",
+                        config.mode, output_name
+                    );
+                    preferred_mode_id()
+                }),
+                None => preferred_mode_id(),
+            };
 
             let drm_mode = connector.modes()[mode_id];
             let wl_mode = WlMode::from(drm_mode);
@@ -995,7 +1123,6 @@ impl WayiceState<UdevData> {
                 }
             };
 
-            // change here for wayice.ini support
             let (phys_w, phys_h) = connector.size().unwrap_or((0, 0));
             let output = Output::new(
                 output_name,
@@ -1014,9 +1141,10 @@ impl WayiceState<UdevData> {
                 .fold(0, |acc, o| acc + self.space.output_geometry(o).unwrap().size.w);
             let position = (x, 0).into();
 
-            output.set_preferred(wl_mode);
+            output.set_preferred(WlMode::from(connector.modes()[preferred_mode_id()]));
             output.change_current_state(Some(wl_mode), None, None, Some(position));
             self.space.map_output(&output, position);
+            apply_output_config(&mut self.space, &output, output_config.as_ref());
 
             output.user_data().insert_if_missing(|| UdevOutputId {
                 crtc,
@@ -1193,7 +1321,7 @@ impl WayiceState<UdevData> {
         }
 
         // fixup window coordinates
-        crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
+        self.fixup_layout(self.pointer.current_location());
     }
 
     fn device_removed(&mut self, node: DrmNode) {
@@ -1231,7 +1359,7 @@ impl WayiceState<UdevData> {
             debug!("Dropping device");
         }
 
-        crate::shell::fixup_positions(&mut self.space, self.pointer.current_location());
+        self.fixup_layout(self.pointer.current_location());
     }
 
     fn frame_finish(&mut self, dev_id: DrmNode, crtc: crtc::Handle, metadata: &mut Option<DrmEventMetadata>) {
@@ -1487,7 +1615,9 @@ impl WayiceState<UdevData> {
             &self.dnd_icon,
             &mut self.cursor_status,
             &self.clock,
-            self.show_window_preview,
+            self.snap_preview.as_ref(),
+            self.switcher.as_ref(),
+            self.overview.as_ref(),
         );
         let reschedule = match &result {
             Ok(has_rendered) => !has_rendered,
@@ -1597,7 +1727,9 @@ fn render_surface<'a>(
     dnd_icon: &Option<DndIcon>,
     cursor_status: &mut CursorImageStatus,
     clock: &Clock<Monotonic>,
-    show_window_preview: bool,
+    snap_preview: Option<&SnapPreview>,
+    switcher: Option<&WindowSwitcher>,
+    overview: Option<&Overview>,
 ) -> Result<bool, SwapBuffersError> {
     let output_geometry = space.output_geometry(output).unwrap();
     let scale = Scale::from(output.current_scale().fractional_scale());
@@ -1667,6 +1799,11 @@ fn render_surface<'a>(
         }
     }
 
+    // draw the drop zone preview below the cursor
+    if let Some(element) = snap_preview.and_then(|p| p.render_element(output, scale)) {
+        custom_elements.push(CustomRenderElements::Solid(element));
+    }
+
     #[cfg(feature = "debug")]
     if let Some(element) = surface.fps_element.as_mut() {
         element.update_fps(surface.fps.avg().round() as u32);
@@ -1675,7 +1812,7 @@ fn render_surface<'a>(
     }
 
     let (elements, clear_color) =
-        output_elements(output, space, custom_elements, renderer, show_window_preview);
+        output_elements(output, space, custom_elements, renderer, switcher, overview);
     let SurfaceCompositorRenderResult {
         rendered,
         states,
@@ -1,11 +1,12 @@
+use crate::config::Config;
 use crate::helpers::output_utils;
-use crate::ipc::server::start_ipc_server;
+use crate::helpers::output_utils::{apply_output_config, output_config};
+use crate::ipc::server::spawn_ipc_server;
 use crate::ipc::shared_memory::ipc_set_string;
 use std::{
     sync::{atomic::Ordering, Mutex},
     time::Duration,
 };
-use tokio::runtime::Runtime;
 
 #[cfg(feature = "egl")]
 use smithay::backend::renderer::ImportEgl;
@@ -94,6 +95,7 @@ impl Backend for WinitData {
     }
     fn early_import(&mut self, _surface: &wl_surface::WlSurface) {}
     fn update_led_state(&mut self, _led_state: LedState) {}
+    fn apply_input_config(&mut self, _config: &Config) {}
 }
 
 pub fn run_winit() {
@@ -206,23 +208,20 @@ pub fn run_winit() {
         .shm_state
         .update_formats(state.backend_data.backend.renderer().shm_formats());
     state.space.map_output(&output, (0, 0));
+    apply_output_config(&mut state.space, &output, output_config(&state.config, &output));
+    state.fixup_layout(state.pointer.current_location());
 
     #[cfg(feature = "xwayland")]
     state.start_xwayland();
+    #[cfg(not(feature = "xwayland"))]
+    state.run_autostart();
 
     info!("Initialization completed, starting the main loop.");
 
     let mut pointer_element = PointerElement::default();
 
-    // Start the IPC server
-    let rt = Runtime::new().unwrap();
-
-    // Start the IPC server in a non-blocking way using the runtime
-    rt.spawn(async {
-        if let Err(e) = start_ipc_server().await {
-            eprintln!("Failed to start IPC server: {:?}", e);
-        }
-    });
+    // Start the IPC server in a non-blocking way using its own runtime
+    let _ipc_runtime = spawn_ipc_server(&event_loop.handle());
 
     while state.running.load(Ordering::SeqCst) {
         let status = winit.dispatch_new_events(|event| match event {
@@ -236,7 +235,7 @@ pub fn run_winit() {
                 };
                 output.change_current_state(Some(mode), None, None, None);
                 output.set_preferred(mode);
-                crate::shell::fixup_positions(&mut state.space, state.pointer.current_location());
+                state.fixup_layout(state.pointer.current_location());
             }
             WinitEvent::Input(event) => state.process_input_event_windowed(event, OUTPUT_NAME),
             _ => (),
@@ -273,7 +272,9 @@ pub fn run_winit() {
             *full_redraw = full_redraw.saturating_sub(1);
             let space = &mut state.space;
             let damage_tracker = &mut state.backend_data.damage_tracker;
-            let show_window_preview = state.show_window_preview;
+            let snap_preview = state.snap_preview.as_ref();
+            let switcher = state.switcher.as_ref();
+            let overview = state.overview.as_ref();
 
             let dnd_icon = state.dnd_icon.as_ref();
 
@@ -350,6 +351,11 @@ pub fn run_winit() {
                     }
                 }
 
+                // draw the drop zone preview below the cursor
+                if let Some(element) = snap_preview.and_then(|p| p.render_element(&output, scale)) {
+                    elements.push(CustomRenderElements::Solid(element));
+                }
+
                 #[cfg(feature = "debug")]
                 elements.push(CustomRenderElements::Fps(fps_element.clone()));
 
@@ -360,7 +366,8 @@ pub fn run_winit() {
                     renderer,
                     damage_tracker,
                     age,
-                    show_window_preview,
+                    switcher,
+                    overview,
                 )
                 .map_err(|err| match err {
                     OutputDamageTrackerError::Rendering(err) => err.into(),
@@ -447,6 +454,7 @@ pub fn run_winit() {
         } else {
             state.space.refresh();
             state.popups.cleanup();
+            state.refresh_overview();
             display_handle.flush_clients().unwrap();
         }
         // add to the shared memory a list of all display outputs rendered
@@ -3,10 +3,13 @@ use std::{
     time::Duration,
 };
 
+use crate::config::Config;
 use crate::{
     core::drawing::*,
     core::render::*,
     core::state::{post_repaint, take_presentation_feedback, Backend, WayiceState},
+    helpers::output_utils::{apply_output_config, output_config},
+    ipc::server::spawn_ipc_server,
 };
 #[cfg(feature = "egl")]
 use smithay::backend::renderer::ImportEgl;
@@ -93,6 +96,7 @@ impl Backend for X11Data {
     }
     fn early_import(&mut self, _surface: &wl_surface::WlSurface) {}
     fn update_led_state(&mut self, _led_state: LedState) {}
+    fn apply_input_config(&mut self, _config: &Config) {}
 }
 
 pub fn run_x11() {
@@ -252,6 +256,8 @@ pub fn run_x11() {
         .shm_state
         .update_formats(state.backend_data.renderer.shm_formats());
     state.space.map_output(&output, (0, 0));
+    apply_output_config(&mut state.space, &output, output_config(&state.config, &output));
+    state.fixup_layout(state.pointer.current_location());
 
     let output_clone = output.clone();
     event_loop
@@ -271,7 +277,7 @@ pub fn run_x11() {
                 output.delete_mode(output.current_mode().unwrap());
                 output.change_current_state(Some(data.backend_data.mode), None, None, None);
                 output.set_preferred(data.backend_data.mode);
-                crate::shell::fixup_positions(&mut data.space, data.pointer.current_location());
+                data.fixup_layout(data.pointer.current_location());
 
                 data.backend_data.render = true;
             }
@@ -288,11 +294,16 @@ pub fn run_x11() {
 
     #[cfg(feature = "xwayland")]
     state.start_xwayland();
+    #[cfg(not(feature = "xwayland"))]
+    state.run_autostart();
 
     info!("Initialization completed, starting the main loop.");
 
     let mut pointer_element = PointerElement::default();
 
+    // Start the IPC server in a non-blocking way using its own runtime
+    let _ipc_runtime = spawn_ipc_server(&event_loop.handle());
+
     while state.running.load(Ordering::SeqCst) {
         if state.backend_data.render {
             profiling::scope!("render_frame");
@@ -376,6 +387,15 @@ pub fn run_x11() {
                 }
             }
 
+            // draw the drop zone preview below the cursor
+            if let Some(element) = state
+                .snap_preview
+                .as_ref()
+                .and_then(|p| p.render_element(&output, scale))
+            {
+                elements.push(CustomRenderElements::Solid(element));
+            }
+
             #[cfg(feature = "debug")]
             elements.push(CustomRenderElements::Fps(fps_element.clone()));
 
@@ -386,7 +406,8 @@ pub fn run_x11() {
                 &mut backend_data.renderer,
                 &mut backend_data.damage_tracker,
                 age.into(),
-                state.show_window_preview,
+                state.switcher.as_ref(),
+                state.overview.as_ref(),
             );
 
             match render_res {
@@ -462,6 +483,7 @@ pub fn run_x11() {
         } else {
             state.space.refresh();
             state.popups.cleanup();
+            state.refresh_overview();
             display_handle.flush_clients().unwrap();
         }
     }
@@ -27,7 +27,6 @@ use smithay::{
     wayland::{compositor::SurfaceData as WlSurfaceData, dmabuf::DmabufFeedback, seat::WaylandFocus},
 };
 
-use super::ssd::HEADER_BAR_HEIGHT;
 use crate::{core::focus::PointerFocusTarget, core::state::Backend, core::state::WayiceState};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,15 +38,11 @@ impl WindowElement {
         location: Point<f64, Logical>,
         window_type: WindowSurfaceType,
     ) -> Option<(PointerFocusTarget, Point<i32, Logical>)> {
-        let state = self.decoration_state();
-        if state.is_ssd && location.y < HEADER_BAR_HEIGHT as f64 {
+        let header_height = self.decoration_state().header_height();
+        if location.y < header_height as f64 {
             return Some((PointerFocusTarget::SSD(SSD(self.clone())), Point::default()));
         }
-        let offset = if state.is_ssd {
-            Point::from((0, HEADER_BAR_HEIGHT))
-        } else {
-            Point::default()
-        };
+        let offset = Point::from((0, header_height));
 
         let surface_under = self.0.surface_under(location - offset.to_f64(), window_type);
         let (under, loc) = match self.0.underlying_surface() {
@@ -165,7 +160,7 @@ impl<BackendData: Backend> PointerTarget<WayiceState<BackendData>> for SSD {
         event: &MotionEvent,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.pointer_enter(event.location);
         }
     }
@@ -176,7 +171,7 @@ impl<BackendData: Backend> PointerTarget<WayiceState<BackendData>> for SSD {
         event: &MotionEvent,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.pointer_enter(event.location);
         }
     }
@@ -194,7 +189,7 @@ impl<BackendData: Backend> PointerTarget<WayiceState<BackendData>> for SSD {
         event: &ButtonEvent,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.clicked(seat, data, &self.0, event.serial);
             state.header_bar.touch_down(seat, data, &self.0, event.serial);
         }
@@ -215,7 +210,7 @@ impl<BackendData: Backend> PointerTarget<WayiceState<BackendData>> for SSD {
         _time: u32,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.pointer_leave();
         }
     }
@@ -286,7 +281,7 @@ impl<BackendData: Backend> TouchTarget<WayiceState<BackendData>> for SSD {
         _seq: Serial,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.pointer_enter(event.location);
             state.header_bar.touch_down(seat, data, &self.0, event.serial);
         }
@@ -300,7 +295,7 @@ impl<BackendData: Backend> TouchTarget<WayiceState<BackendData>> for SSD {
         _seq: Serial,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.touch_up(seat, data, &self.0, event.serial);
         }
     }
@@ -313,7 +308,7 @@ impl<BackendData: Backend> TouchTarget<WayiceState<BackendData>> for SSD {
         _seq: Serial,
     ) {
         let mut state = self.0.decoration_state();
-        if state.is_ssd {
+        if state.has_header() {
             state.header_bar.pointer_enter(event.location);
         }
     }
@@ -356,35 +351,33 @@ impl<BackendData: Backend> TouchTarget<WayiceState<BackendData>> for SSD {
 impl SpaceElement for WindowElement {
     fn geometry(&self) -> Rectangle<i32, Logical> {
         let mut geo = SpaceElement::geometry(&self.0);
-        if self.decoration_state().is_ssd {
-            geo.size.h += HEADER_BAR_HEIGHT;
-        }
+        geo.size.h += self.decoration_state().header_height();
         geo
     }
     fn bbox(&self) -> Rectangle<i32, Logical> {
         let mut bbox = SpaceElement::bbox(&self.0);
-        if self.decoration_state().is_ssd {
-            bbox.size.h += HEADER_BAR_HEIGHT;
-        }
+        bbox.size.h += self.decoration_state().header_height();
         bbox
     }
     fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
-        if self.decoration_state().is_ssd {
-            point.y < HEADER_BAR_HEIGHT as f64
+        let header_height = self.decoration_state().header_height();
+        if header_height > 0 {
+            point.y < header_height as f64
                 || SpaceElement::is_in_input_region(
                     &self.0,
-                    &(*point - Point::from((0.0, HEADER_BAR_HEIGHT as f64))),
+                    &(*point - Point::from((0.0, header_height as f64))),
                 )
         } else {
             SpaceElement::is_in_input_region(&self.0, point)
         }
     }
     fn z_index(&self) -> u8 {
-        SpaceElement::z_index(&self.0)
+        self.stacking_z_index(SpaceElement::z_index(&self.0))
     }
 
     fn set_activate(&self, activated: bool) {
         SpaceElement::set_activate(&self.0, activated);
+        self.decoration_state().header_bar.focused = activated;
     }
     fn output_enter(&self, output: &Output, overlap: Rectangle<i32, Logical>) {
         SpaceElement::output_enter(&self.0, output, overlap);
@@ -427,9 +420,10 @@ where
         scale: Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
+        let alpha = alpha * self.opacity();
         let window_bbox = SpaceElement::bbox(&self.0);
 
-        if self.decoration_state().is_ssd && !window_bbox.is_empty() {
+        if self.decoration_state().has_header() && !window_bbox.is_empty() {
             let window_geo = SpaceElement::geometry(&self.0);
 
             let mut state = self.decoration_state();
@@ -443,7 +437,7 @@ where
                 alpha,
             );
 
-            location.y += (scale.y * HEADER_BAR_HEIGHT as f64) as i32;
+            location.y += (scale.y * state.header_height() as f64) as i32;
 
             let window_elements =
                 AsRenderElements::render_elements(&self.0, renderer, location, scale, alpha);
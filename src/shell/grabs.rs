@@ -4,10 +4,10 @@ use smithay::{
     desktop::{space::SpaceElement, WindowSurface},
     input::{
         pointer::{
-            AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent, GesturePinchBeginEvent,
-            GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
-            GestureSwipeUpdateEvent, GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab,
-            PointerInnerHandle, RelativeMotionEvent,
+            AxisFrame, ButtonEvent, Focus, GestureHoldBeginEvent, GestureHoldEndEvent,
+            GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent,
+            GestureSwipeEndEvent, GestureSwipeUpdateEvent, GrabStartData as PointerGrabStartData,
+            MotionEvent, PointerGrab, PointerInnerHandle, RelativeMotionEvent,
         },
         touch::{GrabStartData as TouchGrabStartData, TouchGrab},
     },
@@ -43,9 +43,10 @@ impl<BackendData: Backend> PointerGrab<WayiceState<BackendData>> for PointerMove
 
         let delta = event.location - self.start_data.location;
         let new_location = self.initial_window_location.to_f64() + delta;
+        let new_location = data.snap_move(&self.window, new_location.to_i32_round(), event.location);
 
-        data.space
-            .map_element(self.window.clone(), new_location.to_i32_round(), true);
+        data.dragged_window = Some(self.window.clone());
+        data.space.map_element(self.window.clone(), new_location, true);
     }
 
     fn relative_motion(
@@ -164,7 +165,11 @@ impl<BackendData: Backend> PointerGrab<WayiceState<BackendData>> for PointerMove
         &self.start_data
     }
 
-    fn unset(&mut self, _data: &mut WayiceState<BackendData>) {}
+    fn unset(&mut self, data: &mut WayiceState<BackendData>) {
+        data.drop_into_group(&self.window);
+        data.drop_window(&self.window);
+        data.drop_in_zone(&self.window);
+    }
 }
 
 pub struct TouchMoveSurfaceGrab<BackendData: Backend + 'static> {
@@ -219,8 +224,9 @@ impl<BackendData: Backend> TouchGrab<WayiceState<BackendData>> for TouchMoveSurf
 
         let delta = event.location - self.start_data.location;
         let new_location = self.initial_window_location.to_f64() + delta;
-        data.space
-            .map_element(self.window.clone(), new_location.to_i32_round(), true);
+        let new_location = data.snap_move(&self.window, new_location.to_i32_round(), event.location);
+        data.dragged_window = Some(self.window.clone());
+        data.space.map_element(self.window.clone(), new_location, true);
     }
 
     fn frame(
@@ -265,7 +271,11 @@ impl<BackendData: Backend> TouchGrab<WayiceState<BackendData>> for TouchMoveSurf
         &self.start_data
     }
 
-    fn unset(&mut self, _data: &mut WayiceState<BackendData>) {}
+    fn unset(&mut self, data: &mut WayiceState<BackendData>) {
+        data.drop_into_group(&self.window);
+        data.drop_window(&self.window);
+        data.drop_in_zone(&self.window);
+    }
 }
 
 bitflags::bitflags! {
@@ -867,3 +877,79 @@ impl<BackendData: Backend> TouchGrab<WayiceState<BackendData>> for TouchResizeSu
 
     fn unset(&mut self, _data: &mut WayiceState<BackendData>) {}
 }
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Start moving `window` with the pointer, independently of what the client asked for
+    pub fn start_pointer_move(
+        &mut self,
+        window: WindowElement,
+        start_data: PointerGrabStartData<WayiceState<BackendData>>,
+        serial: Serial,
+    ) {
+        let Some(initial_window_location) = self.space.element_location(&window) else {
+            return;
+        };
+        let grab = PointerMoveSurfaceGrab {
+            start_data,
+            window,
+            initial_window_location,
+        };
+        let pointer = self.pointer.clone();
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+
+    /// Start resizing `window` with the pointer, from the corner of the
+    /// quadrant the grab started in
+    pub fn start_pointer_resize(
+        &mut self,
+        window: WindowElement,
+        start_data: PointerGrabStartData<WayiceState<BackendData>>,
+        serial: Serial,
+    ) {
+        let Some(initial_window_location) = self.space.element_location(&window) else {
+            return;
+        };
+        let initial_window_size = window.geometry().size;
+
+        let relative = start_data.location - initial_window_location.to_f64();
+        let horizontal = if relative.x < initial_window_size.w as f64 / 2.0 {
+            ResizeEdge::LEFT
+        } else {
+            ResizeEdge::RIGHT
+        };
+        let vertical = if relative.y < initial_window_size.h as f64 / 2.0 {
+            ResizeEdge::TOP
+        } else {
+            ResizeEdge::BOTTOM
+        };
+        let edges = horizontal | vertical;
+
+        // X11 windows that are not associated with a surface yet can't be resized
+        let Some(surface) = window.wl_surface() else {
+            return;
+        };
+        with_states(&surface, |states| {
+            states
+                .data_map
+                .get::<RefCell<SurfaceData>>()
+                .unwrap()
+                .borrow_mut()
+                .resize_state = ResizeState::Resizing(ResizeData {
+                edges,
+                initial_window_location,
+                initial_window_size,
+            });
+        });
+
+        let grab = PointerResizeSurfaceGrab {
+            start_data,
+            window,
+            edges,
+            initial_window_location,
+            initial_window_size,
+            last_window_size: initial_window_size,
+        };
+        let pointer = self.pointer.clone();
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+}
@@ -0,0 +1,306 @@
+use smithay::{
+    desktop::{space::SpaceElement, WindowSurface},
+    utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::Direction,
+    core::state::{Backend, WayiceState},
+};
+
+use super::{ssd::Tabs, WindowElement};
+
+/// Windows sharing one frame, of which only the active tab is mapped
+#[derive(Debug)]
+pub struct WindowGroup {
+    /// The windows in tab order
+    windows: Vec<WindowElement>,
+    active: usize,
+    /// Whether the tabs are stacked title bars instead of side by side
+    stacked: bool,
+}
+
+impl WindowGroup {
+    pub fn windows(&self) -> &[WindowElement] {
+        &self.windows
+    }
+
+    /// The window of the group that is shown
+    pub fn active_window(&self) -> &WindowElement {
+        &self.windows[self.active]
+    }
+
+    /// Show the tabs of the group in the header bars of its windows
+    fn update_tabs(&self) {
+        for window in &self.windows {
+            window.set_tabs(Some(Tabs {
+                count: self.windows.len(),
+                active: self.active,
+                stacked: self.stacked,
+            }));
+        }
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    fn group_of(&self, window: &WindowElement) -> Option<usize> {
+        self.groups.iter().position(|g| g.windows.contains(window))
+    }
+
+    /// Put `new` where the active tab `old` of a group is, hiding `old`.
+    ///
+    /// `new` takes over the tile of `old`, or its place on a hidden workspace
+    /// or among the minimized windows.
+    fn take_place(&mut self, old: &WindowElement, new: &WindowElement) {
+        new.set_floating(old.is_floating());
+        self.workspaces.swap_windows(old, new);
+        for minimized in self.minimized.iter_mut().filter(|m| m.window == *old) {
+            minimized.window = new.clone();
+        }
+        let Some(location) = self.space.element_location(old) else {
+            return;
+        };
+        let focused = self.focused_window().as_ref() == Some(old);
+        let geometry = old.geometry();
+
+        self.space.unmap_elem(old);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = old.0.x11_surface() {
+            let _ = surface.set_hidden(true);
+        }
+        self.space.map_element(new.clone(), location, false);
+        // the client only draws below the title bars
+        let header_height = new.decoration_state().header_height();
+        let size: Size<i32, Logical> = (geometry.size.w, (geometry.size.h - header_height).max(1)).into();
+        match new.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| state.size = Some(size));
+                if toplevel.is_initial_configure_sent() {
+                    toplevel.send_pending_configure();
+                }
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                let _ = surface.set_hidden(false);
+                let _ = surface.configure(Rectangle::from_loc_and_size(location, size));
+            }
+        }
+
+        self.arrange_all();
+        if focused {
+            self.focus_window(new, SERIAL_COUNTER.next_serial());
+        }
+        self.ipc_shm_update_window_list();
+    }
+
+    /// Show the tab at `index` of the group `group`
+    fn show_tab(&mut self, group: usize, index: usize) {
+        let old = self.groups[group].active_window().clone();
+        let new = self.groups[group].windows[index].clone();
+        self.groups[group].active = index;
+        self.groups[group].update_tabs();
+        if old != new {
+            debug!(tab = index, "Switching tab");
+            self.take_place(&old, &new);
+        }
+    }
+
+    /// Show the tab at `index` of the group of `window`, as clicked in its
+    /// header bar
+    pub fn show_tab_of(&mut self, window: &WindowElement, index: usize) {
+        if let Some(group) = self.group_of(window) {
+            if index < self.groups[group].windows.len() {
+                self.show_tab(group, index);
+            }
+        }
+    }
+
+    /// Show the next tab of the group of the focused window, or a previous one
+    /// if `offset` is negative
+    pub fn focus_tab(&mut self, offset: i32) {
+        let Some(group) = self.focused_window().and_then(|w| self.group_of(&w)) else {
+            return;
+        };
+        let count = self.groups[group].windows.len() as i32;
+        let index = (self.groups[group].active as i32 + offset).rem_euclid(count) as usize;
+        self.show_tab(group, index);
+    }
+
+    /// Add `window` to the group of `target` as its active tab, starting a
+    /// group if `target` is in none
+    pub fn join_group(&mut self, window: &WindowElement, target: &WindowElement) {
+        if window == target
+            || self
+                .group_of(window)
+                .is_some_and(|g| Some(g) == self.group_of(target))
+        {
+            return;
+        }
+        if self.space.element_location(target).is_none() {
+            return;
+        }
+        self.remove_from_group(window);
+
+        let group = match self.group_of(target) {
+            Some(group) => group,
+            None => {
+                self.groups.push(WindowGroup {
+                    windows: vec![target.clone()],
+                    active: 0,
+                    stacked: false,
+                });
+                self.groups.len() - 1
+            }
+        };
+        debug!(
+            tabs = self.groups[group].windows.len() + 1,
+            "Adding window to group"
+        );
+        self.groups[group].windows.push(window.clone());
+        self.groups[group].active = self.groups[group].windows.len() - 1;
+        self.groups[group].update_tabs();
+        self.take_place(target, window);
+        self.focus_window(window, SERIAL_COUNTER.next_serial());
+    }
+
+    /// Add the focused window to the group of the nearest window in
+    /// `direction`
+    pub fn group_direction(&mut self, direction: Direction) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let Some(from) = self.space.element_geometry(&window) else {
+            return;
+        };
+        if let Some(target) = self.neighbor(from, Some(&window), direction, |_| true) {
+            self.join_group(&window, &target);
+        }
+    }
+
+    /// Add a window released by a move grab to the group of the window it
+    /// was dropped on the header bar of
+    pub fn drop_into_group(&mut self, window: &WindowElement) {
+        if self.dragged_window.as_ref() != Some(window) {
+            return;
+        }
+        let Some(geometry) = self.space.element_geometry(window) else {
+            return;
+        };
+        // the middle of the header bar of the dropped window
+        let header_height = window.decoration_state().header_height();
+        let point =
+            geometry.loc.to_f64() + Point::from((geometry.size.w as f64 / 2.0, header_height as f64 / 2.0));
+        let target = self
+            .space
+            .elements()
+            .rev()
+            .filter(|w| *w != window)
+            .find(|w| {
+                let Some(geometry) = self.space.element_geometry(w) else {
+                    return false;
+                };
+                let header_height = w.decoration_state().header_height();
+                Rectangle::from_loc_and_size(geometry.loc, (geometry.size.w, header_height))
+                    .to_f64()
+                    .contains(point)
+            })
+            .cloned();
+        if let Some(target) = target {
+            // the window goes into the group instead of a drop zone
+            self.snap_preview = None;
+            self.join_group(window, &target);
+        }
+    }
+
+    /// Take `window` out of its group, the next tab taking its place if it
+    /// is the shown one.
+    ///
+    /// A group left with one window is dissolved.
+    fn remove_from_group(&mut self, window: &WindowElement) {
+        let Some(group) = self.group_of(window) else {
+            return;
+        };
+        let index = self.groups[group]
+            .windows
+            .iter()
+            .position(|w| w == window)
+            .unwrap();
+        if self.groups[group].active == index {
+            let next = if index + 1 < self.groups[group].windows.len() {
+                index + 1
+            } else {
+                index - 1
+            };
+            self.show_tab(group, next);
+        }
+
+        let group_ref = &mut self.groups[group];
+        group_ref.windows.remove(index);
+        if group_ref.active > index {
+            group_ref.active -= 1;
+        }
+        window.set_tabs(None);
+        if group_ref.windows.len() > 1 {
+            group_ref.update_tabs();
+        } else {
+            debug!("Dissolving window group");
+            for window in &group_ref.windows {
+                window.set_tabs(None);
+            }
+            self.groups.remove(group);
+            self.arrange_all();
+        }
+    }
+
+    /// Take the focused window out of its group, placing it next to it
+    pub fn ungroup_window(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        if self.group_of(&window).is_none() {
+            return;
+        }
+        let output = self.window_output(&window);
+        debug!("Taking window out of its group");
+        self.remove_from_group(&window);
+
+        self.place_window_on_output(&window, output.as_ref(), true);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.set_hidden(false);
+        }
+        self.arrange_all();
+        self.focus_window(&window, SERIAL_COUNTER.next_serial());
+        self.ipc_shm_update_window_list();
+    }
+
+    /// Switch the group of the focused window between tabs side by side and
+    /// stacked title bars
+    pub fn toggle_group_layout(&mut self) {
+        let Some(group) = self.focused_window().and_then(|w| self.group_of(&w)) else {
+            return;
+        };
+        let group = &mut self.groups[group];
+        group.stacked = !group.stacked;
+        debug!(stacked = group.stacked, "Changing group layout");
+        group.update_tabs();
+        // the header bar changed height
+        self.arrange_all();
+    }
+
+    /// Drop the grouped windows `matches` accepts from their groups, as they
+    /// were closed
+    pub fn forget_grouped(&mut self, matches: impl Fn(&WindowElement) -> bool) {
+        let closed: Vec<WindowElement> = self
+            .groups
+            .iter()
+            .flat_map(|g| g.windows.iter())
+            .filter(|w| matches(w))
+            .cloned()
+            .collect();
+        for window in closed {
+            self.remove_from_group(&window);
+        }
+    }
+}
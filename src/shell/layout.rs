@@ -0,0 +1,214 @@
+use smithay::utils::{Logical, Rectangle};
+
+use crate::config::LayoutKind;
+
+/// Splits an area into tiles
+pub trait Layout {
+    /// Rectangles of `count` tiles in `area`, the master tile first.
+    ///
+    /// `master_ratio` is the share of the area the master tile takes.
+    fn arrange(
+        &self,
+        area: Rectangle<i32, Logical>,
+        count: usize,
+        master_ratio: f64,
+    ) -> Vec<Rectangle<i32, Logical>>;
+}
+
+/// The master tile on the left, the other tiles stacked on the right
+pub struct MasterStack;
+
+/// Every tile takes part of the remaining space, splitting it along its
+/// longer side
+pub struct Dwindle;
+
+/// Every tile covers the whole area
+pub struct Monocle;
+
+/// The layout arranging tiles for `kind`, or `None` if windows float or
+/// scroll, as the scrolling layout keeps columns of its own
+pub fn layout_for(kind: LayoutKind) -> Option<&'static dyn Layout> {
+    match kind {
+        LayoutKind::Floating | LayoutKind::Scrolling => None,
+        LayoutKind::MasterStack => Some(&MasterStack),
+        LayoutKind::Dwindle => Some(&Dwindle),
+        LayoutKind::Monocle => Some(&Monocle),
+    }
+}
+
+/// Split `area` in two, the first part taking `ratio` of its width, or of
+/// its height if `vertical`
+fn split(
+    area: Rectangle<i32, Logical>,
+    ratio: f64,
+    vertical: bool,
+) -> (Rectangle<i32, Logical>, Rectangle<i32, Logical>) {
+    let mut first = area;
+    let mut second = area;
+    if vertical {
+        first.size.h = (area.size.h as f64 * ratio).round() as i32;
+        second.loc.y += first.size.h;
+        second.size.h -= first.size.h;
+    } else {
+        first.size.w = (area.size.w as f64 * ratio).round() as i32;
+        second.loc.x += first.size.w;
+        second.size.w -= first.size.w;
+    }
+    (first, second)
+}
+
+impl Layout for MasterStack {
+    fn arrange(
+        &self,
+        area: Rectangle<i32, Logical>,
+        count: usize,
+        master_ratio: f64,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        if count <= 1 {
+            return vec![area; count];
+        }
+
+        let (master, stack) = split(area, master_ratio, false);
+        let mut tiles = vec![master];
+        let rows = (count - 1) as i32;
+        for row in 0..rows {
+            // spread the rounding error over the rows instead of leaving a gap at the bottom
+            let top = stack.size.h * row / rows;
+            let bottom = stack.size.h * (row + 1) / rows;
+            tiles.push(Rectangle::from_loc_and_size(
+                (stack.loc.x, stack.loc.y + top),
+                (stack.size.w, bottom - top),
+            ));
+        }
+        tiles
+    }
+}
+
+impl Layout for Dwindle {
+    fn arrange(
+        &self,
+        area: Rectangle<i32, Logical>,
+        count: usize,
+        master_ratio: f64,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let mut tiles = Vec::with_capacity(count);
+        let mut remaining = area;
+        for index in 0..count {
+            if index + 1 == count {
+                tiles.push(remaining);
+                break;
+            }
+            let ratio = if index == 0 { master_ratio } else { 0.5 };
+            let vertical = remaining.size.h > remaining.size.w;
+            let (tile, rest) = split(remaining, ratio, vertical);
+            tiles.push(tile);
+            remaining = rest;
+        }
+        tiles
+    }
+}
+
+impl Layout for Monocle {
+    fn arrange(
+        &self,
+        area: Rectangle<i32, Logical>,
+        count: usize,
+        _master_ratio: f64,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        vec![area; count]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((10, 20), (1000, 700))
+    }
+
+    /// Check that `tiles` lie within `area` and cover all of it without
+    /// overlapping
+    fn assert_covers(tiles: &[Rectangle<i32, Logical>], area: Rectangle<i32, Logical>) {
+        let size = |r: Rectangle<i32, Logical>| r.size.w as i64 * r.size.h as i64;
+        for (index, tile) in tiles.iter().enumerate() {
+            assert!(area.contains_rect(*tile), "{tile:?} is outside of {area:?}");
+            for other in &tiles[index + 1..] {
+                assert_eq!(
+                    tile.intersection(*other).map_or(0, size),
+                    0,
+                    "{tile:?} overlaps {other:?}"
+                );
+            }
+        }
+        assert_eq!(tiles.iter().map(|t| size(*t)).sum::<i64>(), size(area));
+    }
+
+    #[test]
+    fn master_stack_without_windows() {
+        assert!(MasterStack.arrange(area(), 0, 0.5).is_empty());
+    }
+
+    #[test]
+    fn master_stack_single_window_fills_area() {
+        assert_eq!(MasterStack.arrange(area(), 1, 0.5), vec![area()]);
+    }
+
+    #[test]
+    fn master_stack_splits_master_and_stack() {
+        let tiles = MasterStack.arrange(area(), 4, 0.6);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[0], Rectangle::from_loc_and_size((10, 20), (600, 700)));
+        // 700 does not divide by 3, the rows differ by a pixel at most
+        assert_eq!(tiles[1], Rectangle::from_loc_and_size((610, 20), (400, 233)));
+        assert_eq!(tiles[2], Rectangle::from_loc_and_size((610, 253), (400, 233)));
+        assert_eq!(tiles[3], Rectangle::from_loc_and_size((610, 486), (400, 234)));
+        assert_covers(&tiles, area());
+    }
+
+    #[test]
+    fn master_stack_rounding_leaves_no_gaps() {
+        let area = Rectangle::from_loc_and_size((0, 0), (1001, 997));
+        for count in 2..10 {
+            assert_covers(&MasterStack.arrange(area, count, 0.55), area);
+        }
+    }
+
+    #[test]
+    fn dwindle_without_windows() {
+        assert!(Dwindle.arrange(area(), 0, 0.5).is_empty());
+    }
+
+    #[test]
+    fn dwindle_single_window_fills_area() {
+        assert_eq!(Dwindle.arrange(area(), 1, 0.5), vec![area()]);
+    }
+
+    #[test]
+    fn dwindle_splits_along_longer_side() {
+        let tiles = Dwindle.arrange(area(), 3, 0.5);
+        assert_eq!(
+            tiles,
+            vec![
+                Rectangle::from_loc_and_size((10, 20), (500, 700)),
+                Rectangle::from_loc_and_size((510, 20), (500, 350)),
+                Rectangle::from_loc_and_size((510, 370), (500, 350)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dwindle_rounding_leaves_no_gaps() {
+        let area = Rectangle::from_loc_and_size((0, 0), (1001, 997));
+        for count in 2..10 {
+            assert_covers(&Dwindle.arrange(area, count, 0.55), area);
+        }
+    }
+
+    #[test]
+    fn monocle_stacks_every_window_on_the_area() {
+        assert!(Monocle.arrange(area(), 0, 0.5).is_empty());
+        assert_eq!(Monocle.arrange(area(), 1, 0.5), vec![area()]);
+        assert_eq!(Monocle.arrange(area(), 3, 0.5), vec![area(); 3]);
+    }
+}
@@ -0,0 +1,133 @@
+use smithay::utils::{Logical, Point, SERIAL_COUNTER};
+use tracing::debug;
+
+use crate::core::state::{Backend, WayiceState};
+
+use super::{switcher::app_of, FullscreenSurface, WindowElement};
+
+/// A window taken out of the space until it is restored
+#[derive(Debug)]
+pub struct MinimizedWindow {
+    pub window: WindowElement,
+    /// Name of the output the window was on
+    output: String,
+    /// Location of the window relative to that output
+    location: Point<i32, Logical>,
+    fullscreen: bool,
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    pub fn is_minimized(&self, window: &WindowElement) -> bool {
+        self.minimized.iter().any(|m| m.window == *window)
+    }
+
+    /// Unmap `window` and keep it in the list of minimized windows, handing
+    /// the focus to the topmost window of its output if it had it
+    pub fn minimize_window(&mut self, window: &WindowElement) {
+        let (Some(location), Some(output)) =
+            (self.space.element_location(window), self.window_output(window))
+        else {
+            return;
+        };
+        let origin = self
+            .space
+            .output_geometry(&output)
+            .map(|geo| geo.loc)
+            .unwrap_or_default();
+        let fullscreen = output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|f| f.get())
+            .is_some_and(|w| w == *window);
+        if fullscreen {
+            output.user_data().get::<FullscreenSurface>().unwrap().clear();
+        }
+        let focused = self.focused_window().as_ref() == Some(window);
+
+        debug!(output = output.name(), "Minimizing window");
+        self.space.unmap_elem(window);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.set_hidden(true);
+        }
+        self.minimized.push(MinimizedWindow {
+            window: window.clone(),
+            output: output.name(),
+            location: location - origin,
+            fullscreen,
+        });
+
+        self.arrange_all();
+        if focused {
+            self.focus_output(&output);
+        }
+        self.ipc_shm_update_window_list();
+        self.ipc_shm_update_minimized();
+    }
+
+    /// Map a minimized window where it was, or on the current output if its
+    /// output is gone, and focus it
+    pub fn restore_window(&mut self, window: &WindowElement) {
+        let Some(index) = self.minimized.iter().position(|m| m.window == *window) else {
+            return;
+        };
+        let minimized = self.minimized.remove(index);
+        let Some(output) = self
+            .output_named(&minimized.output)
+            .or_else(|| self.current_output())
+        else {
+            return;
+        };
+        let origin = self
+            .space
+            .output_geometry(&output)
+            .map(|geo| geo.loc)
+            .unwrap_or_default();
+
+        debug!(output = output.name(), "Restoring minimized window");
+        self.space
+            .map_element(window.clone(), origin + minimized.location, true);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.set_hidden(false);
+        }
+        if minimized.fullscreen {
+            output.user_data().insert_if_missing(FullscreenSurface::default);
+            output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .unwrap()
+                .set(window.clone());
+        }
+
+        self.arrange_all();
+        self.focus_window(window, SERIAL_COUNTER.next_serial());
+        self.ipc_shm_update_window_list();
+        self.ipc_shm_update_minimized();
+    }
+
+    /// Restore the most recently minimized window, or the most recently
+    /// minimized one of `app` if given, matched against the `app_id` of
+    /// Wayland windows and the class of X11 windows
+    pub fn restore_minimized(&mut self, app: Option<&str>) {
+        let window = self
+            .minimized
+            .iter()
+            .rev()
+            .map(|m| &m.window)
+            .find(|w| app.map_or(true, |app| app_of(w).as_deref() == Some(app)))
+            .cloned();
+        if let Some(window) = window {
+            self.restore_window(&window);
+        }
+    }
+
+    /// Drop the minimized windows `matches` accepts, as they were closed
+    pub fn forget_minimized(&mut self, matches: impl Fn(&WindowElement) -> bool) {
+        let count = self.minimized.len();
+        self.minimized.retain(|m| !matches(&m.window));
+        if self.minimized.len() != count {
+            self.ipc_shm_update_minimized();
+        }
+    }
+}
@@ -8,7 +8,7 @@ use smithay::{
     backend::renderer::utils::on_commit_buffer_handler,
     desktop::{
         layer_map_for_output, space::SpaceElement, LayerSurface, PopupKind, PopupManager, Space,
-        WindowSurfaceType,
+        WindowSurface, WindowSurfaceType,
     },
     input::pointer::{CursorImageStatus, CursorImageSurfaceData},
     output::Output,
@@ -38,17 +38,39 @@ use smithay::{
     },
 };
 
+use crate::core::focus::KeyboardFocusTarget;
 use crate::core::state::{Backend, ClientState, WayiceState};
+use crate::helpers::output_utils::ConfiguredPosition;
 
 mod element;
 mod grabs;
+mod groups;
+mod layout;
+mod minimize;
+mod navigation;
+mod overview;
+mod placement;
+mod rules;
+mod scratchpad;
+mod scrolling;
+mod snapping;
 pub(crate) mod ssd;
+mod stacking;
+mod switcher;
+mod tiling;
+mod workspaces;
 #[cfg(feature = "xwayland")]
 mod x11;
 mod xdg;
 
 pub use self::element::*;
 pub use self::grabs::*;
+pub use self::groups::WindowGroup;
+pub use self::minimize::MinimizedWindow;
+pub use self::overview::{Overview, PressSource};
+pub use self::snapping::SnapPreview;
+pub use self::switcher::WindowSwitcher;
+pub use self::workspaces::{Workspace, Workspaces};
 
 fn fullscreen_output_geometry(
     wl_surface: &WlSurface,
@@ -232,7 +254,29 @@ impl<BackendData: Backend> CompositorHandler for WayiceState<BackendData> {
             });
         }
 
-        ensure_initial_configure(surface, &self.space, &mut self.popups)
+        if let Some(window) = self.window_for_surface(surface) {
+            // rules are applied right before the initial configure, as that is
+            // the first point the app_id and title of an xdg toplevel are known
+            if window
+                .0
+                .toplevel()
+                .is_some_and(|t| !t.is_initial_configure_sent())
+            {
+                window.set_decoration_theme(&self.config.decorations);
+                self.apply_window_rules(&window);
+                if let Some(output) = self.window_output(&window) {
+                    self.arrange_output(&output);
+                }
+            }
+            // the size of a new window is known once it commits its first buffer
+            self.place_pending_window(&window);
+        }
+
+        // a layer surface may have changed its exclusive zone or anchor, and
+        // with it the area left to the tiles
+        if let Some(output) = ensure_initial_configure(surface, &self.space, &mut self.popups) {
+            self.arrange_output(&output);
+        }
     }
 }
 
@@ -257,16 +301,19 @@ impl<BackendData: Backend> WlrLayerShellHandler for WayiceState<BackendData> {
     }
 
     fn layer_destroyed(&mut self, surface: WlrLayerSurface) {
-        if let Some((mut map, layer)) = self.space.outputs().find_map(|o| {
+        let Some((output, layer)) = self.space.outputs().find_map(|o| {
             let map = layer_map_for_output(o);
             let layer = map
                 .layers()
                 .find(|&layer| layer.layer_surface() == &surface)
                 .cloned();
-            layer.map(|layer| (map, layer))
-        }) {
-            map.unmap_layer(&layer);
-        }
+            layer.map(|layer| (o.clone(), layer))
+        }) else {
+            return;
+        };
+        layer_map_for_output(&output).unmap_layer(&layer);
+        // the tiles can take over the space the layer surface reserved
+        self.arrange_output(&output);
     }
 }
 
@@ -277,6 +324,35 @@ impl<BackendData: Backend> WayiceState<BackendData> {
             .find(|window| window.wl_surface().map(|s| &*s == surface).unwrap_or(false))
             .cloned()
     }
+
+    /// The window that has keyboard focus
+    pub fn focused_window(&self) -> Option<WindowElement> {
+        let Some(KeyboardFocusTarget::Window(window)) = self.seat.get_keyboard()?.current_focus() else {
+            return None;
+        };
+        self.space.elements().find(|e| e.0 == window).cloned()
+    }
+
+    /// Grow or shrink the focused window by `delta`, keeping its top left corner in place
+    pub fn resize_focused_window(&mut self, delta: Size<i32, Logical>) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let size = window.0.geometry().size;
+        let size = Size::from(((size.w + delta.w).max(1), (size.h + delta.h).max(1)));
+
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| state.size = Some(size));
+                toplevel.send_pending_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                let location = self.space.element_location(&window).unwrap_or_default();
+                let _ = surface.configure(Rectangle::from_loc_and_size(location, size));
+            }
+        }
+    }
 }
 
 #[derive(Default)]
@@ -285,7 +361,15 @@ pub struct SurfaceData {
     pub resize_state: ResizeState,
 }
 
-fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, popups: &mut PopupManager) {
+/// Send the initial configure of `surface` if it still needs one.
+///
+/// Returns the output whose layer surfaces leave a different area to the
+/// windows after the commit of a layer surface.
+fn ensure_initial_configure(
+    surface: &WlSurface,
+    space: &Space<WindowElement>,
+    popups: &mut PopupManager,
+) -> Option<Output> {
     with_surface_tree_upward(
         surface,
         (),
@@ -333,7 +417,7 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, p
             }
         });
 
-        return;
+        return None;
     }
 
     if let Some(popup) = popups.find_popup(surface) {
@@ -341,7 +425,7 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, p
             PopupKind::Xdg(ref popup) => popup,
             // Doesn't require configure
             PopupKind::InputMethod(ref _input_popup) => {
-                return;
+                return None;
             }
         };
 
@@ -351,102 +435,68 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, p
             popup.send_configure().expect("initial configure failed");
         }
 
-        return;
+        return None;
     };
 
-    if let Some(output) = space.outputs().find(|o| {
+    let output = space.outputs().find(|o| {
         let map = layer_map_for_output(o);
         map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
             .is_some()
-    }) {
-        let initial_configure_sent = with_states(surface, |states| {
-            states
-                .data_map
-                .get::<LayerSurfaceData>()
-                .unwrap()
-                .lock()
-                .unwrap()
-                .initial_configure_sent
-        });
-
-        let mut map = layer_map_for_output(output);
-
-        // arrange the layers before sending the initial configure
-        // to respect any size the client may have sent
-        map.arrange();
-        // send the initial configure if relevant
-        if !initial_configure_sent {
-            let layer = map
-                .layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
-                .unwrap();
+    })?;
+    let initial_configure_sent = with_states(surface, |states| {
+        states
+            .data_map
+            .get::<LayerSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .initial_configure_sent
+    });
+
+    let mut map = layer_map_for_output(output);
+    let zone = map.non_exclusive_zone();
+
+    // arrange the layers before sending the initial configure
+    // to respect any size the client may have sent
+    map.arrange();
+    // send the initial configure if relevant
+    if !initial_configure_sent {
+        let layer = map
+            .layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
+            .unwrap();
+
+        layer.layer_surface().send_configure();
+    }
 
-            layer.layer_surface().send_configure();
-        }
-    };
+    (map.non_exclusive_zone() != zone).then(|| output.clone())
 }
 
-fn place_new_window(
-    space: &mut Space<WindowElement>,
-    pointer_location: Point<f64, Logical>,
-    window: &WindowElement,
-    activate: bool,
-) {
-    // place the window at a random location on same output as pointer
-    // or if there is not output in a [0;800]x[0;800] square
-    use rand::distributions::{Distribution, Uniform};
-
-    let output = space
-        .output_under(pointer_location)
-        .next()
-        .or_else(|| space.outputs().next())
-        .cloned();
-    let output_geometry = output
-        .and_then(|o| {
-            let geo = space.output_geometry(&o)?;
-            let map = layer_map_for_output(&o);
-            let zone = map.non_exclusive_zone();
-            Some(Rectangle::from_loc_and_size(geo.loc + zone.loc, zone.size))
+/// Lay out the outputs again, returning the windows left outside of all of them
+pub fn fixup_positions(space: &mut Space<WindowElement>) -> Vec<WindowElement> {
+    // fixup outputs, keeping configured positions and laying out
+    // the remaining outputs to the right of them
+    let outputs = space.outputs().cloned().collect::<Vec<_>>();
+    let configured_right_edge = outputs
+        .iter()
+        .filter_map(|output| {
+            let position = ConfiguredPosition::get(output)?;
+            Some(position.x + space.output_geometry(output)?.size.w)
         })
-        .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), (800, 800)));
-
-    // set the initial toplevel bounds
-    //#[allow(irrefutable_let_patterns)]
-    // if window.0.is_wayland() {
-    //     if let Some(toplevel) = window.0.toplevel() {
-    //         // The window is a Wayland toplevel
-    //         toplevel.with_pending_state(|state| {
-    //             state.states.set(xdg_toplevel::State::Fullscreen);
-    //         });
-    //     }
-    // } else if window.0.is_x11() {
-    //     if let Some(xwayland) = window.0.x11_surface() {
-    //         // The window is an XWayland window
-    //         xwayland.set_fullscreen(true).unwrap();
-    //     }
-    // }
-
-    let max_x = output_geometry.loc.x + (((output_geometry.size.w as f32) / 3.0) * 2.0) as i32;
-    let max_y = output_geometry.loc.y + (((output_geometry.size.h as f32) / 3.0) * 2.0) as i32;
-    let x_range = Uniform::new(output_geometry.loc.x, max_x);
-    let y_range = Uniform::new(output_geometry.loc.y, max_y);
-    let mut rng = rand::thread_rng();
-    let x = x_range.sample(&mut rng);
-    let y = y_range.sample(&mut rng);
-
-    space.map_element(window.clone(), (x, y), activate);
-}
-
-pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point<f64, Logical>) {
-    // fixup outputs
-    let mut offset = Point::<i32, Logical>::from((0, 0));
-    for output in space.outputs().cloned().collect::<Vec<_>>().into_iter() {
-        let size = space
-            .output_geometry(&output)
-            .map(|geo| geo.size)
-            .unwrap_or_else(|| Size::from((0, 0)));
-        space.map_output(&output, offset);
+        .max()
+        .unwrap_or(0);
+    let mut offset = Point::<i32, Logical>::from((configured_right_edge, 0));
+    for output in outputs.into_iter() {
+        if let Some(position) = ConfiguredPosition::get(&output) {
+            space.map_output(&output, position);
+        } else {
+            let size = space
+                .output_geometry(&output)
+                .map(|geo| geo.size)
+                .unwrap_or_else(|| Size::from((0, 0)));
+            space.map_output(&output, offset);
+            offset.x += size.w;
+        }
         layer_map_for_output(&output).arrange();
-        offset.x += size.w;
     }
 
     // fixup windows
@@ -471,7 +521,5 @@ pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point
             orphaned_windows.push(window.clone());
         }
     }
-    for window in orphaned_windows.into_iter() {
-        place_new_window(space, pointer_location, &window, false);
-    }
+    orphaned_windows
 }
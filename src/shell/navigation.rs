@@ -0,0 +1,189 @@
+use smithay::{
+    output::Output,
+    utils::{Logical, Rectangle, Serial, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::Direction,
+    core::state::{Backend, WayiceState},
+};
+
+use super::WindowElement;
+
+/// How far `to` lies from `from` in `direction`, or `None` if it is not that
+/// way.
+///
+/// Rectangles overlapping `from` across the direction sort first, then the
+/// closest ones, the distance across the direction counting double.
+pub(super) fn distance(
+    from: Rectangle<i32, Logical>,
+    to: Rectangle<i32, Logical>,
+    direction: Direction,
+) -> Option<(bool, i32)> {
+    let center = |rect: Rectangle<i32, Logical>| rect.loc + rect.size.downscale(2).to_point();
+    let (a, b) = (center(from), center(to));
+    let overlap_x = from.loc.x < to.loc.x + to.size.w && to.loc.x < from.loc.x + from.size.w;
+    let overlap_y = from.loc.y < to.loc.y + to.size.h && to.loc.y < from.loc.y + from.size.h;
+    let (along, across, overlaps) = match direction {
+        Direction::Left => (a.x - b.x, (a.y - b.y).abs(), overlap_y),
+        Direction::Right => (b.x - a.x, (a.y - b.y).abs(), overlap_y),
+        Direction::Up => (a.y - b.y, (a.x - b.x).abs(), overlap_x),
+        Direction::Down => (b.y - a.y, (a.x - b.x).abs(), overlap_x),
+    };
+    (along > 0).then_some((!overlaps, along + 2 * across))
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Raise `window` and give it keyboard focus
+    pub fn focus_window(&mut self, window: &WindowElement, serial: Serial) {
+        self.space.raise_element(window, true);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            self.xwm.as_mut().unwrap().raise_window(surface).unwrap();
+        }
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(self, Some(window.clone().into()), serial);
+    }
+
+    /// The window closest to `from` in `direction` that `filter` accepts,
+    /// on any output
+    pub(super) fn neighbor(
+        &self,
+        from: Rectangle<i32, Logical>,
+        exclude: Option<&WindowElement>,
+        direction: Direction,
+        filter: impl Fn(&WindowElement) -> bool,
+    ) -> Option<WindowElement> {
+        self.space
+            .elements()
+            .filter(|w| Some(*w) != exclude && filter(w))
+            .filter_map(|w| Some((distance(from, self.space.element_geometry(w)?, direction)?, w)))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, w)| w.clone())
+    }
+
+    /// Focus the nearest window in `direction` of the focused one, or of the
+    /// pointer if no window has focus
+    pub fn focus_direction(&mut self, direction: Direction) {
+        let focused = self.focused_window();
+        let from = focused
+            .as_ref()
+            .and_then(|w| self.space.element_geometry(w))
+            .unwrap_or_else(|| {
+                Rectangle::from_loc_and_size(self.pointer.current_location().to_i32_round(), (1, 1))
+            });
+        let Some(window) = self.neighbor(from, focused.as_ref(), direction, |_| true) else {
+            return;
+        };
+        debug!(?direction, "Focusing window");
+        self.focus_window(&window, SERIAL_COUNTER.next_serial());
+    }
+
+    /// Swap the focused window with the nearest one in `direction`, tiled
+    /// windows trading tiles and floating windows trading places
+    pub fn swap_direction(&mut self, direction: Direction) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let Some(from) = self.space.element_geometry(&window) else {
+            return;
+        };
+        let tiled = self.is_tiled(&window);
+        let Some(other) = self.neighbor(from, Some(&window), direction, |w| self.is_tiled(w) == tiled) else {
+            return;
+        };
+        let (Some(location), Some(other_location)) = (
+            self.space.element_location(&window),
+            self.space.element_location(&other),
+        ) else {
+            return;
+        };
+
+        debug!(?direction, tiled, "Swapping windows");
+        self.workspaces.swap_windows(&window, &other);
+        // the windows may change outputs, which the tiling layouts go by
+        self.space.map_element(other.clone(), location, false);
+        self.space.map_element(window.clone(), other_location, false);
+        self.arrange_all();
+        self.focus_window(&window, SERIAL_COUNTER.next_serial());
+    }
+
+    /// Focus the topmost window of the nearest output in `direction`, moving
+    /// the pointer there
+    pub fn focus_output_direction(&mut self, direction: Direction) {
+        let Some(current) = self.tiling_output() else {
+            return;
+        };
+        let Some(from) = self.space.output_geometry(&current) else {
+            return;
+        };
+        let output: Option<Output> = self
+            .space
+            .outputs()
+            .filter(|o| **o != current)
+            .filter_map(|o| Some((distance(from, self.space.output_geometry(o)?, direction)?, o)))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, o)| o.clone());
+        let Some(output) = output else {
+            return;
+        };
+        debug!(?direction, output = output.name(), "Focusing output");
+        self.warp_pointer_to(&output);
+        self.focus_output(&output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((x, y), (100, 100))
+    }
+
+    #[test]
+    fn distance_along_direction() {
+        assert_eq!(
+            distance(rect(0, 0), rect(200, 0), Direction::Right),
+            Some((false, 200))
+        );
+        assert_eq!(
+            distance(rect(200, 0), rect(0, 0), Direction::Left),
+            Some((false, 200))
+        );
+        assert_eq!(
+            distance(rect(0, 0), rect(0, 300), Direction::Down),
+            Some((false, 300))
+        );
+        assert_eq!(
+            distance(rect(0, 300), rect(0, 0), Direction::Up),
+            Some((false, 300))
+        );
+    }
+
+    #[test]
+    fn distance_rejects_wrong_direction() {
+        assert_eq!(distance(rect(0, 0), rect(200, 0), Direction::Left), None);
+        assert_eq!(distance(rect(0, 0), rect(200, 0), Direction::Up), None);
+        assert_eq!(distance(rect(0, 0), rect(200, 0), Direction::Down), None);
+        assert_eq!(distance(rect(0, 0), rect(0, 300), Direction::Up), None);
+        // a window at the same spot is in no direction
+        assert_eq!(distance(rect(0, 0), rect(0, 0), Direction::Right), None);
+    }
+
+    #[test]
+    fn distance_counts_offset_across_double() {
+        assert_eq!(
+            distance(rect(0, 0), rect(200, 300), Direction::Right),
+            Some((true, 800))
+        );
+    }
+
+    #[test]
+    fn distance_prefers_overlapping_windows() {
+        let far_in_line = distance(rect(0, 0), rect(1000, 50), Direction::Right);
+        let near_diagonal = distance(rect(0, 0), rect(200, 150), Direction::Right);
+        assert!(far_in_line < near_diagonal);
+    }
+}
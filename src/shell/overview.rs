@@ -0,0 +1,503 @@
+use std::time::{Duration, Instant};
+
+use smithay::{
+    backend::{
+        input::TouchSlot,
+        renderer::element::{
+            solid::{SolidColorBuffer, SolidColorRenderElement},
+            Kind,
+        },
+    },
+    desktop::{space::SpaceElement, Space},
+    input::keyboard::Keysym,
+    output::Output,
+    utils::{IsAlive, Logical, Point, Rectangle, Scale, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::Direction,
+    core::{
+        render::{output_logical_size, preview_cell, PREVIEW_PADDING},
+        state::{Backend, WayiceState},
+    },
+};
+
+use super::{
+    navigation::distance, placement::clamp_into, switcher::HIGHLIGHT_COLOR, tiling::inset, FullscreenSurface,
+    WindowElement,
+};
+
+/// How long the overview takes to open or close
+const ANIMATION_DURATION: Duration = Duration::from_millis(250);
+
+/// Distance the pointer has to move while holding a thumbnail for it to be
+/// dragged rather than clicked
+const DRAG_THRESHOLD: f64 = 8.0;
+
+/// What pressed in the overview, telling its release and motion apart from
+/// those of other buttons and touch points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressSource {
+    Button(u32),
+    Touch(TouchSlot),
+}
+
+/// A press in the overview, on a thumbnail or on the empty space around them
+#[derive(Debug)]
+struct OverviewPress {
+    source: PressSource,
+    window: Option<WindowElement>,
+    start: Point<f64, Logical>,
+    current: Point<f64, Logical>,
+    /// Whether the pointer moved far enough for the thumbnail to be dragged
+    dragging: bool,
+}
+
+/// The overview, showing the windows of each output as thumbnails in a grid
+/// in place of the usual scene
+#[derive(Debug)]
+pub struct Overview {
+    /// Windows of each output, in the order of their thumbnails
+    outputs: Vec<(Output, Vec<WindowElement>)>,
+    selected: Option<WindowElement>,
+    /// Progress of the animation when it started, 0 being closed and 1 open
+    from: f64,
+    started: Instant,
+    closing: bool,
+    press: Option<OverviewPress>,
+    highlight: SolidColorBuffer,
+    /// Output of the frame behind the selected thumbnail, and its area
+    /// relative to that output
+    highlight_area: Option<(Output, Rectangle<i32, Logical>)>,
+}
+
+/// Slows down towards the end of an animation
+fn ease_out(t: f64) -> f64 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// The rectangle `progress` of the way from `from` to `to`
+fn interpolate(
+    from: Rectangle<i32, Logical>,
+    to: Rectangle<i32, Logical>,
+    progress: f64,
+) -> Rectangle<i32, Logical> {
+    let lerp = |a: i32, b: i32| a + ((b - a) as f64 * progress).round() as i32;
+    Rectangle::from_loc_and_size(
+        (lerp(from.loc.x, to.loc.x), lerp(from.loc.y, to.loc.y)),
+        (lerp(from.size.w, to.size.w), lerp(from.size.h, to.size.h)),
+    )
+}
+
+impl Overview {
+    /// How far the overview is open, 0 being closed and 1 open
+    pub fn progress(&self) -> f64 {
+        let t = (self.started.elapsed().as_secs_f64() / ANIMATION_DURATION.as_secs_f64()).min(1.0);
+        let target = if self.closing { 0.0 } else { 1.0 };
+        self.from + (target - self.from) * ease_out(t)
+    }
+
+    /// Whether the overview takes input, which it stops doing as soon as it
+    /// starts closing
+    pub fn is_open(&self) -> bool {
+        !self.closing
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closing && self.started.elapsed() >= ANIMATION_DURATION
+    }
+
+    fn windows(&self, output: &Output) -> Vec<&WindowElement> {
+        self.outputs
+            .iter()
+            .find(|(o, _)| o == output)
+            .map(|(_, windows)| windows.iter().filter(|w| w.alive()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Cells of the thumbnails on `output`, relative to it
+    fn cells(&self, output: &Output) -> Vec<(&WindowElement, Rectangle<i32, Logical>)> {
+        let windows = self.windows(output);
+        let count = windows.len();
+        let output_size = output_logical_size(output);
+        windows
+            .into_iter()
+            .enumerate()
+            .map(|(index, window)| (window, preview_cell(index, count, output_size)))
+            .collect()
+    }
+
+    /// Output of the thumbnail of `window` and its cell, relative to that output
+    fn cell_of(&self, window: &WindowElement) -> Option<(&Output, Rectangle<i32, Logical>)> {
+        self.outputs.iter().find_map(|(output, _)| {
+            let (_, cell) = self.cells(output).into_iter().find(|(w, _)| *w == window)?;
+            Some((output, cell))
+        })
+    }
+
+    /// Where the thumbnails of `output` are drawn, relative to it, topmost
+    /// first.
+    ///
+    /// While the overview opens or closes they move between the windows and
+    /// their cells, and the dragged thumbnail follows the pointer.
+    pub fn thumbnails(
+        &self,
+        output: &Output,
+        space: &Space<WindowElement>,
+    ) -> Vec<(WindowElement, Rectangle<i32, Logical>)> {
+        let progress = self.progress();
+        let origin = |o: &Output| space.output_geometry(o).map(|geo| geo.loc).unwrap_or_default();
+        let dragged = self
+            .press
+            .as_ref()
+            .filter(|press| press.dragging)
+            .and_then(|press| Some((press, press.window.as_ref()?)));
+
+        let mut thumbnails: Vec<(WindowElement, Rectangle<i32, Logical>)> = self
+            .cells(output)
+            .into_iter()
+            .filter(|(window, _)| dragged.map_or(true, |(_, dragged)| dragged != *window))
+            .map(|(window, cell)| {
+                let area = match space.element_bbox(window) {
+                    Some(mut bbox) => {
+                        bbox.loc -= origin(output);
+                        interpolate(bbox, cell, progress)
+                    }
+                    None => cell,
+                };
+                (window.clone(), area)
+            })
+            .collect();
+
+        if let Some((press, window)) = dragged {
+            if let Some((home, mut cell)) = self.cell_of(window) {
+                cell.loc += origin(home) - origin(output) + (press.current - press.start).to_i32_round();
+                thumbnails.insert(0, (window.clone(), cell));
+            }
+        }
+        thumbnails
+    }
+
+    /// The frame behind the selected thumbnail, if it is on `output`
+    pub fn highlight_element(&self, output: &Output, scale: Scale<f64>) -> Option<SolidColorRenderElement> {
+        let (_, area) = self.highlight_area.as_ref().filter(|(o, _)| o == output)?;
+        Some(SolidColorRenderElement::from_buffer(
+            &self.highlight,
+            area.loc.to_physical_precise_round(scale),
+            scale,
+            self.progress() as f32,
+            Kind::Unspecified,
+        ))
+    }
+
+    fn select(&mut self, window: Option<WindowElement>) {
+        let area = window
+            .as_ref()
+            .and_then(|w| self.cell_of(w))
+            .map(|(output, cell)| (output.clone(), inset(cell, -PREVIEW_PADDING / 2)));
+        if let Some((_, area)) = area.as_ref() {
+            if self.highlight_area.as_ref().map(|(_, a)| a.size) != Some(area.size) {
+                self.highlight.update(area.size, HIGHLIGHT_COLOR);
+            }
+        }
+        self.selected = window;
+        self.highlight_area = area;
+    }
+
+    /// Whether `keysym` drives the overview rather than going to a client
+    pub fn handles(keysym: Keysym) -> bool {
+        matches!(
+            keysym,
+            Keysym::Left
+                | Keysym::Right
+                | Keysym::Up
+                | Keysym::Down
+                | Keysym::Return
+                | Keysym::KP_Enter
+                | Keysym::Escape
+        )
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Open the overview, or close it if it is open
+    pub fn toggle_overview(&mut self) {
+        if self.overview.as_ref().is_some_and(|o| o.is_open()) {
+            self.close_overview(None);
+            return;
+        }
+
+        let outputs: Vec<Output> = self.space.outputs().cloned().collect();
+        let outputs: Vec<(Output, Vec<WindowElement>)> = outputs
+            .into_iter()
+            .map(|output| {
+                let mut windows = self.windows_on_output(&output);
+                // in reading order, so thumbnails stay close to their window
+                windows.sort_by_key(|w| {
+                    let location = self.space.element_location(w).unwrap_or_default();
+                    (location.y, location.x)
+                });
+                (output, windows)
+            })
+            .collect();
+        let from = self.overview.as_ref().map_or(0.0, |o| o.progress());
+        debug!(
+            windows = outputs.iter().map(|(_, w)| w.len()).sum::<usize>(),
+            "Opening overview"
+        );
+
+        let mut overview = Overview {
+            outputs,
+            selected: None,
+            from,
+            started: Instant::now(),
+            closing: false,
+            press: None,
+            highlight: SolidColorBuffer::default(),
+            highlight_area: None,
+        };
+        let current = self.current_output();
+        let selected = self
+            .focused_window()
+            .filter(|w| overview.cell_of(w).is_some())
+            .or_else(|| {
+                let output = current.as_ref()?;
+                overview.windows(output).first().map(|w| (*w).clone())
+            });
+        overview.select(selected);
+        self.overview = Some(overview);
+    }
+
+    /// Close the overview, focusing `window` if given
+    pub fn close_overview(&mut self, window: Option<WindowElement>) {
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        debug!("Closing overview");
+        overview.from = overview.progress();
+        overview.started = Instant::now();
+        overview.closing = true;
+        overview.press = None;
+        if let Some(window) = window.filter(|w| w.alive() && self.space.element_location(w).is_some()) {
+            self.focus_window(&window, SERIAL_COUNTER.next_serial());
+        }
+    }
+
+    /// Drop the overview once it finished closing, and keep its selection on
+    /// a window that still exists
+    pub fn refresh_overview(&mut self) {
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        if overview.is_closed() {
+            self.overview = None;
+            return;
+        }
+        let selected = overview.selected.clone().filter(|w| w.alive()).or_else(|| {
+            let output = overview.highlight_area.as_ref().map(|(o, _)| o.clone())?;
+            overview.windows(&output).first().map(|w| (*w).clone())
+        });
+        let area = selected
+            .as_ref()
+            .and_then(|w| overview.cell_of(w))
+            .map(|(_, cell)| inset(cell, -PREVIEW_PADDING / 2));
+        if selected != overview.selected || area != overview.highlight_area.as_ref().map(|(_, a)| *a) {
+            overview.select(selected);
+        }
+    }
+
+    /// Handle a key handled by the overview, see [`Overview::handles`]
+    pub fn overview_key(&mut self, keysym: Keysym) {
+        let direction = match keysym {
+            Keysym::Left => Direction::Left,
+            Keysym::Right => Direction::Right,
+            Keysym::Up => Direction::Up,
+            Keysym::Down => Direction::Down,
+            Keysym::Return | Keysym::KP_Enter => {
+                let selected = self.overview.as_ref().and_then(|o| o.selected.clone());
+                self.close_overview(selected);
+                return;
+            }
+            _ => {
+                self.close_overview(None);
+                return;
+            }
+        };
+        self.overview_select_direction(direction);
+    }
+
+    /// Select the nearest thumbnail in `direction` of the selected one, on
+    /// any output
+    fn overview_select_direction(&mut self, direction: Direction) {
+        let cells = self.overview_cells();
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        let from = overview
+            .selected
+            .as_ref()
+            .and_then(|selected| cells.iter().find(|(w, _)| w == selected));
+        let next = match from {
+            Some((selected, from)) => cells
+                .iter()
+                .filter(|(w, _)| w != selected)
+                .filter_map(|(w, cell)| Some((distance(*from, *cell, direction)?, w)))
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, w)| w.clone()),
+            None => cells.first().map(|(w, _)| w.clone()),
+        };
+        if next.is_some() {
+            overview.select(next);
+        }
+    }
+
+    /// Cells of all thumbnails of the overview, in global coordinates
+    fn overview_cells(&self) -> Vec<(WindowElement, Rectangle<i32, Logical>)> {
+        let Some(overview) = self.overview.as_ref() else {
+            return Vec::new();
+        };
+        overview
+            .outputs
+            .iter()
+            .filter_map(|(output, _)| Some((output, self.space.output_geometry(output)?.loc)))
+            .flat_map(|(output, origin)| {
+                overview.cells(output).into_iter().map(move |(window, mut cell)| {
+                    cell.loc += origin;
+                    (window.clone(), cell)
+                })
+            })
+            .collect()
+    }
+
+    fn overview_thumbnail_under(&self, location: Point<f64, Logical>) -> Option<WindowElement> {
+        self.overview_cells()
+            .into_iter()
+            .find(|(_, cell)| cell.to_f64().contains(location))
+            .map(|(window, _)| window)
+    }
+
+    /// Start a click or drag in the overview, returning whether the overview
+    /// took the press.
+    ///
+    /// Further presses while one is held are taken but ignored.
+    pub fn overview_press(&mut self, source: PressSource, location: Point<f64, Logical>) -> bool {
+        if !self.overview.as_ref().is_some_and(|o| o.is_open()) {
+            return false;
+        }
+        if self.overview.as_ref().is_some_and(|o| o.press.is_some()) {
+            return true;
+        }
+        let window = self.overview_thumbnail_under(location);
+        let overview = self.overview.as_mut().unwrap();
+        if window.is_some() {
+            overview.select(window.clone());
+        }
+        overview.press = Some(OverviewPress {
+            source,
+            window,
+            start: location,
+            current: location,
+            dragging: false,
+        });
+        true
+    }
+
+    /// Drag the pressed thumbnail, or select the one under `location`.
+    ///
+    /// `touch` is the touch point that moved, or `None` for the pointer.
+    pub fn overview_motion(&mut self, touch: Option<TouchSlot>, location: Point<f64, Logical>) {
+        if !self.overview.as_ref().is_some_and(|o| o.is_open()) {
+            return;
+        }
+        let under = self.overview_thumbnail_under(location);
+        let overview = self.overview.as_mut().unwrap();
+        match overview.press.as_mut() {
+            Some(press) => {
+                let follows = match (press.source, touch) {
+                    (PressSource::Button(_), None) => true,
+                    (PressSource::Touch(slot), Some(touch)) => slot == touch,
+                    _ => false,
+                };
+                if !follows {
+                    return;
+                }
+                press.current = location;
+                let moved = press.current - press.start;
+                press.dragging |= press.window.is_some() && moved.x.hypot(moved.y) > DRAG_THRESHOLD;
+            }
+            None if under.is_some() && under != overview.selected => overview.select(under),
+            None => {}
+        }
+    }
+
+    /// End a press in the overview, returning whether the overview took it.
+    ///
+    /// Clicking a thumbnail focuses its window and closes the overview, as
+    /// does clicking next to them without focusing anything. A thumbnail
+    /// dropped on another output moves its window to the workspace shown
+    /// there. Releases of presses ignored by [`Self::overview_press`] are
+    /// taken as well.
+    pub fn overview_release(&mut self, source: PressSource) -> bool {
+        let Some(overview) = self.overview.as_mut() else {
+            return false;
+        };
+        let Some(press) = overview.press.take_if(|press| press.source == source) else {
+            return overview.press.is_some();
+        };
+        let Some(window) = press.window else {
+            self.close_overview(None);
+            return true;
+        };
+        if !press.dragging {
+            self.close_overview(Some(window));
+            return true;
+        }
+        let target = self.space.output_under(press.current).next().cloned();
+        if let Some(target) = target {
+            self.overview_move_to_output(&window, &target);
+        }
+        true
+    }
+
+    /// Move `window` to `output`, keeping its position relative to the
+    /// output, and its thumbnail along with it
+    fn overview_move_to_output(&mut self, window: &WindowElement, output: &Output) {
+        let Some(home) = self.window_output(window) else {
+            return;
+        };
+        // fullscreen windows stay in place
+        let fullscreen = home
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|f| f.get())
+            .is_some_and(|w| w == *window);
+        if home == *output || fullscreen {
+            return;
+        }
+        let (Some(location), Some(from), Some(area)) = (
+            self.space.element_location(window),
+            self.space.output_geometry(&home),
+            self.usable_area(output),
+        ) else {
+            return;
+        };
+
+        debug!(
+            output = output.name(),
+            "Moving window to output from the overview"
+        );
+        let location = clamp_into(location - from.loc + area.loc, window.geometry().size, area);
+        self.space.map_element(window.clone(), location, false);
+        self.arrange_all();
+
+        let overview = self.overview.as_mut().unwrap();
+        for (o, windows) in overview.outputs.iter_mut() {
+            windows.retain(|w| w != window);
+            if o == output {
+                windows.push(window.clone());
+            }
+        }
+        overview.select(Some(window.clone()));
+    }
+}
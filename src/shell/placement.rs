@@ -0,0 +1,301 @@
+use std::cell::Cell;
+
+use smithay::{
+    desktop::{layer_map_for_output, space::SpaceElement, WindowSurface},
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size},
+    wayland::{compositor::with_states, shell::xdg::XdgToplevelSurfaceData},
+};
+use tracing::debug;
+
+use crate::{
+    config::Placement,
+    core::state::{Backend, WayiceState},
+};
+
+use super::WindowElement;
+
+/// Offset between windows opened by the cascade placement
+const CASCADE_STEP: i32 = 32;
+
+/// Set on Wayland windows mapped before their size was known, which are
+/// placed once they commit their first buffer
+#[derive(Debug, Default)]
+struct PendingPlacement(Cell<bool>);
+
+impl WindowElement {
+    fn set_placement_pending(&self, pending: bool) {
+        self.user_data().insert_if_missing(PendingPlacement::default);
+        self.user_data().get::<PendingPlacement>().unwrap().0.set(pending);
+    }
+
+    /// Stop waiting for the first buffer to place the window, as it was put
+    /// in place some other way
+    pub fn cancel_placement(&self) {
+        if let Some(pending) = self.user_data().get::<PendingPlacement>() {
+            pending.0.set(false);
+        }
+    }
+
+    fn placement_pending(&self) -> bool {
+        self.user_data()
+            .get::<PendingPlacement>()
+            .is_some_and(|pending| pending.0.get())
+    }
+
+    /// Whether the window has committed a buffer, and so has a size
+    fn has_size(&self) -> bool {
+        let size = self.0.geometry().size;
+        size.w > 0 && size.h > 0
+    }
+
+    /// Whether the window is modal, in which case it is centered on its
+    /// parent or the output
+    fn is_modal(&self) -> bool {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => with_states(toplevel.wl_surface(), |states| {
+                states
+                    .data_map
+                    .get::<XdgToplevelSurfaceData>()
+                    .is_some_and(|data| data.lock().unwrap().modal)
+            }),
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(_) => false,
+        }
+    }
+}
+
+/// Move `loc` so a window of `size` there lies within `area`, or starts at
+/// its top left corner if it is larger
+pub(super) fn clamp_into(
+    loc: Point<i32, Logical>,
+    size: Size<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    Point::from((
+        loc.x.min(area.loc.x + area.size.w - size.w).max(area.loc.x),
+        loc.y.min(area.loc.y + area.size.h - size.h).max(area.loc.y),
+    ))
+}
+
+/// The location in `area` where a window of `size` covers the least of
+/// `others`, preferring the top left.
+///
+/// Only the edges of the area and of the other windows are tried, as the
+/// overlap can only be smallest where the window touches one of them.
+fn least_overlap(
+    area: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+    others: &[Rectangle<i32, Logical>],
+) -> Point<i32, Logical> {
+    let mut xs = vec![area.loc.x, area.loc.x + area.size.w - size.w];
+    let mut ys = vec![area.loc.y, area.loc.y + area.size.h - size.h];
+    for other in others {
+        xs.extend([other.loc.x + other.size.w, other.loc.x - size.w]);
+        ys.extend([other.loc.y + other.size.h, other.loc.y - size.h]);
+    }
+    let fits = |start: i32, len: i32, area_start: i32, area_len: i32| {
+        start >= area_start && start + len <= area_start + area_len
+    };
+    xs.retain(|x| fits(*x, size.w, area.loc.x, area.size.w));
+    ys.retain(|y| fits(*y, size.h, area.loc.y, area.size.h));
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+
+    let mut best = (i64::MAX, area.loc);
+    for &y in &ys {
+        for &x in &xs {
+            let rect = Rectangle::from_loc_and_size((x, y), size);
+            let overlap: i64 = others
+                .iter()
+                .filter_map(|other| other.intersection(rect))
+                .map(|r| r.size.w as i64 * r.size.h as i64)
+                .sum();
+            if overlap < best.0 {
+                best = (overlap, rect.loc);
+            }
+        }
+    }
+    best.1
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Part of `output` not reserved by layer surfaces
+    pub(super) fn usable_area(&self, output: &Output) -> Option<Rectangle<i32, Logical>> {
+        let geometry = self.space.output_geometry(output)?;
+        let zone = layer_map_for_output(output).non_exclusive_zone();
+        Some(Rectangle::from_loc_and_size(geometry.loc + zone.loc, zone.size))
+    }
+
+    /// Area windows are placed in on `output`, or if there is no output a
+    /// [0;800]x[0;800] square
+    fn placement_area(&self, output: Option<&Output>) -> Rectangle<i32, Logical> {
+        output
+            .and_then(|o| self.usable_area(o))
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), (800, 800)))
+    }
+
+    /// Map a new window on the output under the pointer.
+    ///
+    /// The window is not activated, the window rules decide whether it is
+    /// once they are applied.
+    pub fn place_new_window(&mut self, window: &WindowElement) {
+        let output = self.current_output();
+        self.place_window_on_output(window, output.as_ref(), false);
+    }
+
+    /// Map `window` on `output` where the placement policy of `[general]`
+    /// puts it.
+    ///
+    /// Wayland windows have no size before their first buffer, until then
+    /// they wait in the top left corner.
+    pub fn place_window_on_output(
+        &mut self,
+        window: &WindowElement,
+        output: Option<&Output>,
+        activate: bool,
+    ) {
+        let area = self.placement_area(output);
+        self.space.map_element(window.clone(), area.loc, activate);
+        if window.has_size() {
+            self.place_window(window, area);
+        } else {
+            window.set_placement_pending(true);
+        }
+    }
+
+    /// Place a window that was waiting for its size, once it has one
+    pub fn place_pending_window(&mut self, window: &WindowElement) {
+        if !window.placement_pending() || !window.has_size() {
+            return;
+        }
+        window.cancel_placement();
+        let output = self.window_output(window);
+        let area = self.placement_area(output.as_ref());
+        self.place_window(window, area);
+    }
+
+    /// The window a transient window belongs to
+    fn transient_parent(&self, window: &WindowElement) -> Option<WindowElement> {
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => self.window_for_surface(&toplevel.parent()?),
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                let parent = surface.is_transient_for()?;
+                self.space
+                    .elements()
+                    .find(|e| e.0.x11_surface().is_some_and(|s| s.window_id() == parent))
+                    .cloned()
+            }
+        }
+    }
+
+    /// Move `window` within `area` where the placement policy puts it
+    fn place_window(&mut self, window: &WindowElement, area: Rectangle<i32, Logical>) {
+        // tiled windows are placed by their layout
+        if self.is_tiled(window) {
+            return;
+        }
+        let size = window.geometry().size;
+        let parent = self
+            .transient_parent(window)
+            .and_then(|parent| self.space.element_geometry(&parent));
+        let centered_on = |rect: Rectangle<i32, Logical>| {
+            rect.loc + rect.size.downscale(2).to_point() - size.downscale(2).to_point()
+        };
+
+        let location = match parent {
+            Some(parent) => centered_on(parent),
+            None if window.is_modal() => centered_on(area),
+            None => {
+                let others: Vec<Rectangle<i32, Logical>> = self
+                    .space
+                    .elements()
+                    .filter(|other| *other != window)
+                    .filter_map(|other| self.space.element_geometry(other))
+                    .filter(|geometry| geometry.overlaps(area))
+                    .collect();
+                match self.config.general.placement {
+                    Placement::Centered => centered_on(area),
+                    Placement::UnderPointer => {
+                        self.pointer.current_location().to_i32_round() - size.downscale(2).to_point()
+                    }
+                    Placement::Cascade => match others.last() {
+                        Some(topmost)
+                            if topmost.loc.x + CASCADE_STEP + size.w <= area.loc.x + area.size.w
+                                && topmost.loc.y + CASCADE_STEP + size.h <= area.loc.y + area.size.h =>
+                        {
+                            topmost.loc + Point::from((CASCADE_STEP, CASCADE_STEP))
+                        }
+                        _ => area.loc,
+                    },
+                    Placement::LeastOverlap => least_overlap(area, size, &others),
+                }
+            }
+        };
+
+        let location = clamp_into(location, size, area);
+        debug!(?location, "Placing window");
+        self.space.map_element(window.clone(), location, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((0, 0), (1000, 1000))
+    }
+
+    #[test]
+    fn clamp_into_keeps_windows_inside() {
+        let size = Size::from((200, 100));
+        assert_eq!(clamp_into((50, 60).into(), size, area()), Point::from((50, 60)));
+    }
+
+    #[test]
+    fn clamp_into_moves_windows_back_inside() {
+        let size = Size::from((200, 100));
+        assert_eq!(clamp_into((-50, -60).into(), size, area()), Point::from((0, 0)));
+        assert_eq!(
+            clamp_into((900, 950).into(), size, area()),
+            Point::from((800, 900))
+        );
+    }
+
+    #[test]
+    fn clamp_into_puts_large_windows_at_top_left() {
+        let size = Size::from((1200, 1100));
+        assert_eq!(clamp_into((300, 200).into(), size, area()), Point::from((0, 0)));
+    }
+
+    #[test]
+    fn least_overlap_prefers_top_left_of_empty_area() {
+        assert_eq!(least_overlap(area(), (400, 400).into(), &[]), Point::from((0, 0)));
+    }
+
+    #[test]
+    fn least_overlap_picks_free_spot() {
+        let others = [Rectangle::from_loc_and_size((0, 0), (500, 500))];
+        assert_eq!(
+            least_overlap(area(), (400, 400).into(), &others),
+            Point::from((500, 0))
+        );
+    }
+
+    #[test]
+    fn least_overlap_picks_smallest_overlap_when_full() {
+        let others = [
+            Rectangle::from_loc_and_size((0, 0), (1000, 500)),
+            Rectangle::from_loc_and_size((0, 500), (700, 500)),
+        ];
+        // right below the first window, overlapping the second by 100 pixels in width
+        assert_eq!(
+            least_overlap(area(), (400, 400).into(), &others),
+            Point::from((600, 500))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::cell::RefCell;
+
+#[cfg(feature = "xwayland")]
+use smithay::utils::Rectangle;
+use smithay::{
+    desktop::{space::SpaceElement, WindowSurface},
+    reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
+    utils::{Point, SERIAL_COUNTER},
+    wayland::shell::xdg::XdgShellHandler,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    config::{Decorations, RuleEffects, WindowProperties},
+    core::state::{Backend, WayiceState},
+    helpers::window_utils::window_properties,
+};
+
+use super::WindowElement;
+
+/// Rule state of a window, stored in its user data once rules were applied
+#[derive(Debug, Default)]
+struct WindowRuleState {
+    /// Properties the rules were last evaluated against
+    properties: RefCell<Option<WindowProperties>>,
+    effects: RefCell<RuleEffects>,
+}
+
+impl WindowElement {
+    fn rule_state(&self) -> Option<&WindowRuleState> {
+        self.user_data().get::<WindowRuleState>()
+    }
+
+    /// Effects of the rules currently matching the window
+    pub fn rule_effects(&self) -> RuleEffects {
+        self.rule_state()
+            .map(|state| state.effects.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Opacity the window is drawn with
+    pub fn opacity(&self) -> f32 {
+        self.rule_state()
+            .and_then(|state| state.effects.borrow().opacity)
+            .unwrap_or(1.0)
+    }
+
+    /// Decorations a rule forces on the window, overriding what the client asks for
+    pub fn forced_decorations(&self) -> Option<Decorations> {
+        self.rule_state()
+            .and_then(|state| state.effects.borrow().decorations)
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Apply the rules matching a window that is about to be shown for the
+    /// first time.
+    ///
+    /// Has to be called after the window was placed in the space, and for
+    /// Wayland windows before the initial configure is sent, so the client
+    /// gets the right size and state from the start.
+    pub fn apply_window_rules(&mut self, window: &WindowElement) {
+        let properties = window_properties(window);
+        let effects = self.config.rules_for(&properties);
+
+        window.user_data().insert_if_missing(WindowRuleState::default);
+        let state = window.rule_state().unwrap();
+        *state.properties.borrow_mut() = Some(properties);
+        *state.effects.borrow_mut() = effects.clone();
+
+        self.apply_rule_effects(window, &effects, true);
+    }
+
+    /// Evaluate the rules of a mapped window again, after its title, app_id
+    /// or X11 class changed.
+    ///
+    /// The rules are only matched if those differ from what they were last
+    /// matched against, and nothing happens unless the combined effects of the matching rules
+    /// change, so clients updating their title all the time don't get moved
+    /// around.
+    pub fn refresh_window_rules(&mut self, window: &WindowElement) {
+        let Some(state) = window.rule_state() else {
+            return;
+        };
+        let properties = window_properties(window);
+        if state.properties.borrow().as_ref() == Some(&properties) {
+            return;
+        }
+
+        let effects = self.config.rules_for(&properties);
+        *state.properties.borrow_mut() = Some(properties);
+        if *state.effects.borrow() == effects {
+            return;
+        }
+        *state.effects.borrow_mut() = effects.clone();
+
+        self.apply_rule_effects(window, &effects, false);
+    }
+
+    /// Evaluate the rules of every window again, after the rules changed.
+    ///
+    /// This includes the windows out of the space: those of hidden
+    /// workspaces, minimized and scratchpad windows and inactive tabs.
+    pub fn reapply_window_rules(&mut self) {
+        let mut windows: Vec<WindowElement> = Vec::new();
+        let all = self
+            .space
+            .elements()
+            .chain(self.workspaces.hidden())
+            .chain(self.minimized.iter().map(|m| &m.window))
+            .chain(self.scratchpad.iter())
+            .chain(self.groups.iter().flat_map(|g| g.windows()));
+        for window in all {
+            if !windows.contains(window) {
+                windows.push(window.clone());
+            }
+        }
+
+        for window in windows {
+            if let Some(state) = window.rule_state() {
+                state.properties.take();
+                self.refresh_window_rules(&window);
+            }
+        }
+    }
+
+    fn apply_rule_effects(&mut self, window: &WindowElement, effects: &RuleEffects, mapping: bool) {
+        debug!(?effects, "Applying window rules");
+
+        // windows out of the space keep their place and fullscreen state
+        // until they are shown again
+        let in_space = self.space.element_location(window).is_some();
+        let output = effects.output.as_ref().filter(|_| in_space).and_then(|name| {
+            let output = self.space.outputs().find(|o| o.name() == *name).cloned();
+            if output.is_none() {
+                warn!(output = name, "Output of window rule not found");
+            }
+            output
+        });
+        if output.is_some() || (in_space && effects.position.is_some()) {
+            let output = output
+                .or_else(|| self.space.outputs_for_element(window).first().cloned())
+                .or_else(|| self.space.outputs().next().cloned());
+            match effects.position {
+                Some(position) => {
+                    let origin = output
+                        .and_then(|o| self.space.output_geometry(&o))
+                        .map(|geo| geo.loc)
+                        .unwrap_or_default();
+                    self.space
+                        .map_element(window.clone(), origin + Point::from(position), false);
+                    window.cancel_placement();
+                }
+                None => self.place_window_on_output(window, output.as_ref(), false),
+            }
+        }
+
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| {
+                    if let Some(size) = effects.size {
+                        state.size = Some(size.into());
+                    }
+                    if let Some(decorations) = effects.decorations {
+                        state.decoration_mode = Some(match decorations {
+                            Decorations::Server => Mode::ServerSide,
+                            Decorations::Client => Mode::ClientSide,
+                        });
+                    }
+                });
+                match effects.fullscreen {
+                    Some(true) if in_space => self.fullscreen_request(toplevel.clone(), None),
+                    Some(false) if in_space => self.unfullscreen_request(toplevel.clone()),
+                    _ => {}
+                }
+                if toplevel.is_initial_configure_sent() {
+                    toplevel.send_pending_configure();
+                }
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                if let Some(size) = effects.size {
+                    let loc = self.space.element_location(window).unwrap_or_default();
+                    let _ = surface.configure(Rectangle::from_loc_and_size(loc, size));
+                }
+                if let Some(decorations) = effects.decorations {
+                    window.set_ssd(decorations == Decorations::Server);
+                }
+                match effects.fullscreen {
+                    Some(true) if in_space => self.fullscreen_request_x11(surface),
+                    Some(false) if in_space => self.unfullscreen_request_x11(surface),
+                    _ => {}
+                }
+            }
+        }
+
+        if effects.stacking.is_some() && in_space {
+            // sort the window by its new z-index
+            self.space.raise_element(window, false);
+        }
+
+        if mapping {
+            if effects.scratchpad.is_some() && !self.scratchpad.contains(window) {
+                self.scratchpad.push(window.clone());
+            }
+            // new windows are placed without being activated, so that
+            // `focus = false` leaves the focused window as it is
+            match effects.focus {
+                Some(true) => {
+                    self.space.raise_element(window, true);
+                    let keyboard = self.seat.get_keyboard().unwrap();
+                    keyboard.set_focus(self, Some(window.clone().into()), SERIAL_COUNTER.next_serial());
+                }
+                Some(false) => {}
+                None => self.space.raise_element(window, true),
+            }
+        }
+    }
+}
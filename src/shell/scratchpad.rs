@@ -0,0 +1,128 @@
+use smithay::{
+    desktop::{space::SpaceElement, WindowSurface},
+    utils::{IsAlive, Point, SERIAL_COUNTER},
+};
+use tracing::{debug, warn};
+
+use crate::core::state::{Backend, WayiceState};
+
+use super::{placement::clamp_into, FullscreenSurface, WindowElement};
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Send the focused window to the scratchpad, hiding it until it is
+    /// toggled back
+    pub fn move_to_scratchpad(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        debug!("Moving window to the scratchpad");
+        window.set_floating(true);
+        if let WindowSurface::Wayland(toplevel) = window.0.underlying_surface() {
+            // let the client pick its size again
+            toplevel.with_pending_state(|state| state.size = None);
+        }
+        if !self.scratchpad.contains(&window) {
+            self.scratchpad.push(window.clone());
+        }
+        self.hide_scratchpad_window(&window);
+    }
+
+    /// Show or hide the scratchpad windows of the rule called `name`, or
+    /// those sent to the scratchpad by the `move_to_scratchpad` action.
+    ///
+    /// A shown window on the current output is hidden, otherwise the window
+    /// hidden the longest is shown, so repeated toggles cycle through them.
+    /// The command of a rule is started if none of its windows exist.
+    pub fn toggle_scratchpad(&mut self, name: Option<&str>) {
+        self.scratchpad.retain(|w| w.alive());
+        let candidates: Vec<WindowElement> = self
+            .scratchpad
+            .iter()
+            .filter(|w| w.rule_effects().scratchpad.as_deref() == name)
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            let Some(name) = name else {
+                return;
+            };
+            match self
+                .config
+                .rules
+                .iter()
+                .find(|rule| rule.name == name)
+                .and_then(|rule| rule.scratchpad_command.clone())
+            {
+                Some(spawn) => self.spawn_child(&spawn, false),
+                None => warn!(rule = name, "No window to show for scratchpad"),
+            }
+            return;
+        }
+
+        let output = self.current_output();
+        let shown = candidates.iter().find(|w| {
+            self.space.element_location(w).is_some() && output.is_some() && self.window_output(w) == output
+        });
+        match shown {
+            Some(window) => self.hide_scratchpad_window(&window.clone()),
+            None => self.show_scratchpad_window(&candidates[0]),
+        }
+    }
+
+    /// Unmap a scratchpad window, moving it to the back of the scratchpad
+    fn hide_scratchpad_window(&mut self, window: &WindowElement) {
+        let output = self.window_output(window);
+        if let Some(fullscreen) = output
+            .as_ref()
+            .and_then(|o| o.user_data().get::<FullscreenSurface>())
+        {
+            if fullscreen.get().is_some_and(|w| w == *window) {
+                fullscreen.clear();
+            }
+        }
+        let focused = self.focused_window().as_ref() == Some(window);
+
+        self.space.unmap_elem(window);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.set_hidden(true);
+        }
+        self.scratchpad.retain(|w| w != window);
+        self.scratchpad.push(window.clone());
+        // switching workspaces must not bring it back
+        self.workspaces.forget_windows(|w| w == window);
+
+        self.arrange_all();
+        if let (true, Some(output)) = (focused, output) {
+            self.focus_output(&output);
+        }
+        self.ipc_shm_update_window_list();
+    }
+
+    /// Map a scratchpad window centered on the current output and focus it
+    fn show_scratchpad_window(&mut self, window: &WindowElement) {
+        let Some(area) = self.current_output().and_then(|o| self.usable_area(&o)) else {
+            return;
+        };
+        let size = window.geometry().size;
+        let centered = area.loc + Point::from(((area.size.w - size.w) / 2, (area.size.h - size.h) / 2));
+
+        debug!("Showing scratchpad window");
+        if self.space.element_location(window).is_some() {
+            // shown on another output, bring it over
+            self.space.unmap_elem(window);
+        }
+        // drop any workspace still remembering where it was before
+        self.workspaces.forget_windows(|w| w == window);
+        self.space
+            .map_element(window.clone(), clamp_into(centered, size, area), true);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.set_hidden(false);
+        }
+        window.set_floating(true);
+
+        self.arrange_all();
+        self.focus_window(window, SERIAL_COUNTER.next_serial());
+        self.ipc_shm_update_window_list();
+    }
+}
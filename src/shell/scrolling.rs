@@ -0,0 +1,486 @@
+use smithay::{
+    desktop::Window,
+    output::Output,
+    utils::{IsAlive, Logical, Rectangle, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::LayoutKind,
+    core::{
+        focus::KeyboardFocusTarget,
+        state::{Backend, WayiceState},
+    },
+};
+
+use super::{tiling::inset, WindowElement};
+
+/// A column of the scrolling layout, its windows stacked from top to bottom
+#[derive(Debug)]
+struct Column {
+    windows: Vec<WindowElement>,
+    /// Index of the window last focused in the column
+    active: usize,
+    /// Share of the width of the output the column takes
+    width: f64,
+    /// Left edge within the strip, as of the last arrangement
+    x: i32,
+    /// Width in logical pixels, as of the last arrangement
+    pixels: i32,
+}
+
+impl Column {
+    fn new(window: WindowElement, width: f64) -> Self {
+        Column {
+            windows: vec![window],
+            active: 0,
+            width,
+            x: 0,
+            pixels: 0,
+        }
+    }
+
+    fn contains(&self, window: &Window) -> bool {
+        self.windows.iter().any(|w| w.0 == *window)
+    }
+}
+
+/// The columns of a workspace using the scrolling layout, side by side on a
+/// strip wider than the output.
+///
+/// Only the columns in view are mapped in the space, the others are kept
+/// here alone.
+#[derive(Debug, Default)]
+pub struct Strip {
+    columns: Vec<Column>,
+    /// Left edge of the view within the strip
+    scroll: i32,
+}
+
+impl Strip {
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &WindowElement> {
+        self.columns.iter().flat_map(|c| c.windows.iter())
+    }
+
+    fn column_of(&self, window: &Window) -> Option<usize> {
+        self.columns.iter().position(|c| c.contains(window))
+    }
+
+    /// Keep the windows `keep` picks, dropping columns left empty, and
+    /// return the others
+    pub fn retain(&mut self, keep: impl Fn(&WindowElement) -> bool) -> Vec<WindowElement> {
+        let mut removed = Vec::new();
+        for column in &mut self.columns {
+            column.windows.retain(|w| {
+                let kept = keep(w);
+                if !kept {
+                    removed.push(w.clone());
+                }
+                kept
+            });
+            column.active = column.active.min(column.windows.len().saturating_sub(1));
+        }
+        self.columns.retain(|c| !c.windows.is_empty());
+        removed
+    }
+
+    /// Trade the places of two windows in the columns
+    pub fn swap(&mut self, a: &WindowElement, b: &WindowElement) {
+        for window in self.columns.iter_mut().flat_map(|c| c.windows.iter_mut()) {
+            if window == a {
+                *window = b.clone();
+            } else if window == b {
+                *window = a.clone();
+            }
+        }
+    }
+
+    /// Take the window out of its column, returning the width of the column
+    /// if it was the last window in it
+    fn remove(&mut self, window: &Window) -> Option<f64> {
+        let index = self.column_of(window)?;
+        let column = &mut self.columns[index];
+        column.windows.retain(|w| w.0 != *window);
+        column.active = column.active.min(column.windows.len().saturating_sub(1));
+        column
+            .windows
+            .is_empty()
+            .then(|| self.columns.remove(index).width)
+    }
+
+    /// Compute where every window goes, scrolling the view so the column at
+    /// `target` is fully visible.
+    ///
+    /// Windows of columns whose center is out of view get no rectangle.
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        target: Option<usize>,
+        focus: Option<&Window>,
+    ) -> Vec<(WindowElement, Option<Rectangle<i32, Logical>>)> {
+        let mut x = 0;
+        for column in &mut self.columns {
+            column.pixels = (area.size.w as f64 * column.width).round() as i32;
+            column.x = x;
+            x += column.pixels;
+            if let Some(index) = focus.and_then(|f| column.windows.iter().position(|w| w.0 == *f)) {
+                column.active = index;
+            }
+        }
+
+        let target = target
+            .and_then(|index| self.columns.get(index))
+            .map(|column| (column.x, column.pixels));
+        self.scroll = scroll_into_view(self.scroll, target, area.size.w, x);
+
+        let mut placements = Vec::new();
+        for column in &self.columns {
+            let left = column.x - self.scroll;
+            let visible = in_view(left, column.pixels, area.size.w);
+            let rows = column.windows.len() as i32;
+            for (row, window) in column.windows.iter().enumerate() {
+                let rect = visible.then(|| {
+                    let (top, bottom) = row_span(area.size.h, row as i32, rows);
+                    Rectangle::from_loc_and_size(
+                        (area.loc.x + left, area.loc.y + top),
+                        (column.pixels, bottom - top),
+                    )
+                });
+                placements.push((window.clone(), rect));
+            }
+        }
+        placements
+    }
+
+    /// Move the window into a column of its own at `view_x`, the horizontal
+    /// position it was dropped at relative to the view
+    fn drop_at(&mut self, window: &WindowElement, view_x: i32, width: f64) {
+        let width = self.remove(&window.0).unwrap_or(width);
+        let strip_x = view_x + self.scroll;
+        let index = drop_index(self.columns.iter().map(|c| (c.x, c.pixels)), strip_x);
+        self.columns.insert(index, Column::new(window.clone(), width));
+    }
+}
+
+/// Left edge of a view `view_width` wide on a strip `strip_width` wide,
+/// moved as little as possible from `scroll` to fully show the column at
+/// `target`, given as its left edge and width
+fn scroll_into_view(scroll: i32, target: Option<(i32, i32)>, view_width: i32, strip_width: i32) -> i32 {
+    let mut scroll = scroll;
+    if let Some((x, width)) = target {
+        if x < scroll {
+            scroll = x;
+        } else if x + width > scroll + view_width {
+            scroll = x + width - view_width;
+        }
+    }
+    scroll.clamp(0, (strip_width - view_width).max(0))
+}
+
+/// Whether the center of a column `width` wide at `left`, relative to a view
+/// `view_width` wide, is in view
+fn in_view(left: i32, width: i32, view_width: i32) -> bool {
+    let center = left + width / 2;
+    center >= 0 && center < view_width
+}
+
+/// Top and bottom of the window at `row` of a column of `rows` windows
+/// `height` high.
+///
+/// The rounding error is spread over the rows instead of leaving a gap at the
+/// bottom.
+fn row_span(height: i32, row: i32, rows: i32) -> (i32, i32) {
+    (height * row / rows, height * (row + 1) / rows)
+}
+
+/// Index a column dropped at `strip_x` goes to among `columns`, given as
+/// their left edges and widths: right of every column whose center it is
+/// right of
+fn drop_index(columns: impl Iterator<Item = (i32, i32)>, strip_x: i32) -> usize {
+    columns.filter(|(x, width)| x + width / 2 < strip_x).count()
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// The window that has keyboard focus, also if it is scrolled out of view
+    fn keyboard_focus(&self) -> Option<Window> {
+        match self.seat.get_keyboard()?.current_focus() {
+            Some(KeyboardFocusTarget::Window(window)) => Some(window),
+            _ => None,
+        }
+    }
+
+    /// Arrange the columns of the workspace `name` shown on `output`, adding
+    /// new windows in a column right of the focused one
+    pub(super) fn arrange_strip(&mut self, output: &Output, name: &str) {
+        let Some(area) = self.tiling_area(output) else {
+            return;
+        };
+        let windows = self.windows_on_output(output);
+        let Some(workspace) = self.workspaces.get(name) else {
+            return;
+        };
+        // windows scrolled out of view are not in the space, keep them unless
+        // they were closed or moved to another workspace
+        let kept: Vec<WindowElement> = workspace
+            .strip
+            .windows()
+            .filter(|w| {
+                let scrolled_out = self.space.element_location(w).is_none() && !self.workspaces.is_hidden(w);
+                (windows.contains(w) || scrolled_out) && w.is_tileable()
+            })
+            .cloned()
+            .collect();
+        let added: Vec<WindowElement> = windows
+            .iter()
+            .filter(|w| w.is_tileable() && !workspace.strip.windows().any(|s| s == *w))
+            .cloned()
+            .collect();
+        let focus = self.keyboard_focus();
+        let column_width = self.config.tiling.column_width;
+
+        let Some(workspace) = self.workspaces.get_mut(name) else {
+            return;
+        };
+        let strip = &mut workspace.strip;
+        let removed = strip.retain(|w| kept.contains(w));
+        let mut target = focus.as_ref().and_then(|f| strip.column_of(f));
+        for window in added {
+            let index = target.map_or(strip.columns.len(), |index| index + 1);
+            strip.columns.insert(index, Column::new(window, column_width));
+            // scroll to new windows, they only get configured once mapped
+            target = Some(index);
+        }
+        let placements = strip.arrange(area, target, focus.as_ref());
+
+        for window in removed.iter().filter(|w| w.alive()) {
+            window.set_tiled(false);
+        }
+        let half_gap = self.config.tiling.gaps / 2;
+        let mut tiles = Vec::new();
+        for (window, rect) in placements {
+            match rect {
+                Some(rect) => self.place_tile(&window, inset(rect, half_gap)),
+                None if self.dragged_window.as_ref() != Some(&window) => self.space.unmap_elem(&window),
+                None => {}
+            }
+            tiles.push(window);
+        }
+        self.raise_floating(&windows, &tiles);
+    }
+
+    /// Map the windows scrolled out of view again and forget the columns of
+    /// the workspace `name`, as it no longer uses the scrolling layout
+    pub(super) fn release_strip(&mut self, output: &Output, name: &str) {
+        let Some(workspace) = self.workspaces.get_mut(name) else {
+            return;
+        };
+        if workspace.strip.is_empty() {
+            return;
+        }
+        let windows = workspace.strip.retain(|_| false);
+        let location = self.tiling_area(output).map(|area| area.loc).unwrap_or_default();
+        for window in windows {
+            if self.space.element_location(&window).is_none() && !self.workspaces.is_hidden(&window) {
+                self.space.map_element(window, location, false);
+            }
+        }
+    }
+
+    /// Run `change` on the strip of the workspace shown on the output of the
+    /// focused window, with the index of the focused column, and arrange it
+    fn change_strip(&mut self, change: impl FnOnce(&mut Strip, usize)) {
+        let Some(output) = self.tiling_output() else {
+            return;
+        };
+        let name = self.active_workspace(&output);
+        if self.workspace_layout(&name) != LayoutKind::Scrolling {
+            return;
+        }
+        let Some(focus) = self.keyboard_focus() else {
+            return;
+        };
+        let Some(workspace) = self.workspaces.get_mut(&name) else {
+            return;
+        };
+        let Some(index) = workspace.strip.column_of(&focus) else {
+            return;
+        };
+        change(&mut workspace.strip, index);
+        self.arrange_output(&output);
+    }
+
+    /// Focus the column `offset` places right of the focused one, scrolling
+    /// it into view
+    pub fn focus_column(&mut self, offset: i32) {
+        let Some(output) = self.tiling_output() else {
+            return;
+        };
+        let name = self.active_workspace(&output);
+        let Some(workspace) = self.workspaces.get(&name) else {
+            return;
+        };
+        let strip = &workspace.strip;
+        if strip.is_empty() || self.workspace_layout(&name) != LayoutKind::Scrolling {
+            return;
+        }
+        let index = match self.keyboard_focus().and_then(|f| strip.column_of(&f)) {
+            Some(index) => (index as i32 + offset).clamp(0, strip.columns.len() as i32 - 1) as usize,
+            None => 0,
+        };
+        let column = &strip.columns[index];
+        let window = column.windows[column.active].clone();
+
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(self, Some(window.clone().into()), SERIAL_COUNTER.next_serial());
+        self.arrange_output(&output);
+        self.space.raise_element(&window, true);
+    }
+
+    /// Swap the focused column with the one `offset` places right of it
+    pub fn move_column(&mut self, offset: i32) {
+        self.change_strip(|strip, index| {
+            let other = (index as i32 + offset).clamp(0, strip.columns.len() as i32 - 1) as usize;
+            strip.columns.swap(index, other);
+        });
+    }
+
+    /// Stack the focused window at the bottom of the column left of it
+    pub fn consume_window(&mut self) {
+        let Some(focus) = self.keyboard_focus() else {
+            return;
+        };
+        self.change_strip(|strip, index| {
+            if index == 0 {
+                return;
+            }
+            let column = &strip.columns[index];
+            let Some(window) = column.windows.iter().find(|w| w.0 == focus).cloned() else {
+                return;
+            };
+            strip.remove(&focus);
+            let left = &mut strip.columns[index - 1];
+            left.windows.push(window);
+            left.active = left.windows.len() - 1;
+        });
+    }
+
+    /// Take the focused window out of its column into a new column right of it
+    pub fn expel_window(&mut self) {
+        let Some(focus) = self.keyboard_focus() else {
+            return;
+        };
+        self.change_strip(|strip, index| {
+            let column = &strip.columns[index];
+            if column.windows.len() < 2 {
+                return;
+            }
+            let width = column.width;
+            let Some(window) = column.windows.iter().find(|w| w.0 == focus).cloned() else {
+                return;
+            };
+            strip.remove(&focus);
+            strip.columns.insert(index + 1, Column::new(window, width));
+        });
+    }
+
+    /// Change the width of the focused column by `delta`
+    pub(super) fn resize_column(&mut self, delta: f64) {
+        self.change_strip(|strip, index| {
+            let column = &mut strip.columns[index];
+            column.width = (column.width + delta).clamp(0.1, 1.0);
+        });
+    }
+
+    /// Put a window released by a move grab back into the layout, in the
+    /// scrolling layout as a column where it was dropped
+    pub fn drop_window(&mut self, window: &WindowElement) {
+        if self.dragged_window.as_ref() != Some(window) {
+            return;
+        }
+        self.dragged_window = None;
+
+        if let (Some(output), Some(geometry)) =
+            (self.window_output(window), self.space.element_geometry(window))
+        {
+            let name = self.active_workspace(&output);
+            let area = self.tiling_area(&output);
+            let column_width = self.config.tiling.column_width;
+            let scrolling = self.workspace_layout(&name) == LayoutKind::Scrolling;
+            if let (true, Some(area), Some(workspace)) = (scrolling, area, self.workspaces.get_mut(&name)) {
+                if window.is_tileable() {
+                    let center = geometry.loc.x + geometry.size.w / 2;
+                    debug!(workspace = name, "Dropping window into a column");
+                    workspace.strip.drop_at(window, center - area.loc.x, column_width);
+                }
+            }
+        }
+        self.arrange_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_into_view_without_target_stays() {
+        assert_eq!(scroll_into_view(300, None, 1000, 3000), 300);
+    }
+
+    #[test]
+    fn scroll_into_view_keeps_visible_column() {
+        assert_eq!(scroll_into_view(300, Some((500, 400)), 1000, 3000), 300);
+    }
+
+    #[test]
+    fn scroll_into_view_scrolls_left_and_right() {
+        assert_eq!(scroll_into_view(300, Some((100, 400)), 1000, 3000), 100);
+        assert_eq!(scroll_into_view(300, Some((1200, 500)), 1000, 3000), 700);
+    }
+
+    #[test]
+    fn scroll_into_view_stays_on_strip() {
+        // the strip got narrower than where the view was
+        assert_eq!(scroll_into_view(2500, None, 1000, 3000), 2000);
+        // a strip narrower than the view does not scroll
+        assert_eq!(scroll_into_view(300, Some((0, 500)), 1000, 800), 0);
+    }
+
+    #[test]
+    fn in_view_checks_column_center() {
+        assert!(in_view(0, 500, 1000));
+        assert!(in_view(-200, 500, 1000));
+        assert!(!in_view(-300, 500, 1000));
+        assert!(in_view(700, 500, 1000));
+        assert!(!in_view(800, 500, 1000));
+    }
+
+    #[test]
+    fn row_span_single_row_fills_column() {
+        assert_eq!(row_span(700, 0, 1), (0, 700));
+    }
+
+    #[test]
+    fn row_span_rounding_leaves_no_gaps() {
+        let spans: Vec<_> = (0..3).map(|row| row_span(700, row, 3)).collect();
+        assert_eq!(spans, vec![(0, 233), (233, 466), (466, 700)]);
+    }
+
+    #[test]
+    fn drop_index_on_empty_strip() {
+        assert_eq!(drop_index(std::iter::empty(), 500), 0);
+    }
+
+    #[test]
+    fn drop_index_goes_by_column_centers() {
+        let columns = [(0, 500), (500, 500), (1000, 1000)];
+        assert_eq!(drop_index(columns.into_iter(), 100), 0);
+        assert_eq!(drop_index(columns.into_iter(), 300), 1);
+        assert_eq!(drop_index(columns.into_iter(), 1400), 2);
+        assert_eq!(drop_index(columns.into_iter(), 1600), 3);
+    }
+}
@@ -0,0 +1,290 @@
+use std::cell::Cell;
+
+use smithay::{
+    backend::renderer::element::{
+        solid::{SolidColorBuffer, SolidColorRenderElement},
+        Kind,
+    },
+    desktop::{space::SpaceElement, WindowSurface},
+    output::Output,
+    utils::{Logical, Point, Rectangle, Scale, Size},
+    wayland::shell::xdg::XdgShellHandler,
+};
+use tracing::debug;
+
+use crate::core::state::{Backend, WayiceState};
+
+use super::WindowElement;
+
+/// Distance from the edge of an output within which the pointer is in a
+/// drop zone
+const DROP_ZONE_SIZE: f64 = 8.0;
+
+/// Where a window dropped at the edge of an output goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapZone {
+    LeftHalf,
+    RightHalf,
+    Maximize,
+}
+
+/// Translucent rectangle showing where the window being moved goes if it is
+/// dropped in a drop zone
+#[derive(Debug)]
+pub struct SnapPreview {
+    output: Output,
+    zone: SnapZone,
+    /// Area the window will cover, relative to the output
+    area: Rectangle<i32, Logical>,
+    buffer: SolidColorBuffer,
+}
+
+impl SnapPreview {
+    /// The element drawing the preview, if it is shown on `output`
+    pub fn render_element(&self, output: &Output, scale: Scale<f64>) -> Option<SolidColorRenderElement> {
+        if *output != self.output {
+            return None;
+        }
+        Some(SolidColorRenderElement::from_buffer(
+            &self.buffer,
+            self.area.loc.to_physical_precise_round(scale),
+            scale,
+            1.0,
+            Kind::Unspecified,
+        ))
+    }
+}
+
+/// Size of a window before it was snapped to half of an output, restored
+/// once it is moved away
+#[derive(Debug, Default)]
+struct UnsnappedSize(Cell<Option<Size<i32, Logical>>>);
+
+impl WindowElement {
+    fn unsnapped_size(&self) -> &Cell<Option<Size<i32, Logical>>> {
+        self.user_data().insert_if_missing(UnsnappedSize::default);
+        &self.user_data().get::<UnsnappedSize>().unwrap().0
+    }
+}
+
+/// Offset bringing one end of `start..end` onto one of `lines`, if one is
+/// closer than `threshold`
+fn snap_offset(start: i32, end: i32, lines: &[i32], threshold: i32) -> i32 {
+    lines
+        .iter()
+        .flat_map(|line| [line - start, line - end])
+        .filter(|offset| offset.abs() < threshold)
+        .min_by_key(|offset| offset.abs())
+        .unwrap_or(0)
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Where a window moved by a grab to `location` goes, snapped to nearby
+    /// output and window edges.
+    ///
+    /// Also shows the preview of the drop zone under `pointer`, if any.
+    pub fn snap_move(
+        &mut self,
+        window: &WindowElement,
+        location: Point<i32, Logical>,
+        pointer: Point<f64, Logical>,
+    ) -> Point<i32, Logical> {
+        if self.dragged_window.as_ref() != Some(window) {
+            self.unsnap(window);
+        }
+        // tiled windows go back to their tile when dropped
+        if self.is_tiled(window) {
+            self.snap_preview = None;
+            return location;
+        }
+        self.update_snap_preview(pointer);
+
+        let threshold = self.config.snapping.threshold;
+        if threshold == 0 {
+            return location;
+        }
+        let rect = Rectangle::from_loc_and_size(location, window.geometry().size);
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        for output in self.space.outputs() {
+            let areas = [self.space.output_geometry(output), self.usable_area(output)];
+            for area in areas.into_iter().flatten() {
+                xs.extend([area.loc.x, area.loc.x + area.size.w]);
+                ys.extend([area.loc.y, area.loc.y + area.size.h]);
+            }
+        }
+        for other in self.space.elements().filter(|other| *other != window) {
+            let Some(geometry) = self.space.element_geometry(other) else {
+                continue;
+            };
+            // only edges the window could touch
+            if geometry.loc.y < rect.loc.y + rect.size.h + threshold
+                && rect.loc.y < geometry.loc.y + geometry.size.h + threshold
+            {
+                xs.extend([geometry.loc.x, geometry.loc.x + geometry.size.w]);
+            }
+            if geometry.loc.x < rect.loc.x + rect.size.w + threshold
+                && rect.loc.x < geometry.loc.x + geometry.size.w + threshold
+            {
+                ys.extend([geometry.loc.y, geometry.loc.y + geometry.size.h]);
+            }
+        }
+
+        location
+            + Point::from((
+                snap_offset(rect.loc.x, rect.loc.x + rect.size.w, &xs, threshold),
+                snap_offset(rect.loc.y, rect.loc.y + rect.size.h, &ys, threshold),
+            ))
+    }
+
+    /// Show the preview of the drop zone under `pointer`, or hide it if there
+    /// is none
+    fn update_snap_preview(&mut self, pointer: Point<f64, Logical>) {
+        let zone = if self.config.snapping.drop_zones {
+            self.drop_zone(pointer)
+        } else {
+            None
+        };
+        let Some((output, zone, area)) = zone else {
+            self.snap_preview = None;
+            return;
+        };
+        let color = self.config.snapping.preview_color;
+        let preview = self.snap_preview.get_or_insert_with(|| SnapPreview {
+            output: output.clone(),
+            zone,
+            area,
+            buffer: SolidColorBuffer::default(),
+        });
+        preview.output = output;
+        preview.zone = zone;
+        preview.area = area;
+        // solid colors are drawn with premultiplied alpha
+        preview.buffer.update(
+            area.size,
+            [
+                color[0] * color[3],
+                color[1] * color[3],
+                color[2] * color[3],
+                color[3],
+            ],
+        );
+    }
+
+    /// The drop zone `pointer` is in, with the output it belongs to and the
+    /// area a window dropped there covers, relative to the output
+    fn drop_zone(&self, pointer: Point<f64, Logical>) -> Option<(Output, SnapZone, Rectangle<i32, Logical>)> {
+        let output = self.space.output_under(pointer).next()?.clone();
+        let geometry = self.space.output_geometry(&output)?;
+        let zone = if pointer.x < geometry.loc.x as f64 + DROP_ZONE_SIZE {
+            SnapZone::LeftHalf
+        } else if pointer.x >= (geometry.loc.x + geometry.size.w) as f64 - DROP_ZONE_SIZE {
+            SnapZone::RightHalf
+        } else if pointer.y < geometry.loc.y as f64 + DROP_ZONE_SIZE {
+            SnapZone::Maximize
+        } else {
+            return None;
+        };
+        let mut area = self.snap_zone_area(&output, zone)?;
+        area.loc -= geometry.loc;
+        Some((output, zone, area))
+    }
+
+    /// Area of `output` a window dropped in `zone` covers
+    fn snap_zone_area(&self, output: &Output, zone: SnapZone) -> Option<Rectangle<i32, Logical>> {
+        match zone {
+            // maximized windows cover the whole output
+            SnapZone::Maximize => self.space.output_geometry(output),
+            SnapZone::LeftHalf | SnapZone::RightHalf => {
+                let mut area = self.usable_area(output)?;
+                let half = area.size.w / 2;
+                if zone == SnapZone::RightHalf {
+                    area.loc.x += half;
+                    area.size.w -= half;
+                } else {
+                    area.size.w = half;
+                }
+                Some(area)
+            }
+        }
+    }
+
+    /// Put a window released by a move grab in the drop zone it was dropped
+    /// in, if any
+    pub fn drop_in_zone(&mut self, window: &WindowElement) {
+        let Some(preview) = self.snap_preview.take() else {
+            return;
+        };
+        if self.is_tiled(window) {
+            return;
+        }
+        debug!(zone = ?preview.zone, "Dropping window in a drop zone");
+        match preview.zone {
+            SnapZone::Maximize => match window.0.underlying_surface() {
+                WindowSurface::Wayland(toplevel) => self.maximize_request(toplevel.clone()),
+                #[cfg(feature = "xwayland")]
+                WindowSurface::X11(surface) => self.maximize_request_x11(surface),
+            },
+            SnapZone::LeftHalf | SnapZone::RightHalf => {
+                let Some(area) = self.snap_zone_area(&preview.output, preview.zone) else {
+                    return;
+                };
+                window.unsnapped_size().set(Some(window.0.geometry().size));
+                self.place_tile(window, area);
+            }
+        }
+    }
+
+    /// Give a window snapped to half of an output its size back as it starts
+    /// being moved
+    fn unsnap(&mut self, window: &WindowElement) {
+        let Some(size) = window.unsnapped_size().take() else {
+            return;
+        };
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| state.size = Some(size));
+                window.set_tiled(false);
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                if let Some(location) = self.space.element_location(window) {
+                    let _ = surface.configure(Rectangle::from_loc_and_size(location, size));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_offset_without_lines() {
+        assert_eq!(snap_offset(100, 300, &[], 10), 0);
+    }
+
+    #[test]
+    fn snap_offset_snaps_start_within_threshold() {
+        assert_eq!(snap_offset(100, 300, &[105], 10), 5);
+        assert_eq!(snap_offset(100, 300, &[92], 10), -8);
+    }
+
+    #[test]
+    fn snap_offset_snaps_end_within_threshold() {
+        assert_eq!(snap_offset(100, 300, &[297], 10), -3);
+    }
+
+    #[test]
+    fn snap_offset_ignores_lines_outside_threshold() {
+        assert_eq!(snap_offset(100, 300, &[120, 200, 280], 10), 0);
+        // the threshold itself is too far
+        assert_eq!(snap_offset(100, 300, &[110], 10), 0);
+    }
+
+    #[test]
+    fn snap_offset_picks_closest_line() {
+        assert_eq!(snap_offset(100, 300, &[95, 303], 10), 3);
+    }
+}
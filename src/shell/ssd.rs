@@ -8,13 +8,17 @@ use smithay::{
     },
     desktop::WindowSurface,
     input::Seat,
-    utils::{Logical, Point, Serial},
+    utils::{Logical, Point, Rectangle, Serial},
     wayland::shell::xdg::XdgShellHandler,
 };
 
 use std::cell::{RefCell, RefMut};
 
-use crate::{core::state::Backend, core::state::WayiceState};
+use crate::{
+    config::{DecorationButton, DecorationTheme},
+    core::state::Backend,
+    core::state::WayiceState,
+};
 
 use super::WindowElement;
 
@@ -23,28 +27,66 @@ pub struct WindowState {
     pub header_bar: HeaderBar,
 }
 
+impl WindowState {
+    /// Whether the header bar is shown, which it is for server-side decorated
+    /// windows and windows in a group
+    pub fn has_header(&self) -> bool {
+        self.is_ssd || self.header_bar.tabs.is_some()
+    }
+
+    /// Height of the header bar, zero unless it is shown
+    pub fn header_height(&self) -> i32 {
+        if self.has_header() {
+            self.header_bar.height()
+        } else {
+            0
+        }
+    }
+}
+
+/// The tabs of a window group, shown in the header bar of its shown window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tabs {
+    pub count: usize,
+    /// Index of the shown window
+    pub active: usize,
+    /// Whether the tabs are stacked title bars instead of side by side
+    pub stacked: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct HeaderBar {
     pub pointer_loc: Option<Point<f64, Logical>>,
     pub width: u32,
-    pub close_button_hover: bool,
-    pub maximize_button_hover: bool,
+    /// Whether the window is focused, which selects the colors used
+    pub focused: bool,
+    pub theme: DecorationTheme,
     pub background: SolidColorBuffer,
-    pub close_button: SolidColorBuffer,
-    pub maximize_button: SolidColorBuffer,
+    /// One buffer for every button of the theme, in the same order
+    pub buttons: Vec<SolidColorBuffer>,
+    /// Tabs of the group of the window, if it is in one
+    pub tabs: Option<Tabs>,
+    /// The tabs other than the active one, and where they are drawn
+    inactive_tabs: Vec<(Point<i32, Logical>, SolidColorBuffer)>,
+    /// Width, focus, hovered button and tabs the buffers were last drawn for
+    drawn: Option<(u32, bool, Option<usize>, Option<Tabs>)>,
 }
 
-const BG_COLOR: [f32; 4] = [0.75f32, 0.9f32, 0.78f32, 1f32];
-const MAX_COLOR: [f32; 4] = [1f32, 0.965f32, 0.71f32, 1f32];
-const CLOSE_COLOR: [f32; 4] = [1f32, 0.66f32, 0.612f32, 1f32];
-const MAX_COLOR_HOVER: [f32; 4] = [0.71f32, 0.624f32, 0f32, 1f32];
-const CLOSE_COLOR_HOVER: [f32; 4] = [0.75f32, 0.11f32, 0.016f32, 1f32];
-
-pub const HEADER_BAR_HEIGHT: i32 = 32;
-const BUTTON_HEIGHT: u32 = HEADER_BAR_HEIGHT as u32;
-const BUTTON_WIDTH: u32 = 32;
-
 impl HeaderBar {
+    fn new(theme: DecorationTheme) -> Self {
+        HeaderBar {
+            pointer_loc: None,
+            width: 0,
+            focused: false,
+            theme,
+            background: SolidColorBuffer::default(),
+            buttons: Vec::new(),
+            tabs: None,
+            inactive_tabs: Vec::new(),
+            drawn: None,
+        }
+    }
+
     pub fn pointer_enter(&mut self, loc: Point<f64, Logical>) {
         self.pointer_loc = Some(loc);
     }
@@ -53,6 +95,66 @@ impl HeaderBar {
         self.pointer_loc = None;
     }
 
+    pub fn set_theme(&mut self, theme: &DecorationTheme) {
+        if self.theme != *theme {
+            self.theme = theme.clone();
+            self.drawn = None;
+        }
+    }
+
+    /// Height of all rows of the header bar, which has one per tab when
+    /// they are stacked
+    pub fn height(&self) -> i32 {
+        match self.tabs {
+            Some(tabs) if tabs.stacked => self.theme.height * tabs.count as i32,
+            _ => self.theme.height,
+        }
+    }
+
+    /// Offset of the row holding the buttons, the one of the active tab
+    fn active_row(&self) -> i32 {
+        match self.tabs {
+            Some(tabs) if tabs.stacked => self.theme.height * tabs.active as i32,
+            _ => 0,
+        }
+    }
+
+    /// Index of the button under the pointer, if any
+    fn hovered_button(&self) -> Option<usize> {
+        let loc = self.pointer_loc?;
+        let row = self.active_row() as f64;
+        if loc.y < row || loc.y >= row + self.theme.height as f64 {
+            return None;
+        }
+        self.theme.button_index_at(loc.x, self.width as i32)
+    }
+
+    /// Horizontal span of the tab at `index` when the tabs are side by side
+    fn tab_span(&self, tabs: Tabs, index: usize) -> (i32, i32) {
+        let (start, end) = self.theme.tab_span(self.width as i32);
+        let count = tabs.count as i32;
+        let index = index as i32;
+        (
+            start + (end - start) * index / count,
+            start + (end - start) * (index + 1) / count,
+        )
+    }
+
+    /// Index of the tab under the pointer, unless it is the active one
+    fn hovered_inactive_tab(&self) -> Option<usize> {
+        let loc = self.pointer_loc?;
+        let tabs = self.tabs?;
+        let index = if tabs.stacked {
+            (loc.y / self.theme.height as f64) as usize
+        } else {
+            (0..tabs.count).find(|index| {
+                let (start, end) = self.tab_span(tabs, *index);
+                loc.x >= start as f64 && loc.x < end as f64
+            })?
+        };
+        (index < tabs.count && index != tabs.active).then_some(index)
+    }
+
     pub fn clicked<BackendData: Backend>(
         &mut self,
         seat: &Seat<WayiceState<BackendData>>,
@@ -60,8 +162,17 @@ impl HeaderBar {
         window: &WindowElement,
         serial: Serial,
     ) {
-        match self.pointer_loc.as_ref() {
-            Some(loc) if loc.x >= (self.width - BUTTON_WIDTH) as f64 => {
+        if let Some(index) = self.hovered_inactive_tab() {
+            // the header bar is borrowed until the click is handled
+            let window = window.clone();
+            state
+                .handle
+                .insert_idle(move |data| data.show_tab_of(&window, index));
+            return;
+        }
+        let button = self.hovered_button().map(|index| self.theme.buttons[index]);
+        match (self.pointer_loc.as_ref(), button) {
+            (Some(_), Some(DecorationButton::Close)) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => w.send_close(),
                     #[cfg(feature = "xwayland")]
@@ -70,7 +181,7 @@ impl HeaderBar {
                     }
                 };
             }
-            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 2)) as f64 => {
+            (Some(_), Some(DecorationButton::Maximize)) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => state.maximize_request(w.clone()),
                     #[cfg(feature = "xwayland")]
@@ -82,7 +193,14 @@ impl HeaderBar {
                     }
                 };
             }
-            Some(_) => {
+            (Some(_), Some(DecorationButton::Minimize)) => {
+                // the header bar is borrowed until the click is handled
+                let window = window.clone();
+                state
+                    .handle
+                    .insert_idle(move |data| data.minimize_window(&window));
+            }
+            (Some(_), None) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => {
                         let seat = seat.clone();
@@ -111,10 +229,17 @@ impl HeaderBar {
         window: &WindowElement,
         serial: Serial,
     ) {
-        match self.pointer_loc.as_ref() {
-            Some(loc) if loc.x >= (self.width - BUTTON_WIDTH) as f64 => {}
-            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 2)) as f64 => {}
-            Some(_) => {
+        if let Some(index) = self.hovered_inactive_tab() {
+            // the header bar is borrowed until the click is handled
+            let window = window.clone();
+            state
+                .handle
+                .insert_idle(move |data| data.show_tab_of(&window, index));
+            return;
+        }
+        match (self.pointer_loc.as_ref(), self.hovered_button()) {
+            (Some(_), Some(_)) => {}
+            (Some(_), None) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => {
                         let seat = seat.clone();
@@ -143,8 +268,9 @@ impl HeaderBar {
         window: &WindowElement,
         _serial: Serial,
     ) {
-        match self.pointer_loc.as_ref() {
-            Some(loc) if loc.x >= (self.width - BUTTON_WIDTH) as f64 => {
+        let button = self.hovered_button().map(|index| self.theme.buttons[index]);
+        match (self.pointer_loc.as_ref(), button) {
+            (Some(_), Some(DecorationButton::Close)) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => w.send_close(),
                     #[cfg(feature = "xwayland")]
@@ -153,7 +279,7 @@ impl HeaderBar {
                     }
                 };
             }
-            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 2)) as f64 => {
+            (Some(_), Some(DecorationButton::Maximize)) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => state.maximize_request(w.clone()),
                     #[cfg(feature = "xwayland")]
@@ -165,6 +291,13 @@ impl HeaderBar {
                     }
                 };
             }
+            (Some(_), Some(DecorationButton::Minimize)) => {
+                // the header bar is borrowed until the click is handled
+                let window = window.clone();
+                state
+                    .handle
+                    .insert_idle(move |data| data.minimize_window(&window));
+            }
             _ => {}
         };
     }
@@ -174,59 +307,51 @@ impl HeaderBar {
             self.width = 0;
             return;
         }
+        self.width = width;
 
-        self.background
-            .update((width as i32, HEADER_BAR_HEIGHT), BG_COLOR);
-
-        let mut needs_redraw_buttons = false;
-        if width != self.width {
-            needs_redraw_buttons = true;
-            self.width = width;
+        let hovered = self.hovered_button();
+        if self.drawn == Some((width, self.focused, hovered, self.tabs)) {
+            return;
         }
 
-        if self
-            .pointer_loc
-            .as_ref()
-            .map(|l| l.x >= (width - BUTTON_WIDTH) as f64)
-            .unwrap_or(false)
-            && (needs_redraw_buttons || !self.close_button_hover)
-        {
-            self.close_button
-                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), CLOSE_COLOR_HOVER);
-            self.close_button_hover = true;
-        } else if !self
-            .pointer_loc
-            .as_ref()
-            .map(|l| l.x >= (width - BUTTON_WIDTH) as f64)
-            .unwrap_or(false)
-            && (needs_redraw_buttons || self.close_button_hover)
-        {
-            self.close_button
-                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), CLOSE_COLOR);
-            self.close_button_hover = false;
+        let colors = if self.focused {
+            self.theme.focused
+        } else {
+            self.theme.unfocused
+        };
+        // the background shows through between the inactive tabs
+        self.background
+            .update((width as i32, self.height()), colors.background);
+        self.buttons
+            .resize_with(self.theme.buttons.len(), SolidColorBuffer::default);
+        for (index, (button, buffer)) in self.theme.buttons.iter().zip(&mut self.buttons).enumerate() {
+            buffer.update(
+                (self.theme.button_width, self.theme.height),
+                colors.button(*button, hovered == Some(index)),
+            );
         }
-
-        if self
-            .pointer_loc
-            .as_ref()
-            .map(|l| l.x >= (width - BUTTON_WIDTH * 2) as f64 && l.x <= (width - BUTTON_WIDTH) as f64)
-            .unwrap_or(false)
-            && (needs_redraw_buttons || !self.maximize_button_hover)
-        {
-            self.maximize_button
-                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), MAX_COLOR_HOVER);
-            self.maximize_button_hover = true;
-        } else if !self
-            .pointer_loc
-            .as_ref()
-            .map(|l| l.x >= (width - BUTTON_WIDTH * 2) as f64 && l.x <= (width - BUTTON_WIDTH) as f64)
-            .unwrap_or(false)
-            && (needs_redraw_buttons || self.maximize_button_hover)
-        {
-            self.maximize_button
-                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), MAX_COLOR);
-            self.maximize_button_hover = false;
+        let inactive_tabs: Vec<Rectangle<i32, Logical>> = match self.tabs {
+            Some(tabs) => (0..tabs.count)
+                .filter(|index| *index != tabs.active)
+                .map(|index| {
+                    if tabs.stacked {
+                        let y = self.theme.height * index as i32;
+                        Rectangle::from_loc_and_size((0, y), (width as i32, self.theme.height - 1))
+                    } else {
+                        let (start, end) = self.tab_span(tabs, index);
+                        Rectangle::from_loc_and_size((start, 0), (end - start - 1, self.theme.height))
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        self.inactive_tabs
+            .resize_with(inactive_tabs.len(), Default::default);
+        for ((loc, buffer), rect) in self.inactive_tabs.iter_mut().zip(inactive_tabs) {
+            *loc = rect.loc;
+            buffer.update(rect.size, colors.inactive_tab);
         }
+        self.drawn = Some((width, self.focused, hovered, self.tabs));
     }
 }
 
@@ -240,29 +365,32 @@ impl<R: Renderer> AsRenderElements<R> for HeaderBar {
         scale: smithay::utils::Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
-        let header_end_offset: Point<i32, Logical> = Point::from((self.width as i32, 0));
-        let button_offset: Point<i32, Logical> = Point::from((BUTTON_WIDTH as i32, 0));
-
-        vec![
-            SolidColorRenderElement::from_buffer(
-                &self.close_button,
-                location + (header_end_offset - button_offset).to_physical_precise_round(scale),
-                scale,
-                alpha,
-                Kind::Unspecified,
-            )
-            .into(),
-            SolidColorRenderElement::from_buffer(
-                &self.maximize_button,
-                location + (header_end_offset - button_offset.upscale(2)).to_physical_precise_round(scale),
-                scale,
-                alpha,
-                Kind::Unspecified,
-            )
-            .into(),
+        // buttons and tabs first, so they are drawn on top of the background
+        let row = self.active_row();
+        let buttons = self.buttons.iter().enumerate().map(|(index, button)| {
+            let offset =
+                Point::<i32, Logical>::from((self.theme.button_offset(index, self.width as i32), row));
+            (offset, button)
+        });
+        let tabs = self.inactive_tabs.iter().map(|(offset, tab)| (*offset, tab));
+        let mut elements = buttons
+            .chain(tabs)
+            .map(|(offset, buffer)| {
+                SolidColorRenderElement::from_buffer(
+                    buffer,
+                    location + offset.to_physical_precise_round(scale),
+                    scale,
+                    alpha,
+                    Kind::Unspecified,
+                )
+                .into()
+            })
+            .collect::<Vec<_>>();
+        elements.push(
             SolidColorRenderElement::from_buffer(&self.background, location, scale, alpha, Kind::Unspecified)
                 .into(),
-        ]
+        );
+        elements
     }
 }
 
@@ -271,15 +399,7 @@ impl WindowElement {
         self.user_data().insert_if_missing(|| {
             RefCell::new(WindowState {
                 is_ssd: false,
-                header_bar: HeaderBar {
-                    pointer_loc: None,
-                    width: 0,
-                    close_button_hover: false,
-                    maximize_button_hover: false,
-                    background: SolidColorBuffer::default(),
-                    close_button: SolidColorBuffer::default(),
-                    maximize_button: SolidColorBuffer::default(),
-                },
+                header_bar: HeaderBar::new(DecorationTheme::default()),
             })
         });
 
@@ -292,4 +412,21 @@ impl WindowElement {
     pub fn set_ssd(&self, ssd: bool) {
         self.decoration_state().is_ssd = ssd;
     }
+
+    pub fn set_decoration_theme(&self, theme: &DecorationTheme) {
+        self.decoration_state().header_bar.set_theme(theme);
+    }
+
+    pub fn set_tabs(&self, tabs: Option<Tabs>) {
+        self.decoration_state().header_bar.tabs = tabs;
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Hand the configured decoration theme to every window
+    pub fn update_decoration_themes(&mut self) {
+        for window in self.space.elements() {
+            window.set_decoration_theme(&self.config.decorations);
+        }
+    }
 }
@@ -0,0 +1,115 @@
+use std::cell::Cell;
+
+use smithay::desktop::{space::RenderZindex, WindowSurface};
+use tracing::debug;
+
+use crate::{
+    config::Stacking,
+    core::state::{Backend, WayiceState},
+};
+
+use super::WindowElement;
+
+/// Stacking and stickiness toggled by the user, overriding the rules
+#[derive(Debug, Default)]
+struct StackingToggle {
+    stacking: Cell<Option<Stacking>>,
+    sticky: Cell<Option<bool>>,
+}
+
+impl WindowElement {
+    fn stacking_toggle(&self) -> &StackingToggle {
+        self.user_data().insert_if_missing(StackingToggle::default);
+        self.user_data().get::<StackingToggle>().unwrap()
+    }
+
+    /// Where the window is stacked, unless toggled as a `stacking` rule says
+    pub fn stacking(&self) -> Stacking {
+        self.user_data()
+            .get::<StackingToggle>()
+            .and_then(|t| t.stacking.get())
+            .or(self.rule_effects().stacking)
+            .unwrap_or_default()
+    }
+
+    /// Whether the window stays shown when its output switches workspaces
+    pub fn is_sticky(&self) -> bool {
+        self.user_data()
+            .get::<StackingToggle>()
+            .and_then(|t| t.sticky.get())
+            .or(self.rule_effects().sticky)
+            .unwrap_or(false)
+    }
+
+    /// Z-index the space sorts the window by, one above or below the other
+    /// windows if it is always on top or always below
+    pub(super) fn stacking_z_index(&self, z_index: u8) -> u8 {
+        // leave windows outside the shell layer, like override-redirect ones, alone
+        if z_index != RenderZindex::Shell as u8 {
+            return z_index;
+        }
+        match self.stacking() {
+            Stacking::Above => z_index + 1,
+            Stacking::Normal => z_index,
+            Stacking::Below => z_index - 1,
+        }
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Stack `window` as `stacking` says from now on
+    pub fn set_stacking(&mut self, window: &WindowElement, stacking: Stacking) {
+        debug!(?stacking, "Changing window stacking");
+        window.stacking_toggle().stacking.set(Some(stacking));
+        // the space only sorts windows by z-index as they are raised
+        if self.space.element_location(window).is_some() {
+            self.space.raise_element(window, false);
+        }
+    }
+
+    /// Keep the focused window above the others, or stack it normally again
+    /// if it already is
+    pub fn toggle_always_on_top(&mut self) {
+        self.toggle_stacking(Stacking::Above);
+    }
+
+    /// Keep the focused window below the others, or stack it normally again
+    /// if it already is
+    pub fn toggle_always_below(&mut self) {
+        self.toggle_stacking(Stacking::Below);
+    }
+
+    fn toggle_stacking(&mut self, stacking: Stacking) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let stacking = if window.stacking() == stacking {
+            Stacking::Normal
+        } else {
+            stacking
+        };
+        self.set_stacking(&window, stacking);
+    }
+
+    /// Show the focused window on every workspace of its output, or only on
+    /// the shown one again.
+    ///
+    /// Sticky windows float, as the tiles change with the workspace.
+    pub fn toggle_sticky(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let sticky = !window.is_sticky();
+        debug!(sticky, "Toggling sticky");
+        window.stacking_toggle().sticky.set(Some(sticky));
+        if sticky && !window.is_floating() {
+            window.set_floating(true);
+            if let WindowSurface::Wayland(toplevel) = window.0.underlying_surface() {
+                // let the client pick its size again
+                toplevel.with_pending_state(|state| state.size = None);
+            }
+            self.arrange_all();
+            self.space.raise_element(&window, true);
+        }
+    }
+}
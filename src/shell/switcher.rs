@@ -0,0 +1,188 @@
+use smithay::{
+    backend::renderer::element::{
+        solid::{SolidColorBuffer, SolidColorRenderElement},
+        Kind,
+    },
+    desktop::Window,
+    output::Output,
+    utils::{IsAlive, Logical, Rectangle, Scale, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::Modifiers,
+    core::{
+        render::{output_logical_size, preview_cell, PREVIEW_PADDING},
+        state::{Backend, WayiceState},
+    },
+    helpers::window_utils::window_properties,
+};
+
+use super::{tiling::inset, WindowElement};
+
+/// Color of the frame behind the selected preview, with premultiplied alpha
+pub(super) const HIGHLIGHT_COLOR: [f32; 4] = [0.1, 0.25, 0.45, 0.5];
+
+/// The Alt-Tab overlay, showing previews of the windows in most recently
+/// used order while the modifiers of its binding are held
+#[derive(Debug)]
+pub struct WindowSwitcher {
+    output: Output,
+    windows: Vec<WindowElement>,
+    selected: usize,
+    /// Modifiers held when the switcher opened, it closes once none of them
+    /// are held anymore
+    held: Modifiers,
+    highlight: SolidColorBuffer,
+    /// Area of the frame behind the selected preview, relative to the output
+    highlight_area: Rectangle<i32, Logical>,
+}
+
+impl WindowSwitcher {
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// The windows to choose from, in the order their previews are shown
+    pub fn windows(&self) -> &[WindowElement] {
+        &self.windows
+    }
+
+    /// Whether the switcher stays open while `modifiers` are held
+    pub fn holds(&self, modifiers: Modifiers) -> bool {
+        modifiers.intersects(self.held)
+    }
+
+    pub fn highlight_element(&self, scale: Scale<f64>) -> SolidColorRenderElement {
+        SolidColorRenderElement::from_buffer(
+            &self.highlight,
+            self.highlight_area.loc.to_physical_precise_round(scale),
+            scale,
+            1.0,
+            Kind::Unspecified,
+        )
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        let cell = preview_cell(index, self.windows.len(), output_logical_size(&self.output));
+        self.highlight_area = inset(cell, -PREVIEW_PADDING / 2);
+        self.highlight.update(self.highlight_area.size, HIGHLIGHT_COLOR);
+    }
+}
+
+/// What windows of the same app have in common, the `app_id` of Wayland
+/// windows and the class of X11 windows
+pub(super) fn app_of(window: &WindowElement) -> Option<String> {
+    let properties = window_properties(window);
+    properties.app_id.or(properties.class)
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Move `window` to the front of the focus history
+    pub fn record_focus(&mut self, window: &Window) {
+        let Some(window) = self.space.elements().find(|e| e.0 == *window).cloned() else {
+            return;
+        };
+        self.focus_history.retain(|w| w.alive() && *w != window);
+        self.focus_history.insert(0, window);
+    }
+
+    /// The windows of the space and the minimized ones, most recently focused
+    /// first and those never focused last
+    fn windows_by_recent_use(&self) -> Vec<WindowElement> {
+        let mut windows: Vec<WindowElement> = self
+            .focus_history
+            .iter()
+            .filter(|w| self.space.element_location(w).is_some() || self.is_minimized(w))
+            .cloned()
+            .collect();
+        let unfocused: Vec<WindowElement> = self
+            .space
+            .elements()
+            .rev()
+            .chain(self.minimized.iter().rev().map(|m| &m.window))
+            .filter(|w| !windows.contains(w))
+            .cloned()
+            .collect();
+        windows.extend(unfocused);
+        windows
+    }
+
+    /// Open the window switcher on the current output, or select the next
+    /// window in it, the previous one while Shift is held.
+    ///
+    /// With `same_app` only the windows of the app of the focused window are
+    /// gone through.
+    pub fn switch_window(&mut self, same_app: bool) {
+        let modifiers = Modifiers::from(&self.seat.get_keyboard().unwrap().modifier_state());
+        let step = if modifiers.contains(Modifiers::SHIFT) {
+            -1
+        } else {
+            1
+        };
+        if let Some(switcher) = self.switcher.as_mut() {
+            let count = switcher.windows.len() as i32;
+            switcher.select((switcher.selected as i32 + step).rem_euclid(count) as usize);
+            return;
+        }
+
+        let mut windows = self.windows_by_recent_use();
+        if same_app {
+            let app = self.focused_window().as_ref().and_then(app_of);
+            windows.retain(|w| app_of(w) == app);
+        }
+        let Some(output) = self.current_output() else {
+            return;
+        };
+        if windows.len() < 2 {
+            return;
+        }
+        let selected = if step > 0 { 1 } else { windows.len() - 1 };
+        let held = modifiers.difference(Modifiers::SHIFT);
+        if held.is_empty() {
+            // nothing to release, so switch right away
+            self.switch_to(&windows[selected]);
+            return;
+        }
+
+        debug!(windows = windows.len(), same_app, "Opening window switcher");
+        let mut switcher = WindowSwitcher {
+            output,
+            windows,
+            selected,
+            held,
+            highlight: SolidColorBuffer::default(),
+            highlight_area: Rectangle::default(),
+        };
+        switcher.select(selected);
+        self.switcher = Some(switcher);
+    }
+
+    /// Close the window switcher, focusing the selected window
+    pub fn finish_switch(&mut self) {
+        let Some(switcher) = self.switcher.take() else {
+            return;
+        };
+        let window = switcher.windows[switcher.selected].clone();
+        if window.alive() {
+            self.switch_to(&window);
+        }
+    }
+
+    /// Focus `window`, restoring it first if it is minimized
+    fn switch_to(&mut self, window: &WindowElement) {
+        if self.is_minimized(window) {
+            self.restore_window(window);
+        } else if self.space.element_location(window).is_some() {
+            self.focus_window(window, SERIAL_COUNTER.next_serial());
+        }
+    }
+
+    /// Close the window switcher, leaving the focus alone
+    pub fn cancel_switch(&mut self) {
+        if self.switcher.take().is_some() {
+            debug!("Window switcher cancelled");
+        }
+    }
+}
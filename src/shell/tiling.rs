@@ -0,0 +1,392 @@
+use std::cell::Cell;
+
+use smithay::{
+    desktop::WindowSurface,
+    output::Output,
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{IsAlive, Logical, Point, Rectangle, Size},
+    wayland::{compositor::with_states, shell::xdg::SurfaceCachedState},
+};
+use tracing::debug;
+
+use crate::{
+    config::LayoutKind,
+    core::state::{Backend, WayiceState},
+    helpers::output_utils::output_config,
+};
+
+use super::{fixup_positions, layout::layout_for, WindowElement};
+
+/// States telling a client its window is tiled on every side
+const TILED_STATES: [xdg_toplevel::State; 4] = [
+    xdg_toplevel::State::TiledLeft,
+    xdg_toplevel::State::TiledRight,
+    xdg_toplevel::State::TiledTop,
+    xdg_toplevel::State::TiledBottom,
+];
+
+/// Floating state chosen with `toggle_floating`, stored in the user data of a window
+#[derive(Debug, Default)]
+struct FloatingToggle(Cell<Option<bool>>);
+
+impl WindowElement {
+    /// Whether the window stays out of tiling layouts.
+    ///
+    /// Unless toggled, windows matched by a `floating` rule and dialogs float.
+    pub fn is_floating(&self) -> bool {
+        if let Some(floating) = self.user_data().get::<FloatingToggle>().and_then(|t| t.0.get()) {
+            return floating;
+        }
+        self.rule_effects().floating.unwrap_or_else(|| self.is_dialog())
+    }
+
+    pub(super) fn set_floating(&self, floating: bool) {
+        self.user_data().insert_if_missing(FloatingToggle::default);
+        self.user_data()
+            .get::<FloatingToggle>()
+            .unwrap()
+            .0
+            .set(Some(floating));
+    }
+
+    /// Whether the window has a parent or a fixed size, as most dialogs do
+    fn is_dialog(&self) -> bool {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.parent().is_some()
+                    || with_states(toplevel.wl_surface(), |states| {
+                        let mut guard = states.cached_state.get::<SurfaceCachedState>();
+                        let data = guard.current();
+                        data.min_size.w > 0 && data.min_size.h > 0 && data.min_size == data.max_size
+                    })
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                surface.is_transient_for().is_some()
+                    || surface
+                        .min_size()
+                        .is_some_and(|min| Some(min) == surface.max_size())
+            }
+        }
+    }
+
+    /// Whether the window can be part of a tiling layout at all
+    pub(super) fn is_tileable(&self) -> bool {
+        if !self.alive() || self.is_floating() {
+            return false;
+        }
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => !toplevel.with_pending_state(|state| {
+                state.states.contains(xdg_toplevel::State::Maximized)
+                    || state.states.contains(xdg_toplevel::State::Fullscreen)
+            }),
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                !surface.is_override_redirect() && !surface.is_maximized() && !surface.is_fullscreen()
+            }
+        }
+    }
+
+    /// Tell a Wayland client whether its window is tiled, so it can drop
+    /// shadows and rounded corners
+    pub(super) fn set_tiled(&self, tiled: bool) {
+        if let WindowSurface::Wayland(toplevel) = self.0.underlying_surface() {
+            toplevel.with_pending_state(|state| {
+                for tiled_state in TILED_STATES {
+                    if tiled {
+                        state.states.set(tiled_state);
+                    } else {
+                        state.states.unset(tiled_state);
+                    }
+                }
+            });
+            if toplevel.is_initial_configure_sent() {
+                toplevel.send_pending_configure();
+            }
+        }
+    }
+}
+
+/// `rect` with `by` taken off every side
+pub(super) fn inset(rect: Rectangle<i32, Logical>, by: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size(
+        (rect.loc.x + by, rect.loc.y + by),
+        ((rect.size.w - 2 * by).max(1), (rect.size.h - 2 * by).max(1)),
+    )
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Layout of the workspace called `name`: the one chosen with
+    /// `cycle_layout`, else the one of its `[workspace.<name>]` section, of
+    /// the section of its output, or of `[tiling]`
+    pub fn workspace_layout(&self, name: &str) -> LayoutKind {
+        let workspace = self.workspaces.get(name);
+        workspace
+            .and_then(|w| w.layout)
+            .or_else(|| self.config.workspaces.layout_for(name))
+            .or_else(|| {
+                let output = self.output_named(&workspace?.output)?;
+                output_config(&self.config, &output)?.layout
+            })
+            .unwrap_or(self.config.tiling.layout)
+    }
+
+    /// Layout of the workspace `output` shows
+    pub(super) fn output_layout(&mut self, output: &Output) -> LayoutKind {
+        let name = self.active_workspace(output);
+        self.workspace_layout(&name)
+    }
+
+    /// Lay out outputs and windows again after outputs were added, removed
+    /// or resized, placing windows left outside of every output on the one
+    /// under `pointer_location`
+    pub fn fixup_layout(&mut self, pointer_location: Point<f64, Logical>) {
+        let orphaned = fixup_positions(&mut self.space);
+        let output = self
+            .space
+            .output_under(pointer_location)
+            .next()
+            .or_else(|| self.space.outputs().next())
+            .cloned();
+        for window in orphaned {
+            self.place_window_on_output(&window, output.as_ref(), false);
+        }
+        self.arrange_all();
+    }
+
+    /// Whether the window is arranged by a tiling layout
+    pub fn is_tiled(&self, window: &WindowElement) -> bool {
+        self.workspaces
+            .iter()
+            .any(|w| w.tiles.contains(window) || w.strip.windows().any(|s| s == window))
+    }
+
+    /// Arrange the tiles of every output, opening a workspace on those that
+    /// show none yet
+    pub fn arrange_all(&mut self) {
+        for output in self.space.outputs().cloned().collect::<Vec<_>>() {
+            self.active_workspace(&output);
+            self.arrange_output(&output);
+        }
+    }
+
+    /// Arrange the tiled windows of the workspace `output` shows, in the part
+    /// of the output not reserved by layer surfaces
+    pub fn arrange_output(&mut self, output: &Output) {
+        let Some(name) = self.workspaces.active_on(&output.name()).map(str::to_string) else {
+            return;
+        };
+        let layout_kind = self.workspace_layout(&name);
+        if layout_kind == LayoutKind::Scrolling {
+            self.arrange_strip(output, &name);
+            return;
+        }
+        self.release_strip(output, &name);
+        let layout = layout_for(layout_kind);
+
+        let windows = self.windows_on_output(output);
+        let tileable: Vec<WindowElement> = match layout {
+            Some(_) => windows.iter().filter(|w| w.is_tileable()).cloned().collect(),
+            None => Vec::new(),
+        };
+        let Some(workspace) = self.workspaces.get_mut(&name) else {
+            return;
+        };
+        let mut removed = Vec::new();
+        workspace.tiles.retain(|w| {
+            let keep = tileable.contains(w);
+            if !keep {
+                removed.push(w.clone());
+            }
+            keep
+        });
+        for window in &tileable {
+            if !workspace.tiles.contains(window) {
+                workspace.tiles.push(window.clone());
+            }
+        }
+        let tiles = workspace.tiles.clone();
+        let master_ratio = workspace.master_ratio.unwrap_or(self.config.tiling.master_ratio);
+
+        for window in removed.iter().filter(|w| w.alive()) {
+            window.set_tiled(false);
+        }
+        let (Some(layout), Some(area)) = (layout, self.tiling_area(output)) else {
+            return;
+        };
+
+        let half_gap = self.config.tiling.gaps / 2;
+        let rects = layout.arrange(area, tiles.len(), master_ratio);
+        for (window, rect) in tiles.iter().zip(rects) {
+            self.place_tile(window, inset(rect, half_gap));
+        }
+
+        if layout_kind == LayoutKind::Monocle {
+            if let Some(focused) = self.focused_window().filter(|w| tiles.contains(w)) {
+                self.space.raise_element(&focused, false);
+            }
+        }
+        self.raise_floating(&windows, &tiles);
+    }
+
+    /// Raise the `windows` of an output that are not `tiles`, so floating and
+    /// fullscreen windows stay above the tiles
+    pub(super) fn raise_floating(&mut self, windows: &[WindowElement], tiles: &[WindowElement]) {
+        for window in windows.iter().filter(|w| !tiles.contains(w)) {
+            self.space.raise_element(window, false);
+        }
+    }
+
+    /// Area of `output` the tiles are laid out in, without the outer gaps.
+    ///
+    /// Half of the inner gap is added back, as every tile is shrunk by that
+    /// much on each side.
+    pub(super) fn tiling_area(&self, output: &Output) -> Option<Rectangle<i32, Logical>> {
+        let area = self.usable_area(output)?;
+        Some(inset(
+            area,
+            self.config.tiling.outer_gaps - self.config.tiling.gaps / 2,
+        ))
+    }
+
+    /// Move `window` to `rect` and have the client resize it to fit
+    pub(super) fn place_tile(&mut self, window: &WindowElement, rect: Rectangle<i32, Logical>) {
+        if self.dragged_window.as_ref() == Some(window) {
+            return;
+        }
+        // the client only draws below the server side title bar
+        let header_height = window.decoration_state().header_height();
+        let size = Size::from((rect.size.w, (rect.size.h - header_height).max(1)));
+
+        // mapping raises the window, so only do it when it has to move
+        if self.space.element_location(window) != Some(rect.loc) {
+            self.space.map_element(window.clone(), rect.loc, false);
+        }
+
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| state.size = Some(size));
+                window.set_tiled(true);
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                let geometry = Rectangle::from_loc_and_size(rect.loc, size);
+                if surface.geometry() != geometry {
+                    let _ = surface.configure(geometry);
+                }
+            }
+        }
+    }
+
+    /// Output tiling actions apply to, the one of the focused window
+    pub(super) fn tiling_output(&self) -> Option<Output> {
+        self.focused_window()
+            .and_then(|w| self.window_output(&w))
+            .or_else(|| self.current_output())
+    }
+
+    /// Take the focused window out of the tiling layout, or put it back
+    pub fn toggle_floating(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let floating = !window.is_floating();
+        debug!(floating, "Toggling floating");
+        window.set_floating(floating);
+        if floating {
+            if let WindowSurface::Wayland(toplevel) = window.0.underlying_surface() {
+                // let the client pick its size again
+                toplevel.with_pending_state(|state| state.size = None);
+            }
+        }
+        self.arrange_all();
+        if floating {
+            self.space.raise_element(&window, true);
+        }
+    }
+
+    /// Switch the workspace of the current output to the next layout
+    pub fn cycle_layout(&mut self) {
+        let Some(output) = self.tiling_output() else {
+            return;
+        };
+        let name = self.active_workspace(&output);
+        let current = self.workspace_layout(&name);
+        let index = LayoutKind::ALL
+            .iter()
+            .position(|l| *l == current)
+            .unwrap_or_default();
+        let layout = LayoutKind::ALL[(index + 1) % LayoutKind::ALL.len()];
+        debug!(workspace = name, layout = layout.name(), "Changing layout");
+
+        if let Some(workspace) = self.workspaces.get_mut(&name) {
+            workspace.layout = Some(layout);
+        }
+        self.arrange_output(&output);
+        self.ipc_shm_update_workspaces();
+    }
+
+    /// Swap the focused tile with the one `offset` places after it,
+    /// wrapping around
+    pub fn swap_tile(&mut self, offset: i32) {
+        self.reorder_tiles(|tiles, index| {
+            let other = (index as i32 + offset).rem_euclid(tiles.len() as i32) as usize;
+            tiles.swap(index, other);
+        });
+    }
+
+    /// Make the focused tile the master tile, or if it already is swap it
+    /// with the next one
+    pub fn promote_tile(&mut self) {
+        self.reorder_tiles(|tiles, index| {
+            if index == 0 {
+                if tiles.len() > 1 {
+                    tiles.swap(0, 1);
+                }
+            } else {
+                let window = tiles.remove(index);
+                tiles.insert(0, window);
+            }
+        });
+    }
+
+    /// Change the order of the tiles on the output of the focused window,
+    /// `reorder` getting the index of the focused tile
+    fn reorder_tiles(&mut self, reorder: impl FnOnce(&mut Vec<WindowElement>, usize)) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let Some(output) = self.window_output(&window) else {
+            return;
+        };
+        let name = self.active_workspace(&output);
+        let Some(workspace) = self.workspaces.get_mut(&name) else {
+            return;
+        };
+        let Some(index) = workspace.tiles.iter().position(|w| *w == window) else {
+            return;
+        };
+        reorder(&mut workspace.tiles, index);
+        self.arrange_output(&output);
+    }
+
+    /// Change the share of the master tile on the current output by `delta`,
+    /// or the width of the focused column in the scrolling layout
+    pub fn resize_master(&mut self, delta: f64) {
+        let Some(output) = self.tiling_output() else {
+            return;
+        };
+        let name = self.active_workspace(&output);
+        if self.workspace_layout(&name) == LayoutKind::Scrolling {
+            self.resize_column(delta);
+            return;
+        }
+        let default = self.config.tiling.master_ratio;
+        let Some(workspace) = self.workspaces.get_mut(&name) else {
+            return;
+        };
+        let ratio = (workspace.master_ratio.unwrap_or(default) + delta).clamp(0.1, 0.9);
+        workspace.master_ratio = Some(ratio);
+        self.arrange_output(&output);
+    }
+}
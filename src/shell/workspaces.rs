@@ -0,0 +1,545 @@
+use smithay::{
+    desktop::space::SpaceElement,
+    input::pointer::MotionEvent,
+    output::Output,
+    utils::{IsAlive, Logical, Point, SERIAL_COUNTER},
+};
+use tracing::debug;
+
+use crate::{
+    config::LayoutKind,
+    core::state::{Backend, WayiceState},
+};
+
+use super::{scrolling::Strip, FullscreenSurface, WindowElement};
+
+/// A window of a hidden workspace, kept out of the space so it is neither
+/// drawn nor sent frame callbacks
+#[derive(Debug)]
+struct HiddenWindow {
+    window: WindowElement,
+    /// Location relative to the output the workspace is on
+    location: Point<i32, Logical>,
+    fullscreen: bool,
+}
+
+/// A named set of windows on one output
+#[derive(Debug)]
+pub struct Workspace {
+    pub name: String,
+    /// Name of the output the workspace is on
+    pub output: String,
+    hidden: Vec<HiddenWindow>,
+    /// Tiled windows in layout order, the master tile first
+    pub(super) tiles: Vec<WindowElement>,
+    /// Layout chosen with `cycle_layout`, overriding the configured one
+    pub(super) layout: Option<LayoutKind>,
+    /// Master ratio changed with `grow_master` and `shrink_master`
+    pub(super) master_ratio: Option<f64>,
+    /// Columns of the scrolling layout
+    pub(super) strip: Strip,
+}
+
+impl Workspace {
+    /// Number of windows on the workspace while it is hidden
+    pub fn hidden_windows(&self) -> usize {
+        self.hidden.iter().filter(|h| h.window.alive()).count()
+    }
+}
+
+/// Every workspace, and the one each output shows.
+///
+/// The windows of shown workspaces live in the space like any other window,
+/// only those of hidden workspaces are kept here.
+#[derive(Debug, Default)]
+pub struct Workspaces {
+    workspaces: Vec<Workspace>,
+    /// Output names and the workspace each of them shows
+    active: Vec<(String, String)>,
+}
+
+impl Workspaces {
+    pub fn iter(&self) -> impl Iterator<Item = &Workspace> {
+        self.workspaces.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+
+    pub(super) fn get_mut(&mut self, name: &str) -> Option<&mut Workspace> {
+        self.workspaces.iter_mut().find(|w| w.name == name)
+    }
+
+    /// Name of the workspace `output` shows
+    pub fn active_on(&self, output: &str) -> Option<&str> {
+        self.active
+            .iter()
+            .find(|(o, _)| o == output)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Name of the output showing the workspace, if it is shown
+    pub fn shown_on(&self, name: &str) -> Option<&str> {
+        self.active
+            .iter()
+            .find(|(_, n)| n == name)
+            .map(|(output, _)| output.as_str())
+    }
+
+    fn set_active(&mut self, output: &str, name: &str) {
+        self.active.retain(|(o, n)| o != output && n != name);
+        self.active.push((output.to_string(), name.to_string()));
+    }
+
+    /// Create the workspace on `output` unless it exists, keeping the list
+    /// in the order of the configured `names`
+    fn ensure(&mut self, name: &str, output: &str, names: &[String]) {
+        if self.get(name).is_some() {
+            return;
+        }
+        self.workspaces.push(Workspace {
+            name: name.to_string(),
+            output: output.to_string(),
+            hidden: Vec::new(),
+            tiles: Vec::new(),
+            layout: None,
+            master_ratio: None,
+            strip: Strip::default(),
+        });
+        let order = |name: &str| names.iter().position(|n| n == name).unwrap_or(usize::MAX);
+        self.workspaces
+            .sort_by(|a, b| (order(&a.name), &a.name).cmp(&(order(&b.name), &b.name)));
+    }
+
+    /// The windows of every hidden workspace
+    pub fn hidden(&self) -> impl Iterator<Item = &WindowElement> {
+        self.workspaces
+            .iter()
+            .flat_map(|w| w.hidden.iter().map(|h| &h.window))
+    }
+
+    /// Whether the window is on a hidden workspace
+    pub fn is_hidden(&self, window: &WindowElement) -> bool {
+        self.workspaces
+            .iter()
+            .any(|w| w.hidden.iter().any(|h| h.window == *window))
+    }
+
+    /// Trade the places of two windows in the tiles, columns and hidden
+    /// windows of every workspace
+    pub fn swap_windows(&mut self, a: &WindowElement, b: &WindowElement) {
+        for workspace in &mut self.workspaces {
+            for hidden in &mut workspace.hidden {
+                if hidden.window == *a {
+                    hidden.window = b.clone();
+                } else if hidden.window == *b {
+                    hidden.window = a.clone();
+                }
+            }
+            for tile in &mut workspace.tiles {
+                if tile == a {
+                    *tile = b.clone();
+                } else if tile == b {
+                    *tile = a.clone();
+                }
+            }
+            workspace.strip.swap(a, b);
+        }
+    }
+
+    /// Stop tracking the windows `matches` picks, as they were unmapped
+    pub fn forget_windows(&mut self, matches: impl Fn(&WindowElement) -> bool) {
+        for workspace in &mut self.workspaces {
+            workspace.hidden.retain(|h| !matches(&h.window));
+            workspace.tiles.retain(|w| !matches(w));
+            workspace.strip.retain(|w| !matches(w));
+        }
+    }
+}
+
+impl<BackendData: Backend> WayiceState<BackendData> {
+    /// Output the pointer is on, which workspace actions apply to
+    pub fn current_output(&self) -> Option<Output> {
+        self.space
+            .output_under(self.pointer.current_location())
+            .next()
+            .or_else(|| self.space.outputs().next())
+            .cloned()
+    }
+
+    /// Output a window belongs to, the one containing its center
+    pub fn window_output(&self, window: &WindowElement) -> Option<Output> {
+        let geometry = self.space.element_geometry(window)?;
+        let center = geometry.loc + geometry.size.downscale(2).to_point();
+        self.space
+            .outputs()
+            .find(|o| {
+                self.space
+                    .output_geometry(o)
+                    .is_some_and(|geo| geo.contains(center))
+            })
+            .or_else(|| self.space.outputs_for_element(window).first())
+            .cloned()
+    }
+
+    /// Windows on `output`, from bottom to top
+    pub(super) fn windows_on_output(&self, output: &Output) -> Vec<WindowElement> {
+        self.space
+            .elements()
+            .filter(|w| self.window_output(w).as_ref() == Some(output))
+            .cloned()
+            .collect()
+    }
+
+    fn output_origin(&self, output: &Output) -> Point<i32, Logical> {
+        self.space
+            .output_geometry(output)
+            .map(|geo| geo.loc)
+            .unwrap_or_default()
+    }
+
+    pub(super) fn output_named(&self, name: &str) -> Option<Output> {
+        self.space.outputs().find(|o| o.name() == name).cloned()
+    }
+
+    /// Name of the workspace `output` shows, opening one if it shows none yet
+    pub fn active_workspace(&mut self, output: &Output) -> String {
+        if let Some(name) = self.workspaces.active_on(&output.name()) {
+            return name.to_string();
+        }
+
+        let name = self.unused_workspace(output);
+        self.ensure_workspace(&name, output);
+        self.workspaces.set_active(&output.name(), &name);
+        name
+    }
+
+    /// A workspace `output` can show: a hidden one already on it, else the
+    /// first free name that is not meant for another output
+    fn unused_workspace(&self, output: &Output) -> String {
+        let output_name = output.name();
+        if let Some(workspace) = self
+            .workspaces
+            .iter()
+            .find(|w| w.output == output_name && self.workspaces.shown_on(&w.name).is_none())
+        {
+            return workspace.name.clone();
+        }
+
+        let config = &self.config.workspaces;
+        (0..)
+            .map(|index| config.name_for(index))
+            .find(|name| {
+                self.workspaces.get(name).is_none()
+                    && config.output_for(name).map_or(true, |o| o == output_name)
+            })
+            .unwrap()
+    }
+
+    /// Create the workspace on `output` unless it exists
+    fn ensure_workspace(&mut self, name: &str, output: &Output) {
+        self.workspaces
+            .ensure(name, &output.name(), &self.config.workspaces.names);
+    }
+
+    /// Unmap the windows shown on `output` but the sticky ones, storing them
+    /// in the workspace `name`
+    fn hide_workspace(&mut self, output: &Output, name: &str) {
+        let origin = self.output_origin(output);
+        let fullscreen = output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .filter(|f| f.get().is_some_and(|w| !w.is_sticky()))
+            .and_then(|f| f.clear());
+
+        let mut hidden = Vec::new();
+        for window in self.windows_on_output(output) {
+            if window.is_sticky() {
+                continue;
+            }
+            let location = self.space.element_location(&window).unwrap_or_default() - origin;
+            self.space.unmap_elem(&window);
+            hidden.push(HiddenWindow {
+                fullscreen: fullscreen.as_ref() == Some(&window),
+                window,
+                location,
+            });
+        }
+        debug!(workspace = name, windows = hidden.len(), "Hiding workspace");
+
+        self.workspaces.active.retain(|(o, _)| *o != output.name());
+        let declared = self.config.workspaces.is_declared(name);
+        let Some(workspace) = self.workspaces.get_mut(name) else {
+            return;
+        };
+        workspace.hidden.extend(hidden);
+        workspace.hidden.retain(|h| h.window.alive());
+        workspace.tiles.retain(|w| w.alive());
+        if workspace.hidden.is_empty()
+            && workspace.tiles.is_empty()
+            && workspace.strip.is_empty()
+            && !declared
+        {
+            self.workspaces.workspaces.retain(|w| w.name != name);
+        }
+    }
+
+    /// Map the windows of the workspace `name` on `output` again
+    fn show_workspace(&mut self, output: &Output, name: &str) {
+        let origin = self.output_origin(output);
+        let Some(workspace) = self.workspaces.get_mut(name) else {
+            return;
+        };
+        workspace.output = output.name();
+        let hidden = std::mem::take(&mut workspace.hidden);
+        debug!(workspace = name, windows = hidden.len(), "Showing workspace");
+
+        for hidden in hidden.into_iter().filter(|h| h.window.alive()) {
+            self.space
+                .map_element(hidden.window.clone(), origin + hidden.location, false);
+            if hidden.fullscreen {
+                output.user_data().insert_if_missing(FullscreenSurface::default);
+                output
+                    .user_data()
+                    .get::<FullscreenSurface>()
+                    .unwrap()
+                    .set(hidden.window);
+            }
+        }
+        self.workspaces.set_active(&output.name(), name);
+    }
+
+    /// Give keyboard focus to the topmost window of `output`
+    pub(super) fn focus_output(&mut self, output: &Output) {
+        let serial = SERIAL_COUNTER.next_serial();
+        match self.windows_on_output(output).pop() {
+            Some(window) => self.focus_window(&window, serial),
+            None => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.set_focus(self, None, serial);
+            }
+        }
+    }
+
+    /// Move the pointer to the center of `output`
+    pub(super) fn warp_pointer_to(&mut self, output: &Output) {
+        let Some(geometry) = self.space.output_geometry(output) else {
+            return;
+        };
+        let location = (geometry.loc + geometry.size.downscale(2).to_point()).to_f64();
+        let under = self.surface_under(location);
+        let pointer = self.pointer.clone();
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis(),
+            },
+        );
+        pointer.frame(self);
+    }
+
+    /// Show the workspace called `name`.
+    ///
+    /// Existing workspaces are shown on their output, new ones are opened on
+    /// their configured output or the current one.
+    pub fn switch_workspace(&mut self, name: &str) {
+        let Some(current) = self.current_output() else {
+            return;
+        };
+        let output = self
+            .workspaces
+            .get(name)
+            .map(|w| w.output.clone())
+            .or_else(|| self.config.workspaces.output_for(name).map(String::from))
+            .and_then(|o| self.output_named(&o))
+            .unwrap_or_else(|| current.clone());
+        self.ensure_workspace(name, &output);
+
+        let previous = self.active_workspace(&output);
+        if previous != name {
+            self.hide_workspace(&output, &previous);
+            self.show_workspace(&output, name);
+        }
+        if output != current {
+            self.warp_pointer_to(&output);
+        }
+        self.arrange_all();
+        self.focus_output(&output);
+        self.ipc_shm_update_workspaces();
+    }
+
+    /// Show the workspace at `index` of the configured names
+    pub fn switch_workspace_index(&mut self, index: usize) {
+        let name = self.config.workspaces.name_for(index);
+        self.switch_workspace(&name);
+    }
+
+    /// Show the next or previous workspace of the current output
+    pub fn cycle_workspace(&mut self, forward: bool) {
+        let Some(output) = self.current_output() else {
+            return;
+        };
+        let active = self.active_workspace(&output);
+        let names: Vec<String> = self
+            .workspaces
+            .iter()
+            .filter(|w| w.output == output.name())
+            .map(|w| w.name.clone())
+            .collect();
+        let Some(index) = names.iter().position(|n| *n == active) else {
+            return;
+        };
+        let next = if forward {
+            (index + 1) % names.len()
+        } else {
+            (index + names.len() - 1) % names.len()
+        };
+        self.switch_workspace(&names[next]);
+    }
+
+    /// Move the focused window to the workspace called `name`, without
+    /// switching to it
+    pub fn move_window_to_workspace(&mut self, name: &str) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let Some(output) = self.window_output(&window) else {
+            return;
+        };
+        if self.active_workspace(&output) == name {
+            return;
+        }
+
+        let fullscreen = output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|f| f.get())
+            .is_some_and(|w| w == window);
+        if fullscreen {
+            output.user_data().get::<FullscreenSurface>().unwrap().clear();
+        }
+        let location = self.space.element_location(&window).unwrap_or_default() - self.output_origin(&output);
+
+        let target = self.workspaces.shown_on(name).and_then(|o| self.output_named(o));
+        match target {
+            Some(target) => {
+                let origin = self.output_origin(&target);
+                self.space.map_element(window.clone(), origin + location, true);
+                if fullscreen {
+                    target.user_data().insert_if_missing(FullscreenSurface::default);
+                    target.user_data().get::<FullscreenSurface>().unwrap().set(window);
+                }
+            }
+            None => {
+                let target = self
+                    .config
+                    .workspaces
+                    .output_for(name)
+                    .and_then(|o| self.output_named(o))
+                    .unwrap_or_else(|| output.clone());
+                self.ensure_workspace(name, &target);
+                self.space.unmap_elem(&window);
+                if let Some(workspace) = self.workspaces.get_mut(name) {
+                    workspace.hidden.push(HiddenWindow {
+                        window,
+                        location,
+                        fullscreen,
+                    });
+                }
+            }
+        }
+
+        self.arrange_all();
+        self.focus_output(&output);
+        self.ipc_shm_update_workspaces();
+    }
+
+    /// Move the focused window to the workspace at `index` of the configured names
+    pub fn move_window_to_workspace_index(&mut self, index: usize) {
+        let name = self.config.workspaces.name_for(index);
+        self.move_window_to_workspace(&name);
+    }
+
+    /// Move the workspace shown on the current output to the next output,
+    /// where it replaces the workspace shown there
+    pub fn move_workspace_to_next_output(&mut self) {
+        let Some(output) = self.current_output() else {
+            return;
+        };
+        let outputs: Vec<Output> = self.space.outputs().cloned().collect();
+        let index = outputs.iter().position(|o| *o == output).unwrap_or_default();
+        let target = outputs[(index + 1) % outputs.len()].clone();
+        if target == output {
+            return;
+        }
+        let name = self.active_workspace(&output);
+        self.move_workspace_to_output(&name, &target);
+    }
+
+    /// Move the workspace called `name` to `target`, showing it there
+    pub fn move_workspace_to_output(&mut self, name: &str, target: &Output) {
+        let Some(workspace) = self.workspaces.get(name) else {
+            return;
+        };
+        let Some(source) = self.output_named(&workspace.output) else {
+            // the output of the workspace is gone, its windows are all hidden
+            let target_previous = self.active_workspace(target);
+            self.hide_workspace(target, &target_previous);
+            self.show_workspace(target, name);
+            self.arrange_all();
+            self.ipc_shm_update_workspaces();
+            return;
+        };
+        if source == *target {
+            return;
+        }
+
+        let shown = self.workspaces.shown_on(name).is_some();
+        let target_previous = self.active_workspace(target);
+        self.hide_workspace(target, &target_previous);
+
+        if shown {
+            // carry the windows over, keeping their place relative to the output
+            let offset = self.output_origin(target) - self.output_origin(&source);
+            for window in self.windows_on_output(&source) {
+                if window.is_sticky() {
+                    continue;
+                }
+                let location = self.space.element_location(&window).unwrap_or_default();
+                self.space.map_element(window, location + offset, false);
+            }
+            let fullscreen = source
+                .user_data()
+                .get::<FullscreenSurface>()
+                .filter(|f| f.get().is_some_and(|w| !w.is_sticky()))
+                .and_then(|f| f.clear());
+            if let Some(window) = fullscreen {
+                target.user_data().insert_if_missing(FullscreenSurface::default);
+                target.user_data().get::<FullscreenSurface>().unwrap().set(window);
+            }
+            self.workspaces.active.retain(|(o, _)| *o != source.name());
+        }
+        self.show_workspace(target, name);
+
+        if shown {
+            let replacement = self.active_workspace(&source);
+            self.show_workspace(&source, &replacement);
+        }
+        self.arrange_all();
+        if shown {
+            self.focus_output(&source);
+        }
+        self.ipc_shm_update_workspaces();
+    }
+
+    /// Move the workspace called `name` to the output called `output_name`
+    pub fn move_workspace_to_output_named(&mut self, name: &str, output_name: &str) {
+        if let Some(output) = self.output_named(output_name) {
+            self.move_workspace_to_output(name, &output);
+        }
+    }
+}
@@ -20,19 +20,32 @@ use smithay::{
         xwayland_shell::{XWaylandShellHandler, XWaylandShellState},
     },
     xwayland::{
-        xwm::{Reorder, ResizeEdge as X11ResizeEdge, XwmId},
+        xwm::{Reorder, ResizeEdge as X11ResizeEdge, WmWindowProperty, XwmId},
         X11Surface, X11Wm, XwmHandler,
     },
 };
 use tracing::{error, trace};
 
-use crate::{core::focus::KeyboardFocusTarget, core::state::Backend, core::state::WayiceState};
+use crate::{
+    config::{Decorations, LayoutKind},
+    core::focus::KeyboardFocusTarget,
+    core::state::Backend,
+    core::state::WayiceState,
+};
 
 use super::{
-    place_new_window, FullscreenSurface, PointerMoveSurfaceGrab, PointerResizeSurfaceGrab, ResizeData,
-    ResizeState, SurfaceData, TouchMoveSurfaceGrab, WindowElement,
+    FullscreenSurface, PointerMoveSurfaceGrab, PointerResizeSurfaceGrab, ResizeData, ResizeState,
+    SurfaceData, TouchMoveSurfaceGrab, WindowElement,
 };
 
+/// Whether a new X11 window is put in fullscreen state.
+///
+/// X11 windows start out fullscreen unless a rule decides, but not when a
+/// tiling layout arranges them, as fullscreen windows are left out of it.
+fn starts_fullscreen(rule: Option<bool>, layout: LayoutKind, floating: bool) -> bool {
+    rule.is_none() && (layout == LayoutKind::Floating || floating)
+}
+
 #[derive(Debug, Default)]
 struct OldGeometry(RefCell<Option<Rectangle<i32, Logical>>>);
 impl OldGeometry {
@@ -62,14 +75,22 @@ impl<BackendData: Backend> XwmHandler for WayiceState<BackendData> {
     fn map_window_request(&mut self, _xwm: XwmId, window: X11Surface) {
         window.set_mapped(true).unwrap();
         let window = WindowElement(Window::new_x11_window(window));
-        place_new_window(&mut self.space, self.pointer.current_location(), &window, true);
+        self.place_new_window(&window);
         let bbox = self.space.element_bbox(&window).unwrap();
         let Some(xsurface) = window.0.x11_surface() else {
             unreachable!()
         };
-        xsurface.set_fullscreen(true).unwrap();
         //xsurface.configure(Some(bbox)).unwrap();
         window.set_ssd(!xsurface.is_decorated());
+        window.set_decoration_theme(&self.config.decorations);
+        self.apply_window_rules(&window);
+        let layout = self
+            .window_output(&window)
+            .map_or(self.config.tiling.layout, |output| self.output_layout(&output));
+        if starts_fullscreen(window.rule_effects().fullscreen, layout, window.is_floating()) {
+            xsurface.set_fullscreen(true).unwrap();
+        }
+        self.arrange_all();
     }
 
     fn mapped_override_redirect_window(&mut self, _xwm: XwmId, window: X11Surface) {
@@ -79,6 +100,8 @@ impl<BackendData: Backend> XwmHandler for WayiceState<BackendData> {
     }
 
     fn unmapped_window(&mut self, _xwm: XwmId, window: X11Surface) {
+        // the next tab of its group takes the place of the window
+        self.forget_grouped(|e| matches!(e.0.x11_surface(), Some(w) if w == &window));
         let maybe = self
             .space
             .elements()
@@ -87,9 +110,15 @@ impl<BackendData: Backend> XwmHandler for WayiceState<BackendData> {
         if let Some(elem) = maybe {
             self.space.unmap_elem(&elem)
         }
+        self.workspaces
+            .forget_windows(|e| matches!(e.0.x11_surface(), Some(w) if w == &window));
+        self.forget_minimized(|e| matches!(e.0.x11_surface(), Some(w) if w == &window));
+        self.scratchpad
+            .retain(|e| !matches!(e.0.x11_surface(), Some(w) if w == &window));
         if !window.is_override_redirect() {
             window.set_mapped(false).unwrap();
         }
+        self.arrange_all();
     }
 
     fn destroyed_window(&mut self, _xwm: XwmId, _window: X11Surface) {}
@@ -158,57 +187,55 @@ impl<BackendData: Backend> XwmHandler for WayiceState<BackendData> {
             window.configure(old_geo).unwrap();
             self.space.map_element(elem, old_geo.loc, false);
         }
+        self.arrange_all();
     }
 
-    fn fullscreen_request(&mut self, _xwm: XwmId, window: X11Surface) {
-        if let Some(elem) = self
+    fn minimize_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        let Some(elem) = self
             .space
             .elements()
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
-        {
-            let outputs_for_window = self.space.outputs_for_element(elem);
-            let output = outputs_for_window
-                .first()
-                // The window hasn't been mapped yet, use the primary output instead
-                .or_else(|| self.space.outputs().next())
-                // Assumes that at least one output exists
-                .expect("No outputs found");
-            let geometry = self.space.output_geometry(output).unwrap();
+            .cloned()
+        else {
+            return;
+        };
+        self.minimize_window(&elem);
+    }
 
-            window.set_fullscreen(true).unwrap();
-            elem.set_ssd(false);
-            window.configure(geometry).unwrap();
-            output.user_data().insert_if_missing(FullscreenSurface::default);
-            output
-                .user_data()
-                .get::<FullscreenSurface>()
-                .unwrap()
-                .set(elem.clone());
-            trace!("Fullscreening: {:?}", elem);
-        }
+    fn unminimize_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        let Some(elem) = self
+            .minimized
+            .iter()
+            .map(|m| &m.window)
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned()
+        else {
+            return;
+        };
+        self.restore_window(&elem);
     }
 
-    fn unfullscreen_request(&mut self, _xwm: XwmId, window: X11Surface) {
-        if let Some(elem) = self
+    fn property_notify(&mut self, _xwm: XwmId, window: X11Surface, property: WmWindowProperty) {
+        if !matches!(property, WmWindowProperty::Title | WmWindowProperty::Class) {
+            return;
+        }
+        let Some(elem) = self
             .space
             .elements()
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
-        {
-            window.set_fullscreen(false).unwrap();
-            elem.set_ssd(!window.is_decorated());
-            if let Some(output) = self.space.outputs().find(|o| {
-                o.user_data()
-                    .get::<FullscreenSurface>()
-                    .and_then(|f| f.get())
-                    .map(|w| &w == elem)
-                    .unwrap_or(false)
-            }) {
-                trace!("Unfullscreening: {:?}", elem);
-                output.user_data().get::<FullscreenSurface>().unwrap().clear();
-                window.configure(self.space.element_bbox(elem)).unwrap();
-                self.backend_data.reset_buffers(output);
-            }
-        }
+            .cloned()
+        else {
+            return;
+        };
+        self.refresh_window_rules(&elem);
+    }
+
+    fn fullscreen_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        self.fullscreen_request_x11(&window);
+    }
+
+    fn unfullscreen_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        self.unfullscreen_request_x11(&window);
     }
 
     fn resize_request(&mut self, _xwm: XwmId, window: X11Surface, _button: u32, edges: X11ResizeEdge) {
@@ -346,6 +373,61 @@ impl<BackendData: Backend> WayiceState<BackendData> {
         self.space.map_element(elem, geometry.loc, false);
     }
 
+    pub fn fullscreen_request_x11(&mut self, window: &X11Surface) {
+        if let Some(elem) = self
+            .space
+            .elements()
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == window))
+        {
+            let outputs_for_window = self.space.outputs_for_element(elem);
+            let output = outputs_for_window
+                .first()
+                // The window hasn't been mapped yet, use the primary output instead
+                .or_else(|| self.space.outputs().next())
+                // Assumes that at least one output exists
+                .expect("No outputs found");
+            let geometry = self.space.output_geometry(output).unwrap();
+
+            window.set_fullscreen(true).unwrap();
+            elem.set_ssd(false);
+            window.configure(geometry).unwrap();
+            output.user_data().insert_if_missing(FullscreenSurface::default);
+            output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .unwrap()
+                .set(elem.clone());
+            trace!("Fullscreening: {:?}", elem);
+        }
+    }
+
+    pub fn unfullscreen_request_x11(&mut self, window: &X11Surface) {
+        if let Some(elem) = self
+            .space
+            .elements()
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == window))
+        {
+            window.set_fullscreen(false).unwrap();
+            elem.set_ssd(match elem.forced_decorations() {
+                Some(decorations) => decorations == Decorations::Server,
+                None => !window.is_decorated(),
+            });
+            if let Some(output) = self.space.outputs().find(|o| {
+                o.user_data()
+                    .get::<FullscreenSurface>()
+                    .and_then(|f| f.get())
+                    .map(|w| &w == elem)
+                    .unwrap_or(false)
+            }) {
+                trace!("Unfullscreening: {:?}", elem);
+                output.user_data().get::<FullscreenSurface>().unwrap().clear();
+                window.configure(self.space.element_bbox(elem)).unwrap();
+                self.backend_data.reset_buffers(output);
+            }
+        }
+        self.arrange_all();
+    }
+
     pub fn move_request_x11(&mut self, window: &X11Surface) {
         if let Some(touch) = self.seat.get_touch() {
             if let Some(start_data) = touch.grab_start_data() {
@@ -432,3 +514,27 @@ impl<BackendData: Backend> WayiceState<BackendData> {
         pointer.set_grab(self, grab, SERIAL_COUNTER.next_serial(), Focus::Clear);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floating_layout_starts_fullscreen() {
+        assert!(starts_fullscreen(None, LayoutKind::Floating, false));
+    }
+
+    #[test]
+    fn tiled_layout_does_not_start_fullscreen() {
+        assert!(!starts_fullscreen(None, LayoutKind::MasterStack, false));
+        assert!(!starts_fullscreen(None, LayoutKind::Scrolling, false));
+        // floating windows, such as dialogs, are not tiled
+        assert!(starts_fullscreen(None, LayoutKind::Dwindle, true));
+    }
+
+    #[test]
+    fn rule_decides_fullscreen() {
+        assert!(!starts_fullscreen(Some(true), LayoutKind::Floating, false));
+        assert!(!starts_fullscreen(Some(false), LayoutKind::Floating, false));
+    }
+}
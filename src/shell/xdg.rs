@@ -34,8 +34,8 @@ use crate::{
 };
 
 use super::{
-    fullscreen_output_geometry, place_new_window, FullscreenSurface, PointerMoveSurfaceGrab,
-    PointerResizeSurfaceGrab, ResizeData, ResizeEdge, ResizeState, SurfaceData, WindowElement,
+    fullscreen_output_geometry, FullscreenSurface, PointerMoveSurfaceGrab, PointerResizeSurfaceGrab,
+    ResizeData, ResizeEdge, ResizeState, SurfaceData, WindowElement,
 };
 
 impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
@@ -48,7 +48,7 @@ impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
         // of a xdg_surface has to be sent during the commit if
         // the surface is not already configured
         let window = WindowElement(Window::new_wayland_window(surface.clone()));
-        place_new_window(&mut self.space, self.pointer.current_location(), &window, true);
+        self.place_new_window(&window);
 
         compositor::add_post_commit_hook(surface.wl_surface(), |state: &mut Self, _, surface| {
             handle_toplevel_commit(&mut state.space, surface);
@@ -58,6 +58,21 @@ impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
         self.ipc_shm_update_window_list();
     }
 
+    fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
+        // the next tab of its group takes the place of the window
+        self.forget_grouped(|w| w.0.toplevel() == Some(&surface));
+        self.workspaces
+            .forget_windows(|w| w.0.toplevel() == Some(&surface));
+        // unmap the window right away instead of on the next refresh of the
+        // space, so the remaining tiles can take over its place
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.space.unmap_elem(&window);
+            self.arrange_all();
+        }
+        self.forget_minimized(|w| w.0.toplevel() == Some(&surface));
+        self.scratchpad.retain(|w| w.0.toplevel() != Some(&surface));
+    }
+
     fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
         // Do not send a configure here, the initial configure
         // of a xdg_surface has to be sent during the commit if
@@ -344,6 +359,8 @@ impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
             }
         }
 
+        // a tiled window goes back to its tile
+        self.arrange_all();
         surface.send_pending_configure();
     }
 
@@ -381,6 +398,24 @@ impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
         }
     }
 
+    fn minimize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.minimize_window(&window);
+        }
+    }
+
+    fn title_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.refresh_window_rules(&window);
+        }
+    }
+
+    fn app_id_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.refresh_window_rules(&window);
+        }
+    }
+
     fn unmaximize_request(&mut self, surface: ToplevelSurface) {
         if !surface
             .current_state()
@@ -394,6 +429,7 @@ impl<BackendData: Backend> XdgShellHandler for WayiceState<BackendData> {
             state.states.unset(xdg_toplevel::State::Maximized);
             state.size = None;
         });
+        self.arrange_all();
         surface.send_pending_configure();
     }
 